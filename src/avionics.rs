@@ -1,6 +1,9 @@
 
 pub mod adc;
+pub mod ahrs;
+pub mod arinc429;
 pub mod autopilot;
+pub mod navigation;
 
 pub mod avionics {
 
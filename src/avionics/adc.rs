@@ -1,56 +1,157 @@
+pub mod atmosphere;
+pub mod filters;
+pub mod redundancy;
+pub mod trends;
+pub mod validity;
+pub mod wind;
+
+/// Above this delay between two samples the ADC filters, trends, wind and AHRS restart from the new sample (link lost, sim paused).
+pub const MAX_SAMPLE_PERIOD_S: f32 = 1.0;
 
 pub mod adc {
 
+    extern crate yaml_rust;
+
     use std::sync::Arc;
-    use std::sync::mpsc::Sender;
+    use std::time::Instant;
+    use yaml_rust::Yaml;
+
+    use crate::bus::{BusMessage, Publisher};
+    use crate::sensors::sensors::{SensorField, SensorsValues};
+    use crate::avionics::adc::atmosphere::AirData;
+    use crate::avionics::adc::filters::AdcFilters;
+    use crate::avionics::adc::trends::AdcTrends;
+    use crate::avionics::adc::validity::ValidityMonitor;
+    use crate::avionics::adc::wind::{WindEstimator, WindInputs};
+    use crate::bus::{AdcParameter, AdcValidity, GeoPosition, WindData};
+    use crate::clock;
+    use crate::{ sensors::SensorsProvider, bus::{AdcDataMessage, LinkState} };
 
-    use crate::bus::BusMessage;
-    use crate::sensors::sensors::SensorsValues;
-    
     /**
      * ADC for Air Data Computer
      * Provide centralized calculated values from différents Air sensors like pitot probe,
      * static probe, AOA probe, accelerometers, etc...
      * - IAS (Knots)
-     * - Altitude (Feets)
-     * - Mach number (mach)
+     * - TAS, EAS (Knots)
+     * - Altitude, pressure altitude, density altitude (Feets)
+     * - Static air temperature (°C)
+     * - IAS trend (Knots in 10 s), altitude trend (Feets in 6 s)
+     * - Mach number (mach), derived from the IAS
      * - AOA (deg)
      * - Vertical speed (feets/min)
      * - G Load factor (Gs)
+     * - Wind direction/speed, headwind and crosswind (Knots) when ground speed and track are provided
+     *
+     * Each parameter carries its status (valid, no data, stale, out of range) and sample timestamp.
      */
-
-    use crate::{ sensors::SensorsProvider, bus::{AdcDataMessage} };
-
     pub struct  Adc {
         pub sensors: Arc::<dyn SensorsProvider + Send + Sync>,
-        pub adc_tx_gui: Sender<BusMessage>,
-        pub adc_tx_ap: Sender<BusMessage>
+        pub publisher: Publisher,
+        pub link_state: Option<LinkState>,
+        pub filters: AdcFilters,
+        pub trends: AdcTrends,
+        pub validity: ValidityMonitor,
+        pub wind: WindEstimator,
+        pub last_sample_at: Option<Instant>
+    }
+
+    /// Air data computer run by the ADC thread : single ADC or redundant ADC channels with voting.
+    pub trait AirDataComputer {
+
+        /// Acquire the sensors and publish the ADC data.
+        fn read_sensors(&mut self);
     }
 
     impl Adc {
 
-        pub fn read_sensors(&self) {
+        /// ADC with the "adc" config section (filters, validity).
+        pub fn from(
+            sensors: Arc::<dyn SensorsProvider + Send + Sync>,
+            publisher: Publisher,
+            adc_config: &Yaml) -> Self {
 
-            match self.sensors.acquire() {
-                Ok(s_values) => {
+            Self {
+                sensors,
+                publisher,
+                link_state: None,
+                filters: AdcFilters::from(&adc_config["filters"]),
+                trends: AdcTrends::new(),
+                validity: ValidityMonitor::from(&adc_config["validity"]),
+                wind: WindEstimator::new(),
+                last_sample_at: None
+            }
+        }
+
+        /// Acquire the sensors and compute the ADC data, None when no sample is available.
+        pub fn compute(&mut self) -> Option<AdcDataMessage> {
 
-                    let adc_registry: AdcRegistry = self.apply_sensors_values(s_values);
+            match self.sensors.acquire() {
+                Ok(s_sample) => {
 
-                    let adc_data:AdcDataMessage = adc_registry.to_adc_data();
-                    let gui_bus_message: BusMessage = BusMessage::AdcData(adc_data);
-                    let ap_bus_message: BusMessage = gui_bus_message.clone();
-        
-                    //println!("[ADC] sending data...");
-                    self.adc_tx_gui.send(gui_bus_message).unwrap();
-                    self.adc_tx_ap.send(ap_bus_message).unwrap();
+                    let adc_registry: AdcRegistry = self.apply_sensors_values(s_sample.values);
 
+                    Some(adc_registry.to_adc_data())
                 },
-                Err(e) => println!("Acquire error : {:?}", e)
+                Err(e) => {
+                    // Link timeouts are reported by the link state, only log unexpected errors.
+                    if self.link_state == Some(LinkState::Receiving) {
+                        println!("Acquire error : {:?}", e)
+                    }
+
+                    None
+                }
             }
+        }
+
+        pub fn sensors_link_state(&self) -> LinkState {
+
+            self.sensors.link_state()
+        }
+
+        /// Publish the provider link state when it changes.
+        fn publish_link_state(&mut self) {
+
+            let link_state: LinkState = self.sensors.link_state();
 
+            if self.link_state != Some(link_state) {
+
+                self.link_state = Some(link_state);
+
+                self.publisher.publish(BusMessage::LinkState(link_state));
+            }
         }
 
-        fn apply_sensors_values(&self, s_values: SensorsValues) -> AdcRegistry {
+        fn apply_sensors_values(&mut self, s_values: SensorsValues) -> AdcRegistry {
+
+            // Validity checked on the raw samples.
+            let mut validity: AdcValidity = self.validity.check(&s_values, clock::now_ms());
+
+            // Filter the raw samples before any computation (spikes, jittery VS/AOA).
+            let now: Instant = Instant::now();
+            let dt: f32 = match self.last_sample_at {
+                Some(last_sample_at) => now.duration_since(last_sample_at).as_secs_f32(),
+                None => 0f32
+            };
+            self.last_sample_at = Some(now);
+
+            let s_values: SensorsValues = self.filters.apply(s_values, dt);
+
+            let air_data: AirData = AirData::compute(s_values.ias, s_values.alt_msl, s_values.static_pressure, s_values.oat);
+
+            // Published Mach derived from the IAS, not the sensor Mach : validity of the IAS it comes from.
+            validity.set(AdcParameter::Mach, validity.get(AdcParameter::Ias));
+            let (ias_trend, alt_trend) = self.trends.update(
+                        Some(s_values.ias).filter(|_| validity.is_valid(AdcParameter::Ias)),
+                        Some(s_values.alt_msl).filter(|_| validity.is_valid(AdcParameter::AltMsl)),
+                        dt);
+
+            // Wind from the true heading (yaw), ground speed and track.
+            let wind_inputs: Option<WindInputs> = match (s_values.ground_speed, s_values.track) {
+                (Some(ground_speed), Some(track)) if validity.is_valid(AdcParameter::Ias) && s_values.received_at(SensorField::Yaw).is_some() =>
+                    Some(WindInputs{ tas: air_data.tas, true_heading: s_values.yaw, ground_speed, track }),
+                _ => None
+            };
+            let wind: Option<WindData> = self.wind.update(wind_inputs, dt);
 
             AdcRegistry{
                 ias: s_values.ias,
@@ -59,15 +160,43 @@ pub mod adc {
                 heading: s_values.heading,
                 vs: s_values.vs,
                 aoa: s_values.aoa,
-                mach: s_values.mach,
+                mach: air_data.mach,
                 g_load: s_values.g_load,
                 pitch: s_values.pitch,
-                roll: s_values.roll
+                roll: s_values.roll,
+                tas: air_data.tas,
+                eas: air_data.eas,
+                pressure_alt: air_data.pressure_alt,
+                density_alt: air_data.density_alt,
+                sat: air_data.sat,
+                ias_trend,
+                alt_trend,
+                ground_speed: s_values.ground_speed,
+                track: s_values.track,
+                position: s_values.position,
+                wind,
+                validity
             }
         }
 
     }
 
+    impl AirDataComputer for Adc {
+
+        fn read_sensors(&mut self) {
+
+            let adc_data: Option<AdcDataMessage> = self.compute();
+
+            self.publish_link_state();
+
+            if let Some(adc_data) = adc_data {
+
+                //println!("[ADC] sending data...");
+                self.publisher.publish(BusMessage::AdcData(Box::new(adc_data)));
+            }
+        }
+    }
+
     #[derive(Clone)]
     pub struct AdcRegistry {
 
@@ -80,7 +209,19 @@ pub mod adc {
         mach: f32,
         g_load: f32,
         pitch: f32,
-        roll: f32
+        roll: f32,
+        tas: f32,
+        eas: f32,
+        pressure_alt: f32,
+        density_alt: f32,
+        sat: f32,
+        ias_trend: f32,
+        alt_trend: f32,
+        ground_speed: Option<f32>,
+        track: Option<f32>,
+        position: Option<GeoPosition>,
+        wind: Option<WindData>,
+        validity: AdcValidity
     }
 
     impl AdcRegistry {
@@ -97,7 +238,19 @@ pub mod adc {
                 mach: 0f32,
                 g_load: 0f32,
                 pitch: 0f32,
-                roll: 0f32
+                roll: 0f32,
+                tas: 0f32,
+                eas: 0f32,
+                pressure_alt: 0f32,
+                density_alt: 0f32,
+                sat: 0f32,
+                ias_trend: 0f32,
+                alt_trend: 0f32,
+                ground_speed: None,
+                track: None,
+                position: None,
+                wind: None,
+                validity: AdcValidity::new()
             };
         }
 
@@ -113,7 +266,19 @@ pub mod adc {
                 mach: self.mach, 
                 g_load: self.g_load, 
                 pitch_angle: self.pitch, 
-                roll_angle: self.roll
+                roll_angle: self.roll,
+                tas: self.tas,
+                eas: self.eas,
+                pressure_alt: self.pressure_alt,
+                density_alt: self.density_alt,
+                sat: self.sat,
+                ias_trend: self.ias_trend,
+                alt_trend: self.alt_trend,
+                ground_speed: self.ground_speed,
+                track: self.track,
+                position: self.position,
+                wind: self.wind,
+                validity: self.validity
             };
         }
     }
@@ -0,0 +1,258 @@
+/*!
+ * ISA (International Standard Atmosphere) model
+ * Conversions used by the ADC (troposphere and lower stratosphere, up to 65 617 ft), subsonic compressible flow :
+ * - Pressure altitude (feets) <-> static pressure (hPa)
+ * - Density altitude (feets)
+ * - IAS/CAS (knots) <-> Mach number <-> TAS/EAS (knots)
+ * - Static air temperature (°C)
+ */
+
+use crate::units::FT_TO_M;
+
+/// Sea level standard temperature (K).
+pub const ISA_T0: f32 = 288.15;
+
+/// Sea level standard pressure (hPa).
+pub const ISA_P0: f32 = 1013.25;
+
+/// Sea level speed of sound (knots).
+pub const ISA_A0: f32 = 661.4788;
+
+/// Temperature lapse rate in the troposphere (K/m).
+const ISA_LAPSE_RATE: f32 = 0.0065;
+
+/// Tropopause altitude (m), temperature (K) and pressure (hPa).
+const ISA_TROPOPAUSE_ALT: f32 = 11_000.0;
+const ISA_TROPOPAUSE_T: f32 = 216.65;
+const ISA_TROPOPAUSE_P: f32 = 226.3206;
+
+/// g0 / (R * L), pressure exponent in the troposphere.
+const ISA_PRESSURE_EXPONENT: f32 = 5.255_88;
+
+/// g0 / (R * T tropopause), pressure decay in the stratosphere (1/m).
+const ISA_STRATOSPHERE_DECAY: f32 = 1.576_885e-4;
+
+const KELVIN: f32 = 273.15;
+
+/// ISA temperature (°C) at a pressure altitude (feets).
+pub fn isa_temperature(pressure_alt_ft: f32) -> f32 {
+
+    let h: f32 = pressure_alt_ft * FT_TO_M;
+
+    if h < ISA_TROPOPAUSE_ALT {
+        ISA_T0 - ISA_LAPSE_RATE * h - KELVIN
+    } else {
+        ISA_TROPOPAUSE_T - KELVIN
+    }
+}
+
+/// ISA static pressure (hPa) at a pressure altitude (feets).
+pub fn isa_pressure(pressure_alt_ft: f32) -> f32 {
+
+    let h: f32 = pressure_alt_ft * FT_TO_M;
+
+    if h < ISA_TROPOPAUSE_ALT {
+        ISA_P0 * (1.0 - ISA_LAPSE_RATE * h / ISA_T0).powf(ISA_PRESSURE_EXPONENT)
+    } else {
+        ISA_TROPOPAUSE_P * (-ISA_STRATOSPHERE_DECAY * (h - ISA_TROPOPAUSE_ALT)).exp()
+    }
+}
+
+/// Pressure altitude (feets) from static pressure (hPa).
+pub fn pressure_altitude(static_pressure_hpa: f32) -> f32 {
+
+    let h: f32 = if static_pressure_hpa > ISA_TROPOPAUSE_P {
+        (ISA_T0 / ISA_LAPSE_RATE) * (1.0 - (static_pressure_hpa / ISA_P0).powf(1.0 / ISA_PRESSURE_EXPONENT))
+    } else {
+        ISA_TROPOPAUSE_ALT - (static_pressure_hpa / ISA_TROPOPAUSE_P).ln() / ISA_STRATOSPHERE_DECAY
+    };
+
+    h / FT_TO_M
+}
+
+/// Air density ratio (sigma = rho / rho0) from static pressure (hPa) and static air temperature (°C).
+pub fn density_ratio(static_pressure_hpa: f32, sat_c: f32) -> f32 {
+
+    (static_pressure_hpa / ISA_P0) * (ISA_T0 / (sat_c + KELVIN))
+}
+
+/// Density altitude (feets) : ISA altitude with the same air density.
+pub fn density_altitude(static_pressure_hpa: f32, sat_c: f32) -> f32 {
+
+    let sigma: f32 = density_ratio(static_pressure_hpa, sat_c);
+    let sigma_tropopause: f32 = density_ratio(ISA_TROPOPAUSE_P, ISA_TROPOPAUSE_T - KELVIN);
+
+    let h: f32 = if sigma > sigma_tropopause {
+        (ISA_T0 / ISA_LAPSE_RATE) * (1.0 - sigma.powf(1.0 / (ISA_PRESSURE_EXPONENT - 1.0)))
+    } else {
+        ISA_TROPOPAUSE_ALT - (sigma / sigma_tropopause).ln() / ISA_STRATOSPHERE_DECAY
+    };
+
+    h / FT_TO_M
+}
+
+/// Mach number from calibrated airspeed (knots) and static pressure (hPa).
+pub fn mach_from_cas(cas_kt: f32, static_pressure_hpa: f32) -> f32 {
+
+    // Impact pressure from CAS (sea level standard conditions).
+    let qc: f32 = ISA_P0 * ((1.0 + 0.2 * (cas_kt / ISA_A0).powi(2)).powf(3.5) - 1.0);
+
+    (5.0 * ((qc / static_pressure_hpa + 1.0).powf(2.0 / 7.0) - 1.0)).max(0.0).sqrt()
+}
+
+/// Calibrated airspeed (knots) from Mach number and static pressure (hPa).
+pub fn cas_from_mach(mach: f32, static_pressure_hpa: f32) -> f32 {
+
+    // Impact pressure from Mach number.
+    let qc: f32 = static_pressure_hpa * ((1.0 + 0.2 * mach.powi(2)).powf(3.5) - 1.0);
+
+    ISA_A0 * (5.0 * ((qc / ISA_P0 + 1.0).powf(2.0 / 7.0) - 1.0)).max(0.0).sqrt()
+}
+
+/// Speed of sound (knots) at a static air temperature (°C).
+pub fn speed_of_sound(sat_c: f32) -> f32 {
+
+    ISA_A0 * ((sat_c + KELVIN) / ISA_T0).sqrt()
+}
+
+/// True airspeed (knots) from Mach number and static air temperature (°C).
+pub fn tas_from_mach(mach: f32, sat_c: f32) -> f32 {
+
+    mach * speed_of_sound(sat_c)
+}
+
+/// Mach number from true airspeed (knots) and static air temperature (°C).
+pub fn mach_from_tas(tas_kt: f32, sat_c: f32) -> f32 {
+
+    tas_kt / speed_of_sound(sat_c)
+}
+
+/// Equivalent airspeed (knots) from Mach number and static pressure (hPa).
+pub fn eas_from_mach(mach: f32, static_pressure_hpa: f32) -> f32 {
+
+    ISA_A0 * mach * (static_pressure_hpa / ISA_P0).sqrt()
+}
+
+/// True airspeed (knots) from calibrated airspeed (knots), static pressure (hPa) and static air temperature (°C).
+pub fn tas_from_cas(cas_kt: f32, static_pressure_hpa: f32, sat_c: f32) -> f32 {
+
+    tas_from_mach(mach_from_cas(cas_kt, static_pressure_hpa), sat_c)
+}
+
+/// Calibrated airspeed (knots) from true airspeed (knots), static pressure (hPa) and static air temperature (°C).
+pub fn cas_from_tas(tas_kt: f32, static_pressure_hpa: f32, sat_c: f32) -> f32 {
+
+    cas_from_mach(mach_from_tas(tas_kt, sat_c), static_pressure_hpa)
+}
+
+/// Air data derived from IAS and the static pressure/temperature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AirData {
+
+    pub mach: f32,
+    pub tas: f32,
+    pub eas: f32,
+    pub pressure_alt: f32,
+    pub density_alt: f32,
+    pub sat: f32,
+}
+
+impl AirData {
+
+    /**
+     * Compute air data from IAS (knots, used as CAS), the sim static pressure (hPa) and OAT (°C) when available.
+     * Without static pressure the altitude (feets) is used as pressure altitude, without OAT the ISA temperature is used.
+     */
+    pub fn compute(ias: f32, altitude_ft: f32, static_pressure_hpa: Option<f32>, oat_c: Option<f32>) -> AirData {
+
+        let static_pressure: f32 = static_pressure_hpa.unwrap_or(isa_pressure(altitude_ft));
+        let pressure_alt: f32 = pressure_altitude(static_pressure);
+        let sat: f32 = oat_c.unwrap_or(isa_temperature(pressure_alt));
+        let mach: f32 = mach_from_cas(ias.max(0.0), static_pressure);
+
+        AirData {
+            mach,
+            tas: tas_from_mach(mach, sat),
+            eas: eas_from_mach(mach, static_pressure),
+            pressure_alt,
+            density_alt: density_altitude(static_pressure, sat),
+            sat,
+        }
+    }
+}
+
+#[cfg(test)]
+fn assert_near(expected: f32, value: f32, tolerance: f32) {
+
+    assert!((expected - value).abs() <= tolerance, "expected {} +/- {}, got {}", expected, tolerance, value);
+}
+
+#[test]
+fn isa_standard_table() {
+
+    // Altitude (ft), temperature (°C), pressure (hPa)
+    let table: [(f32, f32, f32); 7] = [
+        (0.0, 15.0, 1013.25),
+        (5_000.0, 5.09, 843.07),
+        (10_000.0, -4.81, 696.82),
+        (20_000.0, -24.62, 465.63),
+        (30_000.0, -44.44, 300.90),
+        (36_089.0, -56.5, 226.32),
+        (40_000.0, -56.5, 187.54),
+    ];
+
+    for (alt, temperature, pressure) in table {
+
+        assert_near(temperature, isa_temperature(alt), 0.05);
+        assert_near(pressure, isa_pressure(alt), 0.1);
+        assert_near(alt, pressure_altitude(pressure), 5.0);
+    }
+}
+
+#[test]
+fn isa_density_altitude() {
+
+    // ISA day : density altitude = pressure altitude.
+    assert_near(0.0, density_altitude(ISA_P0, 15.0), 1.0);
+    assert_near(10_000.0, density_altitude(isa_pressure(10_000.0), isa_temperature(10_000.0)), 5.0);
+    assert_near(40_000.0, density_altitude(isa_pressure(40_000.0), isa_temperature(40_000.0)), 5.0);
+
+    // Sea level ISA+20 (35°C) : about 2 300 ft, 5 000 ft ISA+15 (20°C) : about 6 800 ft.
+    assert_near(2_300.0, density_altitude(ISA_P0, 35.0), 50.0);
+    assert_near(6_800.0, density_altitude(isa_pressure(5_000.0), 20.1), 100.0);
+}
+
+#[test]
+fn isa_airspeeds() {
+
+    // Sea level ISA : CAS = EAS = TAS.
+    let air_data: AirData = AirData::compute(250.0, 0.0, None, None);
+    assert_near(250.0, air_data.tas, 0.1);
+    assert_near(250.0, air_data.eas, 0.1);
+    assert_near(0.378, air_data.mach, 0.001);
+
+    // FL100 ISA, 250 KCAS : M 0.452, TAS 289 kt, EAS 248 kt.
+    let air_data: AirData = AirData::compute(250.0, 10_000.0, None, None);
+    assert_near(0.452, air_data.mach, 0.001);
+    assert_near(288.7, air_data.tas, 0.5);
+    assert_near(248.1, air_data.eas, 0.5);
+    assert_near(10_000.0, air_data.pressure_alt, 5.0);
+    assert_near(-4.81, air_data.sat, 0.05);
+
+    // FL350 ISA, 280 KCAS : M 0.82, TAS 473 kt.
+    let air_data: AirData = AirData::compute(280.0, 35_000.0, None, None);
+    assert_near(0.821, air_data.mach, 0.002);
+    assert_near(473.4, air_data.tas, 1.0);
+
+    // Reverse conversions.
+    assert_near(280.0, cas_from_mach(air_data.mach, isa_pressure(35_000.0)), 0.1);
+    assert_near(280.0, cas_from_tas(air_data.tas, isa_pressure(35_000.0), air_data.sat), 0.1);
+    assert_near(air_data.mach, mach_from_tas(air_data.tas, air_data.sat), 0.001);
+    assert_near(air_data.tas, tas_from_cas(280.0, isa_pressure(35_000.0), air_data.sat), 0.1);
+
+    // Sim static pressure and OAT used when available (ISA+20 at FL100 : higher TAS).
+    let air_data: AirData = AirData::compute(250.0, 0.0, Some(696.82), Some(15.19));
+    assert_near(10_000.0, air_data.pressure_alt, 5.0);
+    assert_near(299.3, air_data.tas, 0.5);
+    assert!(air_data.density_alt > 12_000.0);
+}
@@ -0,0 +1,344 @@
+/**
+ * ADC sensors filters
+ * Applied between the sensors acquisition and the ADC registry, configured per channel in the "adc.filters" section :
+ * - Median spike rejection (median of the last samples, "median_window")
+ * - First order low-pass ("cutoff_hz")
+ * - Complementary vertical speed : baro VS blended with the integrated vertical acceleration ("complementary_tau_s")
+ */
+extern crate yaml_rust;
+
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use yaml_rust::Yaml;
+
+use crate::avionics::adc::MAX_SAMPLE_PERIOD_S;
+use crate::config::get_positive_f32;
+use crate::sensors::sensors::{SensorField, SensorsValues};
+
+/// Standard gravity (ft/s²).
+const GRAVITY_FT_S2: f32 = 32.174;
+
+/// First order low-pass filter.
+pub struct LowPassFilter {
+    time_constant: f32,
+    value: Option<f32>,
+}
+
+impl LowPassFilter {
+
+    pub fn new(cutoff_hz: f32) -> LowPassFilter {
+
+        LowPassFilter {
+            time_constant: 1.0 / (2.0 * PI * cutoff_hz),
+            value: None,
+        }
+    }
+
+    pub fn update(&mut self, input: f32, dt: f32) -> f32 {
+
+        let value: f32 = match self.value {
+            Some(value) => {
+                let alpha: f32 = dt / (self.time_constant + dt);
+                value + alpha * (input - value)
+            },
+            None => input
+        };
+
+        self.value = Some(value);
+
+        value
+    }
+
+    pub fn reset(&mut self) {
+
+        self.value = None;
+    }
+}
+
+/// Median filter, a single spike in the window never reaches the output.
+pub struct MedianFilter {
+    window: usize,
+    samples: VecDeque<f32>,
+}
+
+impl MedianFilter {
+
+    pub fn new(window: usize) -> MedianFilter {
+
+        MedianFilter {
+            window: window.max(1),
+            samples: VecDeque::with_capacity(window.max(1)),
+        }
+    }
+
+    pub fn update(&mut self, input: f32) -> f32 {
+
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(input);
+
+        let mut sorted: Vec<f32> = self.samples.iter().copied().collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        sorted[sorted.len() / 2]
+    }
+
+    pub fn reset(&mut self) {
+
+        self.samples.clear();
+    }
+}
+
+/**
+ * Complementary vertical speed filter
+ * The vertical acceleration (load factor projected on the vertical axis) is integrated for the short term,
+ * the baro VS corrects the long term drift. Output in feets/min.
+ */
+pub struct ComplementaryVsFilter {
+    time_constant: f32,
+    vs: Option<f32>,
+}
+
+impl ComplementaryVsFilter {
+
+    pub fn new(time_constant: f32) -> ComplementaryVsFilter {
+
+        ComplementaryVsFilter {
+            time_constant,
+            vs: None,
+        }
+    }
+
+    /// Baro VS (feets/min), normal load factor (Gs), pitch and roll (deg).
+    pub fn update(&mut self, baro_vs: f32, g_load: f32, pitch: f32, roll: f32, dt: f32) -> f32 {
+
+        let vs: f32 = match self.vs {
+            Some(vs) => {
+                let vertical_accel: f32 = (g_load * pitch.to_radians().cos() * roll.to_radians().cos() - 1.0) * GRAVITY_FT_S2;
+                let alpha: f32 = self.time_constant / (self.time_constant + dt);
+                alpha * (vs + vertical_accel * dt * 60.0) + (1.0 - alpha) * baro_vs
+            },
+            None => baro_vs
+        };
+
+        self.vs = Some(vs);
+
+        vs
+    }
+
+    pub fn reset(&mut self) {
+
+        self.vs = None;
+    }
+}
+
+/// Filters of one ADC channel : median spike rejection then low-pass, each optional.
+pub struct ChannelFilter {
+    median: Option<MedianFilter>,
+    low_pass: Option<LowPassFilter>,
+}
+
+impl ChannelFilter {
+
+    pub fn update(&mut self, input: f32, dt: f32) -> f32 {
+
+        let mut value: f32 = input;
+
+        if let Some(median) = self.median.as_mut() {
+            value = median.update(value);
+        }
+
+        if let Some(low_pass) = self.low_pass.as_mut() {
+            value = low_pass.update(value, dt);
+        }
+
+        value
+    }
+
+    /// Filter a received value, fields not received (no data) are passed through and restart the filter.
+    pub fn update_received(&mut self, input: f32, received_at: Option<u64>, dt: f32) -> f32 {
+
+        match received_at {
+            Some(_) => self.update(input, dt),
+            None => {
+                self.reset();
+                input
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+
+        if let Some(median) = self.median.as_mut() {
+            median.reset();
+        }
+
+        if let Some(low_pass) = self.low_pass.as_mut() {
+            low_pass.reset();
+        }
+    }
+}
+
+impl From<&Yaml> for ChannelFilter {
+
+    /// "median_window" (samples) and "cutoff_hz" (Hz), both optional.
+    fn from(config: &Yaml) -> Self {
+
+        ChannelFilter {
+            median: config["median_window"].as_i64().filter(|w| *w > 1).map(|w| MedianFilter::new(w as usize)),
+            low_pass: get_positive_f32(&config["cutoff_hz"]).map(LowPassFilter::new),
+        }
+    }
+}
+
+/// Filters of the ADC channels, channels without configuration are passed through.
+pub struct AdcFilters {
+    ias: ChannelFilter,
+    alt_msl: ChannelFilter,
+    alt_agl: ChannelFilter,
+    vs: ChannelFilter,
+    vs_complementary: Option<ComplementaryVsFilter>,
+    aoa: ChannelFilter,
+    g_load: ChannelFilter,
+    pitch: ChannelFilter,
+    roll: ChannelFilter,
+}
+
+impl AdcFilters {
+
+    /// Filter the sensors values, `dt` is the delay (s) since the previous sample.
+    pub fn apply(&mut self, s_values: SensorsValues, dt: f32) -> SensorsValues {
+
+        if dt <= 0.0 || dt > MAX_SAMPLE_PERIOD_S {
+            self.reset();
+        }
+
+        let g_load: f32 = self.g_load.update_received(s_values.g_load, s_values.received_at(SensorField::GLoad), dt);
+        let pitch: f32 = self.pitch.update_received(s_values.pitch, s_values.received_at(SensorField::Pitch), dt);
+        let roll: f32 = self.roll.update_received(s_values.roll, s_values.received_at(SensorField::Roll), dt);
+
+        let mut vs: f32 = self.vs.update_received(s_values.vs, s_values.received_at(SensorField::Vs), dt);
+        if let Some(vs_complementary) = self.vs_complementary.as_mut() {
+            if s_values.received_at(SensorField::Vs).is_some() {
+                vs = vs_complementary.update(vs, g_load, pitch, roll, dt);
+            } else {
+                vs_complementary.reset();
+            }
+        }
+
+        SensorsValues {
+            ias: self.ias.update_received(s_values.ias, s_values.received_at(SensorField::Ias), dt),
+            alt_msl: self.alt_msl.update_received(s_values.alt_msl, s_values.received_at(SensorField::AltMsl), dt),
+            alt_agl: self.alt_agl.update_received(s_values.alt_agl, s_values.received_at(SensorField::AltAgl), dt),
+            vs,
+            aoa: self.aoa.update_received(s_values.aoa, s_values.received_at(SensorField::Aoa), dt),
+            g_load,
+            pitch,
+            roll,
+            ..s_values
+        }
+    }
+
+    pub fn reset(&mut self) {
+
+        self.ias.reset();
+        self.alt_msl.reset();
+        self.alt_agl.reset();
+        self.vs.reset();
+        if let Some(vs_complementary) = self.vs_complementary.as_mut() {
+            vs_complementary.reset();
+        }
+        self.aoa.reset();
+        self.g_load.reset();
+        self.pitch.reset();
+        self.roll.reset();
+    }
+}
+
+impl From<&Yaml> for AdcFilters {
+
+    /// "adc.filters" section, one optional sub section per channel.
+    fn from(config: &Yaml) -> Self {
+
+        AdcFilters {
+            ias: ChannelFilter::from(&config["ias"]),
+            alt_msl: ChannelFilter::from(&config["alt_msl"]),
+            alt_agl: ChannelFilter::from(&config["alt_agl"]),
+            vs: ChannelFilter::from(&config["vs"]),
+            vs_complementary: get_positive_f32(&config["vs"]["complementary_tau_s"]).map(ComplementaryVsFilter::new),
+            aoa: ChannelFilter::from(&config["aoa"]),
+            g_load: ChannelFilter::from(&config["g_load"]),
+            pitch: ChannelFilter::from(&config["pitch"]),
+            roll: ChannelFilter::from(&config["roll"]),
+        }
+    }
+}
+
+#[test]
+fn median_filter_rejects_spikes() {
+
+    let mut median: MedianFilter = MedianFilter::new(3);
+
+    let outputs: Vec<f32> = [10.0, 10.0, 90.0, 10.0, 11.0, -50.0, 12.0]
+                .iter()
+                .map(|v| median.update(*v))
+                .collect();
+
+    assert_eq!(vec![10.0, 10.0, 10.0, 10.0, 11.0, 10.0, 11.0], outputs);
+}
+
+#[test]
+fn low_pass_filter_step_response() {
+
+    let mut low_pass: LowPassFilter = LowPassFilter::new(1.0);
+    let dt: f32 = 0.05;
+
+    assert_eq!(0.0, low_pass.update(0.0, dt));
+
+    // After one time constant (1 / 2πfc) a step reaches about 63%.
+    let steps: usize = (1.0 / (2.0 * PI) / dt).round() as usize;
+    let mut value: f32 = 0.0;
+    for _ in 0..steps {
+        value = low_pass.update(100.0, dt);
+    }
+
+    assert!(value > 55.0 && value < 70.0, "value {}", value);
+
+    low_pass.reset();
+    assert_eq!(42.0, low_pass.update(42.0, dt));
+}
+
+#[test]
+fn complementary_vs_filter() {
+
+    let dt: f32 = 0.05;
+
+    // Noisy baro VS in level flight (1 G) : output jitter attenuated.
+    let mut filter: ComplementaryVsFilter = ComplementaryVsFilter::new(2.0);
+    let mut max_vs: f32 = 0.0;
+    for i in 0..200 {
+        let baro_vs: f32 = if i % 2 == 0 { 300.0 } else { -300.0 };
+        let vs: f32 = filter.update(baro_vs, 1.0, 0.0, 0.0, dt);
+        if i >= 100 {
+            max_vs = max_vs.max(vs.abs());
+        }
+    }
+    assert!(max_vs < 30.0, "max vs {}", max_vs);
+
+    // Pull up at 1.1 G : the VS follows the acceleration before the baro VS (lagging at 0).
+    let mut filter: ComplementaryVsFilter = ComplementaryVsFilter::new(2.0);
+    let mut vs: f32 = filter.update(0.0, 1.0, 0.0, 0.0, dt);
+    for _ in 0..20 {
+        vs = filter.update(0.0, 1.1, 0.0, 0.0, dt);
+    }
+    // 1 s at 0.1 G : about 193 ft/min without baro correction.
+    assert!(vs > 100.0 && vs < 193.0, "vs {}", vs);
+
+    // Steady climb : converges to the baro VS.
+    let mut filter: ComplementaryVsFilter = ComplementaryVsFilter::new(2.0);
+    for _ in 0..400 {
+        vs = filter.update(1500.0, 1.0, 0.0, 0.0, dt);
+    }
+    assert!((vs - 1500.0).abs() < 1.0, "vs {}", vs);
+}
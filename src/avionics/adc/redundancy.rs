@@ -0,0 +1,475 @@
+/**
+ * Redundant ADC
+ * Two or three ADC channels, each fed by its own sensors provider or by a failure-injected copy of the main sensors,
+ * computed every frame and voted ("adc.redundancy" section) :
+ * - Mid-value selection of each parameter (median of 3, mean of 2)
+ * - Miscompare : channel value farther than the parameter threshold from the voted value
+ * - Isolation : channel miscomparing for "isolation_frames" consecutive frames, excluded from the vote until restart
+ *
+ * With 2 channels a miscompare can't tell which channel failed : the parameter is flagged Miscompare, nothing is isolated.
+ * Channel failures : faults of the channel "fault_injection" section, see sensors::fault_injection.
+ */
+extern crate yaml_rust;
+
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use yaml_rust::Yaml;
+
+use crate::avionics::adc::adc::{Adc, AirDataComputer};
+use crate::avionics::adc::validity::sensor_field;
+use crate::avionics::ahrs::attitude::wrap_180;
+use crate::bus::{AdcChannelHealth, AdcDataMessage, AdcHealthMessage, AdcParameter, Bus, BusMessage, LinkState, ParamStatus, ParamValidity, Publisher, SourceId};
+use crate::bus::bus::ADC_PARAMETER_COUNT;
+use crate::config::get_f32;
+use crate::sensors::SensorsProvider;
+use crate::sensors::fault_injection::{self, parse_field, FaultSwitches};
+
+const DEFAULT_ISOLATION_FRAMES: u32 = 25;
+
+/// Default miscompare thresholds, in the AdcParameter order.
+const DEFAULT_MISCOMPARE_THRESHOLDS: [f32; ADC_PARAMETER_COUNT] = [
+    5.0,    // IAS (knots)
+    100.0,  // Altitude MSL (feets)
+    100.0,  // Altitude AGL (feets)
+    5.0,    // Heading (deg)
+    500.0,  // Vertical speed (feets/min)
+    2.0,    // AOA (deg)
+    0.02,   // Mach
+    0.3,    // G load (Gs)
+    3.0,    // Pitch (deg)
+    3.0];   // Roll (deg)
+
+pub struct VoterConfig {
+    miscompare_thresholds: [f32; ADC_PARAMETER_COUNT],
+    isolation_frames: u32,
+}
+
+impl From<&Yaml> for VoterConfig {
+
+    /// "adc.redundancy" section : "miscompare" thresholds per parameter and "isolation_frames".
+    fn from(config: &Yaml) -> Self {
+
+        let mut miscompare_thresholds: [f32; ADC_PARAMETER_COUNT] = DEFAULT_MISCOMPARE_THRESHOLDS;
+        for (key, threshold) in config["miscompare"].as_hash().into_iter().flatten() {
+
+            // Parameters named as in the fault injection scenarios.
+            let param: Option<AdcParameter> = key.as_str()
+                        .and_then(parse_field)
+                        .and_then(|field| AdcParameter::ALL.into_iter().find(|param| sensor_field(*param) == field));
+
+            if let (Some(param), Some(threshold)) = (param, get_f32(threshold)) {
+                miscompare_thresholds[param as usize] = threshold;
+            }
+        }
+
+        let isolation_frames: u32 = config["isolation_frames"].as_i64()
+                    .map(|v| v as u32)
+                    .unwrap_or(DEFAULT_ISOLATION_FRAMES);
+
+        VoterConfig { miscompare_thresholds, isolation_frames }
+    }
+}
+
+/// Mid-value selection voter with miscompare monitoring and isolation of the failed channels.
+pub struct AdcVoter {
+    config: VoterConfig,
+    miscompare_frames: Vec<u32>,
+    isolated: Vec<bool>,
+    health: Vec<AdcChannelHealth>,
+}
+
+impl AdcVoter {
+
+    pub fn new(channels: usize, config: VoterConfig) -> AdcVoter {
+
+        AdcVoter {
+            config,
+            miscompare_frames: vec![0; channels],
+            isolated: vec![false; channels],
+            health: vec![AdcChannelHealth::NoData; channels],
+        }
+    }
+
+    pub fn health(&self) -> AdcHealthMessage {
+
+        AdcHealthMessage { channels: self.health.clone() }
+    }
+
+    /// Vote the channels data (None : no data from the channel), None when no channel has data.
+    pub fn vote(&mut self, channels_data: &[Option<AdcDataMessage>]) -> Option<AdcDataMessage> {
+
+        let channels: usize = self.health.len();
+        let mut miscompare: Vec<bool> = vec![false; channels];
+        let mut identified: Vec<bool> = vec![false; channels];
+
+        let mut voted: AdcDataMessage = self.reference_channel(channels_data)?;
+
+        for param in AdcParameter::ALL {
+
+            // Valid values of the channels used by the vote
+            let candidates: Vec<(usize, f32, ParamValidity)> = channels_data.iter()
+                        .enumerate()
+                        .filter(|(i, _)| !self.isolated[*i])
+                        .filter_map(|(i, d)| d.as_ref().map(|d| (i, d)))
+                        .filter(|(_, d)| d.validity.is_valid(param))
+                        .map(|(i, d)| (i, d.get(param), d.validity.get(param)))
+                        .collect();
+
+            if candidates.is_empty() {
+                // Keep the reference channel value and status.
+                continue;
+            }
+
+            let voted_value: f32 = mid_value(param, &candidates.iter().map(|c| c.1).collect::<Vec<f32>>());
+            let threshold: f32 = self.config.miscompare_thresholds[param as usize];
+
+            let mut status: ParamStatus = ParamStatus::Valid;
+
+            for (i, value, _) in candidates.iter() {
+
+                if deviation(param, *value, voted_value) > threshold {
+
+                    miscompare[*i] = true;
+
+                    if candidates.len() >= 3 {
+                        identified[*i] = true;
+                    } else {
+                        status = ParamStatus::Miscompare;
+                    }
+                }
+            }
+
+            let timestamp_ms: Option<u64> = candidates.iter().filter_map(|c| c.2.timestamp_ms).max();
+
+            voted.set(param, voted_value);
+            voted.validity.set(param, ParamValidity { status, timestamp_ms });
+        }
+
+        self.update_health(channels_data, &miscompare, &identified);
+
+        Some(voted)
+    }
+
+    /// Data of the channel closest to the voted IAS, base of the derived parameters (TAS, trends, etc...).
+    fn reference_channel(&self, channels_data: &[Option<AdcDataMessage>]) -> Option<AdcDataMessage> {
+
+        let available: Vec<&AdcDataMessage> = channels_data.iter()
+                    .enumerate()
+                    .filter(|(i, _)| !self.isolated[*i])
+                    .filter_map(|(_, d)| d.as_ref())
+                    .collect();
+
+        let valid_ias: Vec<f32> = available.iter()
+                    .filter(|d| d.validity.is_valid(AdcParameter::Ias))
+                    .map(|d| d.ias)
+                    .collect();
+
+        if valid_ias.is_empty() {
+            return available.first().map(|d| (*d).clone());
+        }
+
+        let voted_ias: f32 = mid_value(AdcParameter::Ias, &valid_ias);
+
+        available.iter()
+                    .filter(|d| d.validity.is_valid(AdcParameter::Ias))
+                    .min_by(|a, b| (a.ias - voted_ias).abs().total_cmp(&(b.ias - voted_ias).abs()))
+                    .map(|d| (*d).clone())
+    }
+
+    fn update_health(&mut self, channels_data: &[Option<AdcDataMessage>], miscompare: &[bool], identified: &[bool]) {
+
+        for i in 0..self.health.len() {
+
+            if identified[i] {
+                self.miscompare_frames[i] += 1;
+            } else {
+                self.miscompare_frames[i] = 0;
+            }
+
+            if !self.isolated[i] && self.miscompare_frames[i] >= self.config.isolation_frames {
+                println!("[ADC] Channel {} isolated", i + 1);
+                self.isolated[i] = true;
+            }
+
+            self.health[i] = if self.isolated[i] {
+                AdcChannelHealth::Isolated
+            } else if channels_data[i].is_none() {
+                AdcChannelHealth::NoData
+            } else if miscompare[i] {
+                AdcChannelHealth::Miscompare
+            } else {
+                AdcChannelHealth::Ok
+            };
+        }
+    }
+}
+
+/// Median of 3 values, mean of 2 (headings unwrapped around the first value).
+fn mid_value(param: AdcParameter, values: &[f32]) -> f32 {
+
+    let reference: f32 = values[0];
+
+    let mut unwrapped: Vec<f32> = values.iter()
+                .map(|v| if param == AdcParameter::Heading { reference + wrap_180(*v - reference) } else { *v })
+                .collect();
+    unwrapped.sort_by(|a, b| a.total_cmp(b));
+
+    let n: usize = unwrapped.len();
+    let value: f32 = if n % 2 == 1 {
+        unwrapped[n / 2]
+    } else {
+        (unwrapped[n / 2 - 1] + unwrapped[n / 2]) / 2.0
+    };
+
+    if param == AdcParameter::Heading { value.rem_euclid(360.0) } else { value }
+}
+
+fn deviation(param: AdcParameter, value: f32, voted_value: f32) -> f32 {
+
+    if param == AdcParameter::Heading {
+        wrap_180(value - voted_value).abs()
+    } else {
+        (value - voted_value).abs()
+    }
+}
+
+/// Best link state of the channels (the voted data is available while one channel receives).
+fn best_link_state(link_states: &[LinkState]) -> LinkState {
+
+    let rank = |state: &LinkState| match state {
+        LinkState::Receiving => 0,
+        LinkState::Stale => 1,
+        LinkState::Connecting => 2,
+        LinkState::Lost => 3,
+    };
+
+    link_states.iter().copied().min_by_key(rank).unwrap_or(LinkState::Lost)
+}
+
+#[derive(Debug)]
+pub struct RedundancyConfigError(String);
+
+impl fmt::Display for RedundancyConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Redundant ADC config error: {}", self.0)
+    }
+}
+
+impl Error for RedundancyConfigError {}
+
+pub struct RedundantAdc {
+    channels: Vec<Adc>,
+    voter: AdcVoter,
+    publisher: Publisher,
+    link_state: Option<LinkState>,
+    health: Option<AdcHealthMessage>,
+}
+
+impl RedundantAdc {
+
+    /**
+     * One ADC channel per sensors provider, altered by the "fault_injection" section of the channel config.
+     * Faults names are prefixed by the channel name ("ADC 2") in the GUI fault panel.
+     */
+    pub fn from(
+        channels_sensors: Vec<Arc::<dyn SensorsProvider + Send + Sync>>,
+        bus: &Bus,
+        adc_config: &Yaml,
+        fault_switches: &FaultSwitches) -> Result<Self, RedundancyConfigError> {
+
+        let redundancy_config: &Yaml = &adc_config["redundancy"];
+
+        if channels_sensors.len() < 2 || channels_sensors.len() > 3 {
+            return Err(RedundancyConfigError(format!("2 or 3 channels required, {} configured", channels_sensors.len())));
+        }
+
+        let channels: Vec<Adc> = channels_sensors.into_iter()
+                    .enumerate()
+                    .map(|(i, sensors)| {
+                        let sensors = fault_injection::wrap(
+                                    sensors,
+                                    &redundancy_config["channels"][i]["fault_injection"],
+                                    fault_switches,
+                                    &format!("ADC {}", i + 1));
+                        Adc::from(sensors, bus.publisher(SourceId::AdcChannel(i as u8 + 1)), adc_config)
+                    })
+                    .collect();
+
+        println!("Redundant ADC : {} channels", channels.len());
+
+        Ok(Self {
+            voter: AdcVoter::new(channels.len(), VoterConfig::from(redundancy_config)),
+            channels,
+            publisher: bus.publisher(SourceId::Adc),
+            link_state: None,
+            health: None,
+        })
+    }
+
+    fn publish_link_state(&mut self, link_state: LinkState) {
+
+        if self.link_state != Some(link_state) {
+
+            self.link_state = Some(link_state);
+
+            self.publisher.publish(BusMessage::LinkState(link_state));
+        }
+    }
+
+    /// Publish the channels health when it changes.
+    fn publish_health(&mut self) {
+
+        let health: AdcHealthMessage = self.voter.health();
+
+        if self.health.as_ref() != Some(&health) {
+
+            println!("[ADC] Health : {:?}", health.channels);
+
+            self.health = Some(health.clone());
+
+            self.publisher.publish(BusMessage::AdcHealth(health));
+        }
+    }
+}
+
+impl AirDataComputer for RedundantAdc {
+
+    fn read_sensors(&mut self) {
+
+        let mut channels_data: Vec<Option<AdcDataMessage>> = Vec::new();
+        let mut link_states: Vec<LinkState> = Vec::new();
+
+        for channel in self.channels.iter_mut() {
+
+            channels_data.push(channel.compute());
+
+            let link_state: LinkState = channel.sensors_link_state();
+            channel.link_state = Some(link_state);
+            link_states.push(link_state);
+        }
+
+        self.publish_link_state(best_link_state(&link_states));
+
+        let voted_data: Option<AdcDataMessage> = self.voter.vote(&channels_data);
+
+        self.publish_health();
+
+        if let Some(adc_data) = voted_data {
+
+            self.publisher.publish(BusMessage::AdcData(Box::new(adc_data)));
+        }
+    }
+}
+
+#[cfg(test)]
+fn test_channel_data(ias: f32, alt_msl: f32, heading: f32) -> AdcDataMessage {
+
+    let mut data: AdcDataMessage = AdcDataMessage::new();
+    data.ias = ias;
+    data.alt_msl = alt_msl;
+    data.heading = heading;
+
+    for param in AdcParameter::ALL {
+        data.validity.set(param, ParamValidity { status: ParamStatus::Valid, timestamp_ms: Some(1_000) });
+    }
+
+    data
+}
+
+#[cfg(test)]
+fn test_voter_config(isolation_frames: u32) -> VoterConfig {
+
+    VoterConfig { miscompare_thresholds: DEFAULT_MISCOMPARE_THRESHOLDS, isolation_frames }
+}
+
+#[test]
+fn voter_mid_value_and_isolation() {
+
+    let mut voter: AdcVoter = AdcVoter::new(3, test_voter_config(3));
+
+    // Channels agree (heading around north).
+    let voted: AdcDataMessage = voter.vote(&[
+        Some(test_channel_data(250.0, 10_000.0, 359.0)),
+        Some(test_channel_data(252.0, 10_020.0, 1.0)),
+        Some(test_channel_data(251.0, 10_010.0, 0.0))]).unwrap();
+
+    assert_eq!(251.0, voted.ias);
+    assert_eq!(10_010.0, voted.alt_msl);
+    assert_eq!(0.0, voted.heading);
+    assert_eq!(vec![AdcChannelHealth::Ok; 3], voter.health().channels);
+
+    // Channel 2 IAS biased +20 kt : miscompare, isolated after 3 frames, voted value unaffected.
+    for frame in 1..=3 {
+
+        let voted: AdcDataMessage = voter.vote(&[
+            Some(test_channel_data(250.0, 10_000.0, 90.0)),
+            Some(test_channel_data(270.0, 10_000.0, 90.0)),
+            Some(test_channel_data(251.0, 10_000.0, 90.0))]).unwrap();
+
+        assert_eq!(251.0, voted.ias);
+        assert!(voted.validity.is_valid(AdcParameter::Ias));
+
+        let expected: AdcChannelHealth = if frame < 3 { AdcChannelHealth::Miscompare } else { AdcChannelHealth::Isolated };
+        assert_eq!(vec![AdcChannelHealth::Ok, expected, AdcChannelHealth::Ok], voter.health().channels);
+    }
+
+    // Isolation latched : channel 2 excluded even when back in agreement.
+    let voted: AdcDataMessage = voter.vote(&[
+        Some(test_channel_data(250.0, 10_000.0, 90.0)),
+        Some(test_channel_data(250.0, 10_000.0, 90.0)),
+        Some(test_channel_data(252.0, 10_000.0, 90.0))]).unwrap();
+
+    assert_eq!(251.0, voted.ias);
+    assert_eq!(AdcChannelHealth::Isolated, voter.health().channels[1]);
+}
+
+#[test]
+fn voter_two_channels_miscompare() {
+
+    let mut voter: AdcVoter = AdcVoter::new(2, test_voter_config(1));
+
+    // Disagreement : failed channel unknown, parameter flagged, no isolation.
+    let voted: AdcDataMessage = voter.vote(&[
+        Some(test_channel_data(250.0, 10_000.0, 90.0)),
+        Some(test_channel_data(250.0, 10_500.0, 90.0))]).unwrap();
+
+    assert_eq!(10_250.0, voted.alt_msl);
+    assert_eq!(ParamStatus::Miscompare, voted.validity.get(AdcParameter::AltMsl).status);
+    assert!(voted.validity.is_valid(AdcParameter::Ias));
+    assert_eq!(vec![AdcChannelHealth::Miscompare; 2], voter.health().channels);
+
+    // Channel 1 without data : channel 2 used alone.
+    let voted: AdcDataMessage = voter.vote(&[
+        None,
+        Some(test_channel_data(250.0, 10_500.0, 90.0))]).unwrap();
+
+    assert_eq!(10_500.0, voted.alt_msl);
+    assert!(voted.validity.is_valid(AdcParameter::AltMsl));
+    assert_eq!(vec![AdcChannelHealth::NoData, AdcChannelHealth::Ok], voter.health().channels);
+
+    assert!(voter.vote(&[None, None]).is_none());
+}
+
+#[test]
+fn redundancy_config() {
+
+    let config: Yaml = yaml_rust::YamlLoader::load_from_str("
+redundancy:
+    isolation_frames: 10
+    miscompare:
+        ias: 8
+        heading: 2.5
+        yaw: 1.0
+").unwrap().remove(0);
+
+    // Thresholds named as the fault injection parameters, yaw not voted.
+    let voter_config: VoterConfig = VoterConfig::from(&config["redundancy"]);
+    assert_eq!(10, voter_config.isolation_frames);
+    assert_eq!(8.0, voter_config.miscompare_thresholds[AdcParameter::Ias as usize]);
+    assert_eq!(2.5, voter_config.miscompare_thresholds[AdcParameter::Heading as usize]);
+    assert_eq!(DEFAULT_MISCOMPARE_THRESHOLDS[AdcParameter::AltMsl as usize], voter_config.miscompare_thresholds[AdcParameter::AltMsl as usize]);
+
+    let error: RedundancyConfigError = RedundantAdc::from(Vec::new(), &Bus::new(), &config, &FaultSwitches::new()).err().unwrap();
+    assert_eq!("Redundant ADC config error: 2 or 3 channels required, 0 configured", error.to_string());
+}
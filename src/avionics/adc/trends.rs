@@ -0,0 +1,175 @@
+/**
+ * ADC trend vectors
+ * Rate of change estimated by a least squares slope over the last samples, smoothed by a low-pass,
+ * then extrapolated over the prediction time :
+ * - IAS trend : IAS variation (knots) in 10 seconds
+ * - Altitude trend : altitude variation (feets) in 6 seconds
+ */
+use std::collections::VecDeque;
+
+use crate::avionics::adc::MAX_SAMPLE_PERIOD_S;
+use crate::avionics::adc::filters::LowPassFilter;
+
+const IAS_TREND_PREDICTION_S: f32 = 10.0;
+const ALT_TREND_PREDICTION_S: f32 = 6.0;
+
+/// Samples history used for the slope (s).
+const TREND_HISTORY_S: f32 = 2.0;
+
+/// Cut-off of the rate low-pass (Hz).
+const TREND_RATE_CUTOFF_HZ: f32 = 0.5;
+
+/// Trend of one parameter.
+pub struct Trend {
+    prediction: f32,
+    time: f32,
+    history: VecDeque<(f32, f32)>,
+    rate_filter: LowPassFilter,
+}
+
+impl Trend {
+
+    /// Trend predicted `prediction` seconds ahead.
+    pub fn new(prediction: f32) -> Trend {
+
+        Trend {
+            prediction,
+            time: 0.0,
+            history: VecDeque::new(),
+            rate_filter: LowPassFilter::new(TREND_RATE_CUTOFF_HZ),
+        }
+    }
+
+    /// Add a sample, `dt` is the delay (s) since the previous sample. Return the predicted variation.
+    pub fn update(&mut self, value: f32, dt: f32) -> f32 {
+
+        if dt <= 0.0 || dt > MAX_SAMPLE_PERIOD_S {
+            self.reset();
+        }
+
+        self.time += dt;
+        self.history.push_back((self.time, value));
+
+        while let Some((t, _)) = self.history.front() {
+            if self.time - t > TREND_HISTORY_S {
+                self.history.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        match self.slope() {
+            Some(rate) => self.rate_filter.update(rate, dt) * self.prediction,
+            None => 0.0
+        }
+    }
+
+    /// Restart the history on invalid (None) values.
+    fn update_valid(&mut self, value: Option<f32>, dt: f32) -> f32 {
+
+        match value {
+            Some(value) => self.update(value, dt),
+            None => {
+                self.reset();
+                0.0
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+
+        self.time = 0.0;
+        self.history.clear();
+        self.rate_filter.reset();
+    }
+
+    /// Least squares slope (unit/s) of the history, None below 2 samples.
+    fn slope(&self) -> Option<f32> {
+
+        if self.history.len() < 2 {
+            return None;
+        }
+
+        let n: f32 = self.history.len() as f32;
+        let mean_t: f32 = self.history.iter().map(|(t, _)| t).sum::<f32>() / n;
+        let mean_v: f32 = self.history.iter().map(|(_, v)| v).sum::<f32>() / n;
+
+        let mut covariance: f32 = 0.0;
+        let mut variance: f32 = 0.0;
+
+        for (t, v) in self.history.iter() {
+            covariance += (t - mean_t) * (v - mean_v);
+            variance += (t - mean_t) * (t - mean_t);
+        }
+
+        if variance > 0.0 {
+            Some(covariance / variance)
+        } else {
+            None
+        }
+    }
+}
+
+/// Speed and altitude trends of the ADC.
+pub struct AdcTrends {
+    ias: Trend,
+    alt: Trend,
+}
+
+impl AdcTrends {
+
+    pub fn new() -> AdcTrends {
+
+        AdcTrends {
+            ias: Trend::new(IAS_TREND_PREDICTION_S),
+            alt: Trend::new(ALT_TREND_PREDICTION_S),
+        }
+    }
+
+    /// Return (IAS trend in knots, altitude trend in feets), no trend for invalid (None) parameters.
+    pub fn update(&mut self, ias: Option<f32>, alt_msl: Option<f32>, dt: f32) -> (f32, f32) {
+
+        (self.ias.update_valid(ias, dt), self.alt.update_valid(alt_msl, dt))
+    }
+}
+
+#[test]
+fn trend_constant_rates() {
+
+    let dt: f32 = 0.05;
+    let mut trends: AdcTrends = AdcTrends::new();
+    let mut result: (f32, f32) = trends.update(Some(250.0), Some(10_000.0), 0.0);
+    assert_eq!((0.0, 0.0), result);
+
+    // Accelerating 1 kt/s and climbing 1200 ft/min (20 ft/s) for 20 s.
+    for i in 1..=400 {
+        let t: f32 = i as f32 * dt;
+        result = trends.update(Some(250.0 + t), Some(10_000.0 + 20.0 * t), dt);
+    }
+
+    assert!((result.0 - 10.0).abs() < 0.1, "ias trend {}", result.0);
+    assert!((result.1 - 120.0).abs() < 1.0, "alt trend {}", result.1);
+
+    // Invalid IAS : no speed trend.
+    result = trends.update(None, Some(10_000.0 + 20.0 * 20.05), dt);
+    assert_eq!(0.0, result.0);
+    assert!((result.1 - 120.0).abs() < 1.0, "alt trend {}", result.1);
+}
+
+#[test]
+fn trend_rejects_noise_and_restarts() {
+
+    let dt: f32 = 0.05;
+    let mut trend: Trend = Trend::new(IAS_TREND_PREDICTION_S);
+
+    // Steady speed with +/- 2 kt jitter : trend close to 0.
+    let mut value: f32 = 0.0;
+    for i in 0..400 {
+        let jitter: f32 = if i % 2 == 0 { 2.0 } else { -2.0 };
+        value = trend.update(250.0 + jitter, dt);
+    }
+    assert!(value.abs() < 1.0, "trend {}", value);
+
+    // Sample after a 5 s gap (link lost) : history restarted.
+    assert_eq!(0.0, trend.update(200.0, 5.0));
+}
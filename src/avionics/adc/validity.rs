@@ -0,0 +1,151 @@
+/**
+ * ADC parameters validity
+ * Each parameter is checked on the raw sensors sample :
+ * - NoData : never received or no data sent by the provider (X-Plane -999)
+ * - Stale : last reception older than the stale timeout ("adc.validity.stale_timeout_ms")
+ * - OutOfRange : value outside of the parameter physical range
+ */
+extern crate yaml_rust;
+
+use yaml_rust::Yaml;
+
+use crate::bus::{AdcParameter, AdcValidity, ParamStatus, ParamValidity};
+use crate::sensors::sensors::{SensorField, SensorsValues};
+
+const DEFAULT_STALE_TIMEOUT_MS: u64 = 1000;
+
+pub struct ValidityMonitor {
+    stale_timeout_ms: u64,
+}
+
+impl ValidityMonitor {
+
+    pub fn new(stale_timeout_ms: u64) -> ValidityMonitor {
+
+        ValidityMonitor { stale_timeout_ms }
+    }
+
+    /// Validity of all the ADC parameters of a sample at `now_ms` (clock ms).
+    pub fn check(&self, s_values: &SensorsValues, now_ms: u64) -> AdcValidity {
+
+        let mut validity: AdcValidity = AdcValidity::new();
+
+        for param in AdcParameter::ALL {
+
+            let timestamp_ms: Option<u64> = s_values.received_at(sensor_field(param));
+
+            let status: ParamStatus = match timestamp_ms {
+                None => ParamStatus::NoData,
+                Some(t) if now_ms.saturating_sub(t) > self.stale_timeout_ms => ParamStatus::Stale,
+                Some(_) => {
+                    let value: f32 = param_value(s_values, param);
+                    let (min, max) = valid_range(param);
+
+                    if value.is_finite() && value >= min && value <= max {
+                        ParamStatus::Valid
+                    } else {
+                        ParamStatus::OutOfRange
+                    }
+                }
+            };
+
+            validity.set(param, ParamValidity { status, timestamp_ms });
+        }
+
+        validity
+    }
+}
+
+impl From<&Yaml> for ValidityMonitor {
+
+    /// "adc.validity" section.
+    fn from(config: &Yaml) -> Self {
+
+        let stale_timeout_ms: u64 = config["stale_timeout_ms"].as_i64()
+                    .map(|v| v as u64)
+                    .unwrap_or(DEFAULT_STALE_TIMEOUT_MS);
+
+        ValidityMonitor::new(stale_timeout_ms)
+    }
+}
+
+/// Sensors field of an ADC parameter.
+pub fn sensor_field(param: AdcParameter) -> SensorField {
+
+    match param {
+        AdcParameter::Ias => SensorField::Ias,
+        AdcParameter::AltMsl => SensorField::AltMsl,
+        AdcParameter::AltAgl => SensorField::AltAgl,
+        AdcParameter::Heading => SensorField::Heading,
+        AdcParameter::Vs => SensorField::Vs,
+        AdcParameter::Aoa => SensorField::Aoa,
+        AdcParameter::Mach => SensorField::Mach,
+        AdcParameter::GLoad => SensorField::GLoad,
+        AdcParameter::Pitch => SensorField::Pitch,
+        AdcParameter::Roll => SensorField::Roll,
+    }
+}
+
+fn param_value(s_values: &SensorsValues, param: AdcParameter) -> f32 {
+
+    match param {
+        AdcParameter::Ias => s_values.ias,
+        AdcParameter::AltMsl => s_values.alt_msl,
+        AdcParameter::AltAgl => s_values.alt_agl,
+        AdcParameter::Heading => s_values.heading,
+        AdcParameter::Vs => s_values.vs,
+        AdcParameter::Aoa => s_values.aoa,
+        AdcParameter::Mach => s_values.mach,
+        AdcParameter::GLoad => s_values.g_load,
+        AdcParameter::Pitch => s_values.pitch,
+        AdcParameter::Roll => s_values.roll,
+    }
+}
+
+/// Physical range (min, max) of a parameter.
+fn valid_range(param: AdcParameter) -> (f32, f32) {
+
+    match param {
+        AdcParameter::Ias => (0.0, 600.0),
+        AdcParameter::AltMsl => (-2_000.0, 60_000.0),
+        AdcParameter::AltAgl => (-100.0, 60_000.0),
+        AdcParameter::Heading => (0.0, 360.0),
+        AdcParameter::Vs => (-20_000.0, 20_000.0),
+        AdcParameter::Aoa => (-45.0, 45.0),
+        AdcParameter::Mach => (0.0, 2.0),
+        AdcParameter::GLoad => (-5.0, 10.0),
+        AdcParameter::Pitch => (-90.0, 90.0),
+        AdcParameter::Roll => (-180.0, 180.0),
+    }
+}
+
+#[test]
+fn validity_status() {
+
+    let monitor: ValidityMonitor = ValidityMonitor::new(1000);
+
+    // Nothing received
+    let mut s_values: SensorsValues = SensorsValues::new();
+    let validity: AdcValidity = monitor.check(&s_values, 5_000);
+    assert!(AdcParameter::ALL.iter().all(|p| validity.get(*p).status == ParamStatus::NoData));
+
+    s_values.mark_all_received(4_500);
+    s_values.ias = 250.0;
+    s_values.alt_msl = 10_000.0;
+    s_values.heading = 180.0;
+    s_values.g_load = 1.0;
+    s_values.pitch = -999.0;
+
+    // No data sent for the VS (-999), stale AoA
+    s_values.mark_received(SensorField::Vs, None);
+    s_values.mark_received(SensorField::Aoa, Some(3_000));
+
+    let validity: AdcValidity = monitor.check(&s_values, 5_000);
+
+    assert_eq!(ParamValidity{ status: ParamStatus::Valid, timestamp_ms: Some(4_500) }, validity.get(AdcParameter::Ias));
+    assert!(validity.all_valid(&[AdcParameter::Ias, AdcParameter::AltMsl, AdcParameter::Heading, AdcParameter::GLoad, AdcParameter::Roll]));
+    assert_eq!(ParamStatus::NoData, validity.get(AdcParameter::Vs).status);
+    assert_eq!(ParamValidity{ status: ParamStatus::Stale, timestamp_ms: Some(3_000) }, validity.get(AdcParameter::Aoa));
+    assert_eq!(ParamStatus::OutOfRange, validity.get(AdcParameter::Pitch).status);
+    assert!(!validity.all_valid(&[AdcParameter::Pitch, AdcParameter::Roll]));
+}
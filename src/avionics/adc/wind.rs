@@ -0,0 +1,151 @@
+/**
+ * Wind estimation
+ * Wind vector = ground vector (ground speed along the track) - air vector (TAS along the true heading),
+ * north/east components smoothed by a low-pass, then converted to direction/speed and head/cross components.
+ * No wind below the minimum TAS (on ground, heading and track meaningless) or without ground speed/track.
+ */
+use crate::avionics::adc::MAX_SAMPLE_PERIOD_S;
+use crate::avionics::adc::filters::LowPassFilter;
+use crate::bus::WindData;
+
+/// Below this TAS (knots) the wind is not computed.
+const MIN_WIND_TAS_KT: f32 = 50.0;
+
+/// Cut-off of the wind components low-pass (Hz).
+const WIND_CUTOFF_HZ: f32 = 0.1;
+
+/// Inputs of the wind estimation.
+#[derive(Debug, Clone, Copy)]
+pub struct WindInputs {
+    pub tas: f32,
+    pub true_heading: f32,
+    pub ground_speed: f32,
+    pub track: f32,
+}
+
+impl WindData {
+
+    /// Wind blowing from `direction` (deg true) at `speed` (knots), components relative to `true_heading`.
+    pub fn from_direction(direction: f32, speed: f32, true_heading: f32) -> WindData {
+
+        let relative: f32 = (direction - true_heading).to_radians();
+
+        WindData {
+            direction: direction.rem_euclid(360.0),
+            speed,
+            headwind: speed * relative.cos(),
+            crosswind: speed * relative.sin(),
+        }
+    }
+
+    /// Wind of the (north, east) components of the vector the air moves to (knots).
+    fn from_components(north: f32, east: f32, true_heading: f32) -> WindData {
+
+        let speed: f32 = north.hypot(east);
+        let direction: f32 = if speed > 0.0 { east.atan2(north).to_degrees() + 180.0 } else { 0.0 };
+
+        WindData::from_direction(direction, speed, true_heading)
+    }
+}
+
+/// (north, east) components of a vector (knots, deg true).
+fn components(speed: f32, direction: f32) -> (f32, f32) {
+
+    let direction: f32 = direction.to_radians();
+
+    (speed * direction.cos(), speed * direction.sin())
+}
+
+pub struct WindEstimator {
+    north: LowPassFilter,
+    east: LowPassFilter,
+}
+
+impl WindEstimator {
+
+    pub fn new() -> WindEstimator {
+
+        WindEstimator {
+            north: LowPassFilter::new(WIND_CUTOFF_HZ),
+            east: LowPassFilter::new(WIND_CUTOFF_HZ),
+        }
+    }
+
+    /// Smoothed wind, `dt` is the delay (s) since the previous sample. None without inputs (estimation restarted).
+    pub fn update(&mut self, inputs: Option<WindInputs>, dt: f32) -> Option<WindData> {
+
+        let inputs: WindInputs = match inputs {
+            Some(inputs) if inputs.tas >= MIN_WIND_TAS_KT => inputs,
+            _ => {
+                self.reset();
+                return None;
+            }
+        };
+
+        if dt <= 0.0 || dt > MAX_SAMPLE_PERIOD_S {
+            self.reset();
+        }
+
+        let (ground_north, ground_east) = components(inputs.ground_speed, inputs.track);
+        let (air_north, air_east) = components(inputs.tas, inputs.true_heading);
+
+        let north: f32 = self.north.update(ground_north - air_north, dt);
+        let east: f32 = self.east.update(ground_east - air_east, dt);
+
+        Some(WindData::from_components(north, east, inputs.true_heading))
+    }
+
+    pub fn reset(&mut self) {
+
+        self.north.reset();
+        self.east.reset();
+    }
+}
+
+#[test]
+fn wind_triangle() {
+
+    // First sample of an estimator : raw wind.
+    let compute_wind = |inputs: WindInputs| WindEstimator::new().update(Some(inputs), 0.05).unwrap();
+
+    // Heading north at 200 kt TAS, 20 kt wind from the west (270) : drift to the east, track 005.7, GS 201.
+    let wind: WindData = compute_wind(WindInputs{ tas: 200.0, true_heading: 0.0, ground_speed: 200.0_f32.hypot(20.0), track: 20.0_f32.atan2(200.0).to_degrees() });
+    assert!((wind.direction - 270.0).abs() < 0.1, "direction {}", wind.direction);
+    assert!((wind.speed - 20.0).abs() < 0.1, "speed {}", wind.speed);
+    assert!(wind.headwind.abs() < 0.1, "headwind {}", wind.headwind);
+    assert!((wind.crosswind + 20.0).abs() < 0.1, "crosswind {}", wind.crosswind);
+
+    // Heading 090, 30 kt headwind : GS 170 on the same track.
+    let wind: WindData = compute_wind(WindInputs{ tas: 200.0, true_heading: 90.0, ground_speed: 170.0, track: 90.0 });
+    assert!((wind.direction - 90.0).abs() < 0.1, "direction {}", wind.direction);
+    assert!((wind.headwind - 30.0).abs() < 0.1, "headwind {}", wind.headwind);
+    assert!(wind.crosswind.abs() < 0.1, "crosswind {}", wind.crosswind);
+
+    // Wind from the right of a 350 heading (wind 020).
+    let wind: WindData = WindData::from_direction(20.0, 10.0, 350.0);
+    assert!(wind.crosswind > 4.9 && wind.headwind > 8.6);
+}
+
+#[test]
+fn wind_estimator_smoothing() {
+
+    let mut estimator: WindEstimator = WindEstimator::new();
+    let inputs: WindInputs = WindInputs{ tas: 200.0, true_heading: 90.0, ground_speed: 170.0, track: 90.0 };
+
+    // Started on the first sample.
+    let wind: WindData = estimator.update(Some(inputs), 0.05).unwrap();
+    assert!((wind.speed - 30.0).abs() < 0.1);
+
+    // Heading/track jitter smoothed.
+    for i in 0..100 {
+        let jitter: f32 = if i % 2 == 0 { 3.0 } else { -3.0 };
+        estimator.update(Some(WindInputs{ track: 90.0 + jitter, ..inputs }), 0.05);
+    }
+    let wind: WindData = estimator.update(Some(inputs), 0.05).unwrap();
+    assert!((wind.speed - 30.0).abs() < 1.0, "speed {}", wind.speed);
+    assert!((wind.direction - 90.0).abs() < 2.0, "direction {}", wind.direction);
+
+    // Low speed or missing ground data : no wind.
+    assert_eq!(None, estimator.update(Some(WindInputs{ tas: 20.0, ..inputs }), 0.05));
+    assert_eq!(None, estimator.update(None, 0.05));
+}
@@ -0,0 +1,104 @@
+pub mod attitude;
+
+
+pub mod ahrs {
+
+    extern crate yaml_rust;
+
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use yaml_rust::Yaml;
+
+    use crate::avionics::ahrs::attitude::{wrap_180, AttitudeFilter, AttitudeFilterConfig};
+    use crate::bus::{AhrsDataMessage, BusMessage, Publisher};
+    use crate::sensors::SensorsProvider;
+    use crate::sensors::sensors::{SensorField, SensorsSample, SensorsValues};
+
+    /// Inertial sample older than this delay is not integrated (link stale or lost).
+    const MAX_SAMPLE_AGE: Duration = Duration::from_millis(500);
+
+    /**
+     * AHRS for Attitude and Heading Reference System
+     * Compute the attitude (pitch, roll) and heading from the body angular rates and accelerations
+     * instead of the sim attitude (complementary filter, see attitude.rs) :
+     * - Alignment status and progress
+     * - Drift from the sim attitude, kept as reference
+     */
+    pub struct Ahrs {
+        pub sensors: Arc::<dyn SensorsProvider + Send + Sync>,
+        pub publisher: Publisher,
+        pub filter: AttitudeFilter,
+        pub last_sample_at: Option<Instant>
+    }
+
+    impl Ahrs {
+
+        /// AHRS with the "ahrs" config section.
+        pub fn from(
+            sensors: Arc::<dyn SensorsProvider + Send + Sync>,
+            publisher: Publisher,
+            ahrs_config: &Yaml) -> Self {
+
+            Self {
+                sensors,
+                publisher,
+                filter: AttitudeFilter::new(AttitudeFilterConfig::from(ahrs_config)),
+                last_sample_at: None
+            }
+        }
+
+        /// Acquire the sensors and integrate the inertial data, None when no sample is available.
+        pub fn compute(&mut self) -> Option<AhrsDataMessage> {
+
+            let s_sample: SensorsSample = self.sensors.acquire().ok()?;
+
+            let now: Instant = Instant::now();
+            let dt: f32 = match self.last_sample_at {
+                Some(last_sample_at) => now.duration_since(last_sample_at).as_secs_f32(),
+                None => 0f32
+            };
+            self.last_sample_at = Some(now);
+
+            let s_values: SensorsValues = s_sample.values;
+
+            if s_sample.age > MAX_SAMPLE_AGE {
+                self.filter.update(None, None, None, dt);
+            } else {
+                let magnetic_heading: Option<f32> = received(&s_values, SensorField::Heading, s_values.heading);
+                self.filter.update(s_values.body_rates, s_values.body_accels, magnetic_heading, dt);
+            }
+
+            let sim_pitch: Option<f32> = received(&s_values, SensorField::Pitch, s_values.pitch);
+            let sim_roll: Option<f32> = received(&s_values, SensorField::Roll, s_values.roll);
+            let sim_heading: Option<f32> = received(&s_values, SensorField::Heading, s_values.heading);
+
+            Some(AhrsDataMessage {
+                pitch: self.filter.pitch(),
+                roll: self.filter.roll(),
+                heading: self.filter.heading(),
+                status: self.filter.status(),
+                alignment: self.filter.alignment(),
+                pitch_drift: sim_pitch.map(|pitch| self.filter.pitch() - pitch),
+                roll_drift: sim_roll.map(|roll| wrap_180(self.filter.roll() - roll)),
+                heading_drift: sim_heading.map(|heading| wrap_180(self.filter.heading() - heading)),
+            })
+        }
+
+        /// Acquire the sensors and publish the AHRS data.
+        pub fn read_sensors(&mut self) {
+
+            if let Some(ahrs_data) = self.compute() {
+
+                self.publisher.publish(BusMessage::AhrsData(ahrs_data));
+            }
+        }
+    }
+
+    /// Value of a field received from the sensors.
+    fn received(s_values: &SensorsValues, field: SensorField, value: f32) -> Option<f32> {
+
+        s_values.received_at(field).map(|_| value)
+    }
+}
+
+pub use ahrs::Ahrs;
@@ -0,0 +1,283 @@
+/**
+ * AHRS complementary filter
+ * Attitude (pitch, roll) and heading integrated from the body rates (Euler angles kinematics), corrected by :
+ * - the gravity direction of the accelerometers (pitch, roll), only when the load factor is close to 1 g
+ *   (in accelerated flight or coordinated turns the accelerometers don't measure the vertical)
+ * - the magnetic heading (compass)
+ *
+ * Alignment : fast corrections ("alignment_tau_s") during "alignment_s" seconds of unaccelerated flight,
+ * then slow corrections ("attitude_tau_s", "heading_tau_s").
+ */
+extern crate yaml_rust;
+
+use yaml_rust::Yaml;
+
+use crate::avionics::adc::MAX_SAMPLE_PERIOD_S;
+use crate::bus::AhrsStatus;
+use crate::config::get_positive_f32;
+use crate::sensors::sensors::{BodyAccels, BodyRates};
+
+const DEFAULT_ALIGNMENT_S: f32 = 10.0;
+const DEFAULT_ALIGNMENT_TAU_S: f32 = 0.5;
+const DEFAULT_ATTITUDE_TAU_S: f32 = 20.0;
+const DEFAULT_HEADING_TAU_S: f32 = 30.0;
+const DEFAULT_ACCEL_GATE_G: f32 = 0.05;
+
+/// Pitch limit of the Euler kinematics (gimbal lock at +/- 90 deg).
+const MAX_PITCH_DEG: f32 = 89.0;
+
+pub struct AttitudeFilterConfig {
+    pub alignment_s: f32,
+    pub alignment_tau_s: f32,
+    pub attitude_tau_s: f32,
+    pub heading_tau_s: f32,
+    pub accel_gate_g: f32,
+}
+
+impl From<&Yaml> for AttitudeFilterConfig {
+
+    /// "ahrs" section.
+    fn from(config: &Yaml) -> Self {
+
+        AttitudeFilterConfig {
+            alignment_s: get_positive_f32(&config["alignment_s"]).unwrap_or(DEFAULT_ALIGNMENT_S),
+            alignment_tau_s: get_positive_f32(&config["alignment_tau_s"]).unwrap_or(DEFAULT_ALIGNMENT_TAU_S),
+            attitude_tau_s: get_positive_f32(&config["attitude_tau_s"]).unwrap_or(DEFAULT_ATTITUDE_TAU_S),
+            heading_tau_s: get_positive_f32(&config["heading_tau_s"]).unwrap_or(DEFAULT_HEADING_TAU_S),
+            accel_gate_g: get_positive_f32(&config["accel_gate_g"]).unwrap_or(DEFAULT_ACCEL_GATE_G),
+        }
+    }
+}
+
+pub struct AttitudeFilter {
+    config: AttitudeFilterConfig,
+    pitch: f32,
+    roll: f32,
+    heading: f32,
+    status: AhrsStatus,
+    aligned_s: f32,
+}
+
+impl AttitudeFilter {
+
+    pub fn new(config: AttitudeFilterConfig) -> AttitudeFilter {
+
+        AttitudeFilter {
+            config,
+            pitch: 0.0,
+            roll: 0.0,
+            heading: 0.0,
+            status: AhrsStatus::NoData,
+            aligned_s: 0.0,
+        }
+    }
+
+    /**
+     * Integrate a sample, `dt` is the delay (s) since the previous sample, `magnetic_heading` the compass heading when valid.
+     * Without inertial data the filter is restarted (new alignment).
+     */
+    pub fn update(&mut self, rates: Option<BodyRates>, accels: Option<BodyAccels>, magnetic_heading: Option<f32>, dt: f32) {
+
+        let (rates, accels) = match (rates, accels) {
+            (Some(rates), Some(accels)) => (rates, accels),
+            _ => {
+                self.reset();
+                return;
+            }
+        };
+
+        let load_factor: f32 = (accels.axial.powi(2) + accels.side.powi(2) + accels.normal.powi(2)).sqrt();
+        let unaccelerated: bool = (load_factor - 1.0).abs() <= self.config.accel_gate_g;
+
+        // Gravity direction measured by the accelerometers.
+        let accel_pitch: f32 = accels.axial.atan2(accels.side.hypot(accels.normal)).to_degrees();
+        let accel_roll: f32 = (-accels.side).atan2(accels.normal).to_degrees();
+
+        if self.status == AhrsStatus::NoData {
+
+            // Leveling and compass heading at start.
+            self.pitch = accel_pitch;
+            self.roll = accel_roll;
+            self.heading = magnetic_heading.unwrap_or(0.0);
+            self.status = AhrsStatus::Aligning;
+            self.aligned_s = 0.0;
+            return;
+        }
+
+        if dt <= 0.0 || dt > MAX_SAMPLE_PERIOD_S {
+            return;
+        }
+
+        // Euler angles rates from the body rates.
+        let (sin_roll, cos_roll) = self.roll.to_radians().sin_cos();
+        let tan_pitch: f32 = self.pitch.to_radians().tan();
+        let cos_pitch: f32 = self.pitch.to_radians().cos();
+
+        let roll_rate: f32 = rates.p + (rates.q * sin_roll + rates.r * cos_roll) * tan_pitch;
+        let pitch_rate: f32 = rates.q * cos_roll - rates.r * sin_roll;
+        let heading_rate: f32 = (rates.q * sin_roll + rates.r * cos_roll) / cos_pitch;
+
+        self.pitch = (self.pitch + pitch_rate * dt).clamp(-MAX_PITCH_DEG, MAX_PITCH_DEG);
+        self.roll = wrap_180(self.roll + roll_rate * dt);
+        self.heading = (self.heading + heading_rate * dt).rem_euclid(360.0);
+
+        // Alignment progress only in unaccelerated flight.
+        if self.status == AhrsStatus::Aligning && unaccelerated {
+            self.aligned_s += dt;
+            if self.aligned_s >= self.config.alignment_s {
+                println!("[AHRS] Aligned");
+                self.status = AhrsStatus::Aligned;
+            }
+        }
+
+        let (attitude_tau_s, heading_tau_s) = match self.status {
+            AhrsStatus::Aligning => (self.config.alignment_tau_s, self.config.alignment_tau_s),
+            _ => (self.config.attitude_tau_s, self.config.heading_tau_s),
+        };
+
+        if unaccelerated {
+            let gain: f32 = dt / (attitude_tau_s + dt);
+            self.pitch += gain * (accel_pitch - self.pitch);
+            self.roll = wrap_180(self.roll + gain * wrap_180(accel_roll - self.roll));
+        }
+
+        if let Some(magnetic_heading) = magnetic_heading {
+            let gain: f32 = dt / (heading_tau_s + dt);
+            self.heading = (self.heading + gain * wrap_180(magnetic_heading - self.heading)).rem_euclid(360.0);
+        }
+    }
+
+    pub fn reset(&mut self) {
+
+        if self.status != AhrsStatus::NoData {
+            println!("[AHRS] No inertial data");
+        }
+
+        self.status = AhrsStatus::NoData;
+        self.aligned_s = 0.0;
+    }
+
+    pub fn pitch(&self) -> f32 {
+
+        self.pitch
+    }
+
+    pub fn roll(&self) -> f32 {
+
+        self.roll
+    }
+
+    pub fn heading(&self) -> f32 {
+
+        self.heading
+    }
+
+    pub fn status(&self) -> AhrsStatus {
+
+        self.status
+    }
+
+    /// Alignment progress from 0 to 1.
+    pub fn alignment(&self) -> f32 {
+
+        match self.status {
+            AhrsStatus::NoData => 0.0,
+            AhrsStatus::Aligning => (self.aligned_s / self.config.alignment_s).min(1.0),
+            AhrsStatus::Aligned => 1.0,
+        }
+    }
+}
+
+/// Angle in the -180 to 180 range.
+pub fn wrap_180(angle: f32) -> f32 {
+
+    (angle + 180.0).rem_euclid(360.0) - 180.0
+}
+
+#[cfg(test)]
+fn test_config() -> AttitudeFilterConfig {
+
+    AttitudeFilterConfig::from(&Yaml::BadValue)
+}
+
+/// Specific force of a static aircraft at this attitude (deg).
+#[cfg(test)]
+fn level_accels(pitch: f32, roll: f32) -> BodyAccels {
+
+    let (sin_pitch, cos_pitch) = pitch.to_radians().sin_cos();
+    let (sin_roll, cos_roll) = roll.to_radians().sin_cos();
+
+    BodyAccels{ axial: sin_pitch, side: -sin_roll * cos_pitch, normal: cos_roll * cos_pitch }
+}
+
+#[test]
+fn attitude_filter_alignment() {
+
+    let dt: f32 = 0.05;
+    let mut filter: AttitudeFilter = AttitudeFilter::new(test_config());
+    let no_rates: BodyRates = BodyRates{ p: 0.0, q: 0.0, r: 0.0 };
+
+    filter.update(None, Some(level_accels(5.0, -10.0)), Some(90.0), dt);
+    assert_eq!(AhrsStatus::NoData, filter.status());
+
+    // Leveling on the first sample.
+    filter.update(Some(no_rates), Some(level_accels(5.0, -10.0)), Some(90.0), dt);
+    assert_eq!(AhrsStatus::Aligning, filter.status());
+    assert!((filter.pitch() - 5.0).abs() < 0.01 && (filter.roll() + 10.0).abs() < 0.01);
+    assert_eq!(90.0, filter.heading());
+
+    // Aligned after 10 s of unaccelerated flight.
+    for _ in 0..150 {
+        filter.update(Some(no_rates), Some(level_accels(5.0, -10.0)), Some(90.0), dt);
+    }
+    assert_eq!(AhrsStatus::Aligning, filter.status());
+    assert!((filter.alignment() - 0.75).abs() < 0.01, "alignment {}", filter.alignment());
+
+    // Pulling 2 g : alignment paused.
+    for _ in 0..100 {
+        filter.update(Some(no_rates), Some(BodyAccels{ axial: 0.0, side: 0.0, normal: 2.0 }), Some(90.0), dt);
+    }
+    assert_eq!(AhrsStatus::Aligning, filter.status());
+
+    for _ in 0..60 {
+        filter.update(Some(no_rates), Some(level_accels(5.0, -10.0)), Some(90.0), dt);
+    }
+    assert_eq!(AhrsStatus::Aligned, filter.status());
+    assert_eq!(1.0, filter.alignment());
+
+    // Inertial data lost : new alignment.
+    filter.update(Some(no_rates), None, Some(90.0), dt);
+    assert_eq!(AhrsStatus::NoData, filter.status());
+}
+
+#[test]
+fn attitude_filter_coordinated_turn() {
+
+    let dt: f32 = 0.02;
+    let mut filter: AttitudeFilter = AttitudeFilter::new(test_config());
+    let no_rates: BodyRates = BodyRates{ p: 0.0, q: 0.0, r: 0.0 };
+
+    for _ in 0..600 {
+        filter.update(Some(no_rates), Some(level_accels(0.0, 0.0)), Some(0.0), dt);
+    }
+    assert_eq!(AhrsStatus::Aligned, filter.status());
+
+    // Roll to 30 deg right at 10 deg/s : accelerometers along the normal axis (coordinated), attitude from the gyros.
+    let turn_accels: BodyAccels = BodyAccels{ axial: 0.0, side: 0.0, normal: 1.0 / 30_f32.to_radians().cos() };
+    for _ in 0..150 {
+        filter.update(Some(BodyRates{ p: 10.0, q: 0.0, r: 0.0 }), Some(turn_accels), Some(0.0), dt);
+    }
+    assert!((filter.roll() - 30.0).abs() < 0.5, "roll {}", filter.roll());
+
+    // Steady 3 deg/s turn : heading rate 3 deg/s (body rates q = w.sin(roll), r = w.cos(roll)).
+    let turn_rate: f32 = 3.0;
+    let turn_rates: BodyRates = BodyRates{ p: 0.0, q: turn_rate * 30_f32.to_radians().sin(), r: turn_rate * 30_f32.to_radians().cos() };
+    let mut compass: f32 = 0.0;
+    for _ in 0..500 {
+        compass += turn_rate * dt;
+        filter.update(Some(turn_rates), Some(turn_accels), Some(compass), dt);
+    }
+    assert!((filter.roll() - 30.0).abs() < 0.5, "roll {}", filter.roll());
+    assert!(filter.pitch().abs() < 0.5, "pitch {}", filter.pitch());
+    assert!(wrap_180(filter.heading() - 30.0).abs() < 0.5, "heading {}", filter.heading());
+}
@@ -0,0 +1,300 @@
+pub mod transmitter;
+
+/**
+ * ARINC 429 words
+ * 32 bits word, bit 1 the least significant :
+ * - bits 1-8 : label (octal), bit 1 the label most significant bit (transmission order)
+ * - bits 9-10 : SDI (source/destination identifier)
+ * - bits 11-28 : BNR data (two's complement, most significant bit 28), bit 29 : sign
+ * - bits 30-31 : SSM (sign/status matrix)
+ * - bit 32 : odd parity
+ *
+ * ADC words (ARINC 706 labels) encoded from the AdcDataMessage, AP words (selected values) from the APStateMessage,
+ * the AP selected values words of the test bench decoded as AP commands.
+ * The IAS is sent as CAS (label 206), the heading as a +/- 180 deg BNR angle.
+ */
+use std::error::Error;
+use std::fmt;
+
+use crate::avionics::ahrs::attitude::wrap_180;
+use crate::bus::{AdcDataMessage, AdcParameter, APCmdPayload, APStateMessage, ParamStatus, SpeedUnit};
+
+/// Status of a BNR word (bits 30-31).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ssm {
+    FailureWarning = 0,
+    NoComputedData = 1,
+    FunctionalTest = 2,
+    NormalOperation = 3,
+}
+
+impl Ssm {
+
+    fn from_bits(bits: u32) -> Ssm {
+
+        match bits & 0b11 {
+            0 => Ssm::FailureWarning,
+            1 => Ssm::NoComputedData,
+            2 => Ssm::FunctionalTest,
+            _ => Ssm::NormalOperation,
+        }
+    }
+
+    /// SSM of an ADC parameter of this status.
+    pub fn from_status(status: ParamStatus) -> Ssm {
+
+        match status {
+            ParamStatus::Valid => Ssm::NormalOperation,
+            ParamStatus::NoData | ParamStatus::Stale => Ssm::NoComputedData,
+            ParamStatus::OutOfRange | ParamStatus::Miscompare => Ssm::FailureWarning,
+        }
+    }
+}
+
+/// BNR format of a label : range (value of the sign bit) and significant bits, resolution = range / 2^bits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BnrFormat {
+    pub label: u8,
+    pub range: f32,
+    pub sig_bits: u32,
+}
+
+impl BnrFormat {
+
+    pub fn resolution(&self) -> f32 {
+
+        self.range / (1u32 << self.sig_bits) as f32
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Arinc429Error {
+    Parity(u32),
+    UnknownLabel(u8),
+}
+
+impl fmt::Display for Arinc429Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Arinc429Error::Parity(word) => write!(f, "ARINC 429 error: parity of word {:#010x}", word),
+            Arinc429Error::UnknownLabel(label) => write!(f, "ARINC 429 error: unknown label {:03o}", label),
+        }
+    }
+}
+
+impl Error for Arinc429Error {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arinc429Word(pub u32);
+
+impl Arinc429Word {
+
+    /// BNR word of `value`, clamped to the label range.
+    pub fn encode_bnr(format: &BnrFormat, sdi: u8, value: f32, ssm: Ssm) -> Arinc429Word {
+
+        let max: i32 = (1 << format.sig_bits) - 1;
+        let data: i32 = ((value / format.resolution()).round() as i32).clamp(-max - 1, max);
+        let data_mask: u32 = (1u32 << (format.sig_bits + 1)) - 1;
+
+        let word: u32 = (format.label.reverse_bits() as u32)
+                    | ((sdi as u32 & 0b11) << 8)
+                    | ((data as u32 & data_mask) << (28 - format.sig_bits))
+                    | ((ssm as u32) << 29);
+
+        Arinc429Word(with_parity(word))
+    }
+
+    pub fn label(&self) -> u8 {
+
+        (self.0 as u8).reverse_bits()
+    }
+
+    pub fn sdi(&self) -> u8 {
+
+        ((self.0 >> 8) & 0b11) as u8
+    }
+
+    pub fn ssm(&self) -> Ssm {
+
+        Ssm::from_bits(self.0 >> 29)
+    }
+
+    /// Odd parity of the 32 bits.
+    pub fn parity_ok(&self) -> bool {
+
+        self.0.count_ones() % 2 == 1
+    }
+
+    /// Value of a BNR word of this format (label not checked).
+    pub fn decode_bnr(&self, format: &BnrFormat) -> Result<f32, Arinc429Error> {
+
+        if !self.parity_ok() {
+            return Err(Arinc429Error::Parity(self.0));
+        }
+
+        // Data and sign bits, sign extended.
+        let bits: u32 = format.sig_bits + 1;
+        let data: i32 = (((self.0 >> (28 - format.sig_bits)) << (32 - bits)) as i32) >> (32 - bits);
+
+        Ok(data as f32 * format.resolution())
+    }
+}
+
+fn with_parity(word: u32) -> u32 {
+
+    let word: u32 = word & 0x7FFF_FFFF;
+
+    if word.count_ones().is_multiple_of(2) { word | 0x8000_0000 } else { word }
+}
+
+pub const PRESSURE_ALTITUDE: BnrFormat = BnrFormat { label: 0o203, range: 131_072.0, sig_bits: 17 };
+pub const BARO_ALTITUDE: BnrFormat = BnrFormat { label: 0o204, range: 131_072.0, sig_bits: 17 };
+pub const MACH: BnrFormat = BnrFormat { label: 0o205, range: 4.096, sig_bits: 16 };
+pub const COMPUTED_AIRSPEED: BnrFormat = BnrFormat { label: 0o206, range: 1024.0, sig_bits: 14 };
+pub const TRUE_AIRSPEED: BnrFormat = BnrFormat { label: 0o210, range: 2048.0, sig_bits: 15 };
+pub const ALTITUDE_RATE: BnrFormat = BnrFormat { label: 0o212, range: 32_768.0, sig_bits: 11 };
+pub const STATIC_AIR_TEMPERATURE: BnrFormat = BnrFormat { label: 0o213, range: 512.0, sig_bits: 11 };
+pub const ANGLE_OF_ATTACK: BnrFormat = BnrFormat { label: 0o221, range: 180.0, sig_bits: 12 };
+pub const MAGNETIC_HEADING: BnrFormat = BnrFormat { label: 0o320, range: 180.0, sig_bits: 15 };
+pub const PITCH_ANGLE: BnrFormat = BnrFormat { label: 0o324, range: 180.0, sig_bits: 14 };
+pub const ROLL_ANGLE: BnrFormat = BnrFormat { label: 0o325, range: 180.0, sig_bits: 14 };
+
+pub const SELECTED_HEADING: BnrFormat = BnrFormat { label: 0o101, range: 180.0, sig_bits: 12 };
+pub const SELECTED_ALTITUDE: BnrFormat = BnrFormat { label: 0o102, range: 65_536.0, sig_bits: 16 };
+pub const SELECTED_AIRSPEED: BnrFormat = BnrFormat { label: 0o103, range: 512.0, sig_bits: 11 };
+pub const SELECTED_VERTICAL_SPEED: BnrFormat = BnrFormat { label: 0o104, range: 16_384.0, sig_bits: 10 };
+
+/// ADC word : format, field of the ADC data and parameter giving the SSM.
+struct AdcWord {
+    format: BnrFormat,
+    get: fn(&AdcDataMessage) -> f32,
+    param: AdcParameter,
+}
+
+const ADC_WORDS: [AdcWord; 11] = [
+    AdcWord { format: PRESSURE_ALTITUDE, get: |adc| adc.pressure_alt, param: AdcParameter::AltMsl },
+    AdcWord { format: BARO_ALTITUDE, get: |adc| adc.alt_msl, param: AdcParameter::AltMsl },
+    AdcWord { format: MACH, get: |adc| adc.mach, param: AdcParameter::Mach },
+    AdcWord { format: COMPUTED_AIRSPEED, get: |adc| adc.ias, param: AdcParameter::Ias },
+    AdcWord { format: TRUE_AIRSPEED, get: |adc| adc.tas, param: AdcParameter::Ias },
+    AdcWord { format: ALTITUDE_RATE, get: |adc| adc.vs, param: AdcParameter::Vs },
+    AdcWord { format: STATIC_AIR_TEMPERATURE, get: |adc| adc.sat, param: AdcParameter::AltMsl },
+    AdcWord { format: ANGLE_OF_ATTACK, get: |adc| adc.aoa, param: AdcParameter::Aoa },
+    AdcWord { format: MAGNETIC_HEADING, get: |adc| wrap_180(adc.heading), param: AdcParameter::Heading },
+    AdcWord { format: PITCH_ANGLE, get: |adc| adc.pitch_angle, param: AdcParameter::Pitch },
+    AdcWord { format: ROLL_ANGLE, get: |adc| adc.roll_angle, param: AdcParameter::Roll },
+];
+
+/// ADC words of the data, SSM from the validity of the parameters.
+pub fn encode_adc(adc_data: &AdcDataMessage, sdi: u8) -> Vec<Arinc429Word> {
+
+    ADC_WORDS.iter()
+        .map(|word| Arinc429Word::encode_bnr(
+                    &word.format,
+                    sdi,
+                    (word.get)(adc_data),
+                    Ssm::from_status(adc_data.validity.get(word.param).status)))
+        .collect()
+}
+
+/// AP selected values words.
+pub fn encode_ap(ap_state: &APStateMessage, sdi: u8) -> Vec<Arinc429Word> {
+
+    vec![
+        Arinc429Word::encode_bnr(&SELECTED_HEADING, sdi, wrap_180(ap_state.heading), Ssm::NormalOperation),
+        Arinc429Word::encode_bnr(&SELECTED_ALTITUDE, sdi, ap_state.alt, Ssm::NormalOperation),
+        Arinc429Word::encode_bnr(&SELECTED_AIRSPEED, sdi, ap_state.speed, Ssm::NormalOperation),
+        Arinc429Word::encode_bnr(&SELECTED_VERTICAL_SPEED, sdi, ap_state.vs, Ssm::NormalOperation),
+    ]
+}
+
+/// AP command of an AP selected value word, None for the selected heading (no AP heading command).
+pub fn decode_ap_word(word: Arinc429Word) -> Result<Option<APCmdPayload>, Arinc429Error> {
+
+    let ap_cmd: Option<APCmdPayload> = match word.label() {
+        0o101 => {
+            word.decode_bnr(&SELECTED_HEADING)?;
+            None
+        },
+        0o102 => Some(APCmdPayload::SetAlt(word.decode_bnr(&SELECTED_ALTITUDE)?)),
+        0o103 => Some(APCmdPayload::SetSpeed{ speed: word.decode_bnr(&SELECTED_AIRSPEED)?, unit: SpeedUnit::IAS }),
+        0o104 => Some(APCmdPayload::SetVs(word.decode_bnr(&SELECTED_VERTICAL_SPEED)?)),
+        label => return Err(Arinc429Error::UnknownLabel(label)),
+    };
+
+    Ok(ap_cmd)
+}
+
+#[test]
+fn arinc429_bnr_words() {
+
+    // Altitude 10000 ft, SDI 1, normal operation : label 203 reversed (0xC1), data 10000 << 11.
+    let word: Arinc429Word = Arinc429Word::encode_bnr(&PRESSURE_ALTITUDE, 1, 10_000.0, Ssm::NormalOperation);
+    assert_eq!(0o203, word.label());
+    assert_eq!(0xC1, word.0 & 0xFF);
+    assert_eq!(1, word.sdi());
+    assert_eq!(Ssm::NormalOperation, word.ssm());
+    assert_eq!(10_000 << 11, word.0 & 0x0FFF_F800);
+    assert!(word.parity_ok());
+    assert_eq!(Ok(10_000.0), word.decode_bnr(&PRESSURE_ALTITUDE));
+
+    // Negative values (two's complement), clamped to the range.
+    let word: Arinc429Word = Arinc429Word::encode_bnr(&ROLL_ANGLE, 0, -30.0, Ssm::NormalOperation);
+    assert!((word.decode_bnr(&ROLL_ANGLE).unwrap() + 30.0).abs() <= ROLL_ANGLE.resolution());
+    let word: Arinc429Word = Arinc429Word::encode_bnr(&COMPUTED_AIRSPEED, 0, 2000.0, Ssm::NormalOperation);
+    assert_eq!(Ok(1024.0 - COMPUTED_AIRSPEED.resolution()), word.decode_bnr(&COMPUTED_AIRSPEED));
+
+    // Corrupted bit.
+    let corrupted: Arinc429Word = Arinc429Word(word.0 ^ (1 << 15));
+    assert_eq!(Err(Arinc429Error::Parity(corrupted.0)), corrupted.decode_bnr(&COMPUTED_AIRSPEED));
+}
+
+#[test]
+fn arinc429_adc_and_ap_round_trip() {
+
+    use crate::bus::ParamValidity;
+
+    let mut adc_data: AdcDataMessage = AdcDataMessage::new();
+    adc_data.ias = 250.3;
+    adc_data.alt_msl = 12_345.0;
+    adc_data.pressure_alt = 12_100.0;
+    adc_data.mach = 0.52;
+    adc_data.tas = 305.7;
+    adc_data.vs = -1_200.0;
+    adc_data.sat = -9.5;
+    adc_data.aoa = 3.2;
+    adc_data.heading = 271.5;
+    adc_data.pitch_angle = 2.5;
+    adc_data.roll_angle = -15.0;
+    for param in AdcParameter::ALL {
+        adc_data.validity.set(param, ParamValidity { status: ParamStatus::Valid, timestamp_ms: Some(1000) });
+    }
+    adc_data.validity.set(AdcParameter::Aoa, ParamValidity { status: ParamStatus::OutOfRange, timestamp_ms: Some(1000) });
+
+    let words: Vec<Arinc429Word> = encode_adc(&adc_data, 1);
+    assert_eq!(11, words.len());
+    assert_eq!(Ssm::FailureWarning, words.iter().find(|w| w.label() == 0o221).unwrap().ssm());
+
+    for (word, adc_word) in words.iter().zip(ADC_WORDS.iter()) {
+        assert_eq!(adc_word.format.label, word.label());
+        let error: f32 = wrap_180(word.decode_bnr(&adc_word.format).unwrap() - (adc_word.get)(&adc_data)).abs();
+        assert!(error <= adc_word.format.resolution(), "label {:03o} error {}", adc_word.format.label, error);
+    }
+    assert!((words[8].decode_bnr(&MAGNETIC_HEADING).unwrap().rem_euclid(360.0) - 271.5).abs() < 0.01);
+    assert_eq!(Ssm::NormalOperation, words[3].ssm());
+
+    let mut ap_state: APStateMessage = APStateMessage::new();
+    ap_state.heading = 350.0;
+    ap_state.vs = -800.0;
+    let words: Vec<Arinc429Word> = encode_ap(&ap_state, 1);
+    assert!((words[0].decode_bnr(&SELECTED_HEADING).unwrap().rem_euclid(360.0) - 350.0).abs() < 0.05);
+
+    let ap_cmds: Vec<Option<APCmdPayload>> = words.into_iter().map(|word| decode_ap_word(word).unwrap()).collect();
+    assert!(ap_cmds[0].is_none());
+    assert!(matches!(ap_cmds[1], Some(APCmdPayload::SetAlt(alt)) if alt == 15_000.0));
+    assert!(matches!(ap_cmds[2], Some(APCmdPayload::SetSpeed{ speed, unit: SpeedUnit::IAS }) if speed == 250.0));
+    assert!(matches!(ap_cmds[3], Some(APCmdPayload::SetVs(vs)) if vs == -800.0));
+
+    let unknown: Arinc429Word = Arinc429Word::encode_bnr(&BnrFormat { label: 0o377, range: 1.0, sig_bits: 10 }, 0, 0.0, Ssm::NormalOperation);
+    assert_eq!(Err(Arinc429Error::UnknownLabel(0o377)), decode_ap_word(unknown));
+}
@@ -0,0 +1,275 @@
+/**
+ * ARINC 429 transmitter
+ * Sends the ADC words (on each ADC data) and the AP selected values words (on each AP state) as raw 32 bits words
+ * over UDP, one datagram per message, for the avionics test benches.
+ * Receives the AP selected values words of the test bench (FCU) the same way : a selected value changed, with the SDI
+ * of the config and a normal operation SSM, is published as an AP command.
+ * Config ("arinc429" section) :
+ * - address (127.0.0.1:49200) : destination of the datagrams
+ * - listen (any port) : local address receiving the words of the test bench
+ * - sdi (0) : SDI of the words
+ * - byte_order (little) : little or big endian words
+ */
+extern crate yaml_rust;
+
+use std::collections::HashMap;
+use std::io::{self, ErrorKind};
+use std::net::UdpSocket;
+use std::time::Duration;
+use yaml_rust::Yaml;
+
+use crate::avionics::arinc429::{decode_ap_word, encode_adc, encode_ap, Arinc429Word, Ssm};
+use crate::bus::{APCmdPayload, Bus, BusMessage, OverflowPolicy, Publisher, QueueConfig, SourceId, Subscription, Topic};
+use crate::lifecycle::Lifecycle;
+
+const DEFAULT_ARINC429_ADDRESS: &str = "127.0.0.1:49200";
+const DEFAULT_ARINC429_LISTEN: &str = "0.0.0.0:0";
+const ARINC429_QUEUE: QueueConfig = QueueConfig { capacity: 64, overflow: OverflowPolicy::DropOldest };
+
+/// Bus wait of a transmitter cycle, received words and lifecycle checked in between.
+const ARINC429_POLL_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ByteOrder {
+    Little,
+    Big,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arinc429Config {
+    pub address: String,
+    pub listen: String,
+    pub sdi: u8,
+    pub byte_order: ByteOrder,
+}
+
+impl From<&Yaml> for Arinc429Config {
+
+    /// "arinc429" section.
+    fn from(config: &Yaml) -> Self {
+
+        let byte_order: ByteOrder = match config["byte_order"].as_str() {
+            Some("big") => ByteOrder::Big,
+            Some("little") | None => ByteOrder::Little,
+            Some(byte_order) => {
+                println!("[A429] Unknown byte order : {}, little used", byte_order);
+                ByteOrder::Little
+            }
+        };
+
+        Arinc429Config {
+            address: config["address"].as_str().unwrap_or(DEFAULT_ARINC429_ADDRESS).to_string(),
+            listen: config["listen"].as_str().unwrap_or(DEFAULT_ARINC429_LISTEN).to_string(),
+            sdi: config["sdi"].as_i64().filter(|sdi| (0..=3).contains(sdi)).unwrap_or(0) as u8,
+            byte_order,
+        }
+    }
+}
+
+/// Raw datagram of the words.
+pub fn to_datagram(words: &[Arinc429Word], byte_order: ByteOrder) -> Vec<u8> {
+
+    words.iter()
+        .flat_map(|word| match byte_order {
+            ByteOrder::Little => word.0.to_le_bytes(),
+            ByteOrder::Big => word.0.to_be_bytes(),
+        })
+        .collect()
+}
+
+/// Words of a raw datagram, the trailing bytes of an incomplete word ignored.
+pub fn from_datagram(data: &[u8], byte_order: ByteOrder) -> Vec<Arinc429Word> {
+
+    data.chunks_exact(4)
+        .map(|bytes| {
+            let bytes: [u8; 4] = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            match byte_order {
+                ByteOrder::Little => Arinc429Word(u32::from_le_bytes(bytes)),
+                ByteOrder::Big => Arinc429Word(u32::from_be_bytes(bytes)),
+            }
+        })
+        .collect()
+}
+
+pub struct Arinc429Transmitter {
+    config: Arinc429Config,
+    socket: UdpSocket,
+    subscription: Subscription,
+    publisher: Publisher,
+    /// Last received word per label : a selected value commanded on a change only.
+    received: HashMap<u8, u32>,
+}
+
+impl Arinc429Transmitter {
+
+    pub fn bind(config: Arinc429Config, bus: &Bus) -> io::Result<Arinc429Transmitter> {
+
+        let socket: UdpSocket = UdpSocket::bind(&config.listen)?;
+        socket.set_nonblocking(true)?;
+
+        println!("[A429] Transmitting to {} (SDI {}), receiving on {}", config.address, config.sdi, socket.local_addr()?);
+
+        Ok(Arinc429Transmitter {
+            config,
+            socket,
+            subscription: bus.subscribe("A429", &[Topic::AdcData, Topic::ApState], ARINC429_QUEUE),
+            publisher: bus.publisher(SourceId::Arinc429),
+            received: HashMap::new(),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn local_addr(&self) -> std::net::SocketAddr {
+
+        self.socket.local_addr().unwrap()
+    }
+
+    /// AP commands of the received words which changed a selected value.
+    fn ap_commands(&mut self, words: &[Arinc429Word]) -> Vec<APCmdPayload> {
+
+        let mut ap_cmds: Vec<APCmdPayload> = Vec::new();
+
+        for word in words {
+            if word.sdi() != self.config.sdi || word.ssm() != Ssm::NormalOperation {
+                continue;
+            }
+            if self.received.insert(word.label(), word.0) == Some(word.0) {
+                continue;
+            }
+
+            match decode_ap_word(*word) {
+                Ok(Some(ap_cmd)) => ap_cmds.push(ap_cmd),
+                Ok(None) => (),
+                Err(e) => {
+                    println!("[A429] Word ignored : {}", e);
+                    self.received.remove(&word.label());
+                }
+            }
+        }
+
+        ap_cmds
+    }
+
+    /// Publish the AP commands of the words received since the last call.
+    fn receive(&mut self) {
+
+        let mut data: [u8; 1024] = [0; 1024];
+
+        loop {
+            match self.socket.recv(&mut data) {
+                Ok(size) => {
+                    let words: Vec<Arinc429Word> = from_datagram(&data[..size], self.config.byte_order);
+                    for ap_cmd in self.ap_commands(&words) {
+                        self.publisher.publish(BusMessage::APCmd(ap_cmd));
+                    }
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                // ICMP port unreachable of a test bench not started (Windows), nothing received.
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                Err(e) => {
+                    println!("[A429] Receive error : {:?}", e);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Publish the received AP commands, then send the words of the bus messages received during `timeout` at most.
+    pub fn poll(&mut self, timeout: Duration) {
+
+        self.receive();
+
+        if let Some(envelope) = self.subscription.recv_timeout(timeout) {
+
+            let words: Vec<Arinc429Word> = match envelope.message {
+                BusMessage::AdcData(adc_data) => encode_adc(&adc_data, self.config.sdi),
+                BusMessage::APState(ap_state) => encode_ap(&ap_state, self.config.sdi),
+                _ => return
+            };
+
+            // Test bench not started : nothing to report.
+            let _ = self.socket.send_to(&to_datagram(&words, self.config.byte_order), &self.config.address);
+        }
+    }
+
+    pub fn run(&mut self, lifecycle: &Lifecycle) {
+
+        while lifecycle.is_running() {
+            self.poll(ARINC429_POLL_TIMEOUT);
+        }
+    }
+}
+
+#[test]
+fn arinc429_transmitter_loopback() {
+
+    use crate::avionics::arinc429::PRESSURE_ALTITUDE;
+    use crate::bus::AdcDataMessage;
+
+    let bench: UdpSocket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    bench.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+
+    let bus: Bus = Bus::new();
+    let adc: Publisher = bus.publisher(SourceId::Adc);
+    let config: Arinc429Config = Arinc429Config { address: bench.local_addr().unwrap().to_string(), listen: "127.0.0.1:0".to_string(), sdi: 2, byte_order: ByteOrder::Big };
+    let mut transmitter: Arinc429Transmitter = Arinc429Transmitter::bind(config, &bus).unwrap();
+
+    let mut adc_data: AdcDataMessage = AdcDataMessage::new();
+    adc_data.pressure_alt = 8_500.0;
+    adc.publish(BusMessage::AdcData(Box::new(adc_data)));
+    transmitter.poll(Duration::from_millis(100));
+
+    let mut data: [u8; 256] = [0; 256];
+    let size: usize = bench.recv(&mut data).unwrap();
+    assert_eq!(11 * 4, size);
+
+    let words: Vec<Arinc429Word> = from_datagram(&data[..size], ByteOrder::Big);
+    let altitude: &Arinc429Word = words.iter().find(|word| word.label() == PRESSURE_ALTITUDE.label).unwrap();
+    assert_eq!(2, altitude.sdi());
+    assert_eq!(Ok(8_500.0), altitude.decode_bnr(&PRESSURE_ALTITUDE));
+}
+
+#[test]
+fn arinc429_selected_values_received() {
+
+    use crate::avionics::arinc429::{SELECTED_ALTITUDE, SELECTED_HEADING, SELECTED_VERTICAL_SPEED};
+
+    let bench: UdpSocket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    let bus: Bus = Bus::new();
+    let ap_cmds: Subscription = bus.subscribe("AP", &[Topic::ApCmd], QueueConfig { capacity: 8, overflow: OverflowPolicy::DropOldest });
+    let config: Arinc429Config = Arinc429Config { address: bench.local_addr().unwrap().to_string(), listen: "127.0.0.1:0".to_string(), sdi: 1, byte_order: ByteOrder::Little };
+    let mut transmitter: Arinc429Transmitter = Arinc429Transmitter::bind(config, &bus).unwrap();
+
+    // Selected heading not commanded, other SDI and no computed data ignored.
+    let words: [Arinc429Word; 4] = [
+        Arinc429Word::encode_bnr(&SELECTED_HEADING, 1, 90.0, Ssm::NormalOperation),
+        Arinc429Word::encode_bnr(&SELECTED_ALTITUDE, 1, 12_000.0, Ssm::NormalOperation),
+        Arinc429Word::encode_bnr(&SELECTED_ALTITUDE, 2, 8_000.0, Ssm::NormalOperation),
+        Arinc429Word::encode_bnr(&SELECTED_VERTICAL_SPEED, 1, -800.0, Ssm::NoComputedData),
+    ];
+    let datagram: Vec<u8> = to_datagram(&words, ByteOrder::Little);
+
+    // Same words twice : commanded once.
+    bench.send_to(&datagram, transmitter.local_addr()).unwrap();
+    bench.send_to(&datagram, transmitter.local_addr()).unwrap();
+    let mut ap_cmd: Option<crate::bus::BusEnvelope> = None;
+    for _ in 0..50 {
+        transmitter.poll(Duration::from_millis(5));
+        ap_cmd = ap_cmds.recv_timeout(Duration::ZERO);
+        if ap_cmd.is_some() {
+            break;
+        }
+    }
+    let ap_cmd = ap_cmd.unwrap();
+    assert_eq!(SourceId::Arinc429, ap_cmd.source);
+    assert!(matches!(ap_cmd.message, BusMessage::APCmd(APCmdPayload::SetAlt(alt)) if alt == 12_000.0));
+
+    transmitter.poll(Duration::from_millis(20));
+    assert!(ap_cmds.recv_timeout(Duration::ZERO).is_none());
+
+    // Selected altitude changed.
+    let word: Arinc429Word = Arinc429Word::encode_bnr(&SELECTED_ALTITUDE, 1, 13_000.0, Ssm::NormalOperation);
+    assert_eq!(vec![APCmdPayload::SetAlt(13_000.0)], transmitter.ap_commands(&[word]));
+    assert!(transmitter.ap_commands(&[word]).is_empty());
+}
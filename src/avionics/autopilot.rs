@@ -1,54 +1,214 @@
 
+pub mod pitch_controller;
+pub mod roll_controller;
+
 pub mod autopilot {
 
-    use std::sync::{Arc, mpsc::{Sender, Receiver, TryRecvError}};
-    use crate::{bus::{AdcDataMessage, BusMessage, APStateMessage, APCmdPayload}};
+    extern crate yaml_rust;
+
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use yaml_rust::Yaml;
+    use crate::{bus::{AdcDataMessage, AdcParameter, AdcValidity, AhrsDataMessage, AhrsStatus, Bus, BusEnvelope, BusMessage, Publisher, QueueConfig, SequenceMonitor, SourceId, Subscription, Topic, APStateMessage, APCmdPayload, LinkState, NavStateMessage}};
+
+    use crate::avionics::autopilot::pitch_controller::{PitchController, PitchControllerConfig};
+    use crate::avionics::autopilot::roll_controller::{RollController, RollControllerConfig};
+    use crate::avionics::navigation::Navigation;
+    use crate::avionics::navigation::lnav::LnavConfig;
+    use crate::avionics::navigation::vnav::VnavConfig;
+    use crate::{flight_ctrl::{FlightCtrlsCommand, FlightCtrlsProvider}};
+    use crate::scheduler::EventDrivenTask;
 
-    use crate::{flight_ctrl::FlightCtrlsProvider};
+    /// ADC and AHRS data older than this delay are not used by the control laws (bus congested, thread stalled).
+    const MAX_ADC_DATA_AGE: Duration = Duration::from_millis(250);
+
+    /// Topics handled by the AP.
+    pub const AP_TOPICS: [Topic; 5] = [Topic::AdcData, Topic::AhrsData, Topic::LinkState, Topic::ApCmd, Topic::FlightPlan];
 
     pub struct Autopilot {
 
         pub ap_state: APStateMessage,
         pub flcs: Arc::<dyn FlightCtrlsProvider + Send + Sync>,
-        pub rx_ap: Receiver<BusMessage>,
-        pub ap_tx_gui: Sender<BusMessage>,
+        pub subscription: Subscription,
+        pub publisher: Publisher,
+        pub seq_monitor: SequenceMonitor,
+        // Flight plan and LNAV/VNAV guidance, bank command flown by the roll controller in LNAV mode,
+        // vertical speed command by the pitch controller in VNAV mode.
+        pub navigation: Navigation,
+        pub nav_state: Option<NavStateMessage>,
+        pub roll_controller: RollController,
+        pub pitch_controller: PitchController,
+        pub adc_data: Option<AdcDataMessage>,
+        // Attitude flown by the control laws, the sim attitude of the ADC data is used without AHRS inertial data.
+        pub ahrs_data: Option<AhrsDataMessage>,
+        // Axes driven by the last control cycle.
+        pub roll_driven: bool,
+        pub pitch_driven: bool,
     }
 
     impl Autopilot {
 
-        pub const fn from(
+        /// AP with the "autopilot" config section.
+        pub fn from(
             flcs: Arc::<dyn FlightCtrlsProvider + Send + Sync>,
-            rx_ap: Receiver<BusMessage>,
-            ap_tx_gui: Sender<BusMessage>) -> Self {
+            bus: &Bus,
+            queue_config: QueueConfig,
+            ap_config: &Yaml) -> Self {
 
             Self { 
                 ap_state: APStateMessage::new(), 
                 flcs: flcs, 
-                rx_ap: rx_ap, 
-                ap_tx_gui: ap_tx_gui }
+                subscription: bus.subscribe("AP", &AP_TOPICS, queue_config),
+                publisher: bus.publisher(SourceId::Ap),
+                seq_monitor: SequenceMonitor::new(),
+                navigation: Navigation::new(LnavConfig::from(&ap_config["lnav"]), VnavConfig::from(&ap_config["vnav"])),
+                nav_state: None,
+                roll_controller: RollController::new(RollControllerConfig::from(&ap_config["roll"])),
+                pitch_controller: PitchController::new(PitchControllerConfig::from(&ap_config["pitch"])),
+                adc_data: None,
+                ahrs_data: None,
+                roll_driven: false,
+                pitch_driven: false }
         }
 
-        pub fn handle_bus_message(&mut self) {
+        /// Check the envelope (lost messages, age) and handle its message.
+        pub fn handle_bus_envelope(&mut self, envelope: BusEnvelope) {
 
-            match self.rx_ap.try_recv() {
-                Ok(bus_message) => {
-                    match bus_message {
-                        BusMessage::AdcData(adc_data) => self.handle_adc_data_message(adc_data),
-                        BusMessage::APCmd(ap_cmd) => self.handle_ap_cmd_message(ap_cmd),
-                        _ => (),
-                    };
-                },
-                Err(e) => {
-                    match e {
-                        TryRecvError::Empty => (),
-                        TryRecvError::Disconnected => println!("[AP] Message processing error : {:?}", e)
-                    }   
+            let lost: u64 = self.seq_monitor.check(&envelope);
+            if lost > 0 {
+                println!("[AP] {} message(s) lost from {} ({} since start, {} dropped by the {} queue)",
+                    lost, envelope.source, self.seq_monitor.lost(), self.subscription.dropped(), self.subscription.name());
+            }
+
+            if let BusMessage::AdcData(_) | BusMessage::AhrsData(_) = envelope.message {
+                if envelope.age() > MAX_ADC_DATA_AGE {
+                    println!("[AP] Data from {} too old : {} ms", envelope.source, envelope.age().as_millis());
+                    return;
                 }
             }
+
+            self.handle_bus_message(envelope.message);
+        }
+
+        pub fn handle_bus_message(&mut self, bus_message: BusMessage) {
+
+            match bus_message {
+                BusMessage::AdcData(adc_data) => self.handle_adc_data_message(*adc_data),
+                BusMessage::AhrsData(ahrs_data) => self.handle_ahrs_data_message(ahrs_data),
+                BusMessage::APCmd(ap_cmd) => self.handle_ap_cmd_message(ap_cmd),
+                BusMessage::LinkState(link_state) => self.handle_link_state_message(link_state),
+                BusMessage::FlightPlan(flight_plan) => self.navigation.load(flight_plan),
+                _ => (),
+            };
         }
 
         fn handle_adc_data_message(&mut self, adc_data: AdcDataMessage) {
             //println!("[AP][DATA] {:?}", adc_data);
+
+            self.check_parameters_validity(&adc_data.validity);
+
+            self.nav_state = self.navigation.update(&adc_data, self.ap_state.alt);
+
+            if self.ap_state.lnav_mode {
+                let lnav_lost: Option<&str> = match self.nav_state {
+                    _ if !self.navigation.is_active() => Some("Flight plan flown"),
+                    None => Some("Position invalid"),
+                    Some(_) => None
+                };
+
+                if let Some(reason) = lnav_lost {
+                    println!("[AP] {}, LNAV mode dropped", reason);
+                    self.ap_state.lnav_mode = false;
+                    self.notify_observers();
+                }
+            }
+
+            if self.ap_state.vnav_mode && self.nav_state.and_then(|nav_state| nav_state.vnav).is_none() {
+                println!("[AP] No vertical guidance, VNAV mode dropped");
+                self.ap_state.vnav_mode = false;
+                self.notify_observers();
+            }
+
+            self.adc_data = Some(adc_data);
+
+            self.check_attitude();
+        }
+
+        fn handle_ahrs_data_message(&mut self, ahrs_data: AhrsDataMessage) {
+
+            self.ahrs_data = Some(ahrs_data);
+
+            self.check_attitude();
+        }
+
+        /**
+         * Attitude (pitch, roll deg) flown by the control laws : AHRS attitude once aligned, none while aligning,
+         * sim attitude of the ADC data when the AHRS has no inertial data (provider without body rates/accelerations).
+         */
+        fn attitude(&self) -> Option<(f32, f32)> {
+
+            match (&self.ahrs_data, &self.adc_data) {
+                (Some(ahrs_data), _) if ahrs_data.status == AhrsStatus::Aligned => Some((ahrs_data.pitch, ahrs_data.roll)),
+                (Some(ahrs_data), _) if ahrs_data.status == AhrsStatus::Aligning => None,
+                (_, Some(adc_data)) if adc_data.validity.all_valid(&[AdcParameter::Pitch, AdcParameter::Roll]) => {
+                    Some((adc_data.pitch_angle, adc_data.roll_angle))
+                },
+                _ => None
+            }
+        }
+
+        // Never control without attitude : disengage.
+        fn check_attitude(&mut self) {
+
+            if self.ap_state.engaged && self.attitude().is_none() {
+                println!("[AP] Attitude invalid");
+                self.disengage();
+                self.notify_observers();
+            }
+        }
+
+        // Never control on invalid parameters : drop the modes using them.
+        fn check_parameters_validity(&mut self, validity: &AdcValidity) {
+
+            if !self.ap_state.engaged {
+                return;
+            }
+
+            let mut modes_changed: bool = false;
+
+            if self.ap_state.alt_hold_mode && !validity.all_valid(&[AdcParameter::AltMsl, AdcParameter::Vs]) {
+                println!("[AP] Altitude invalid, ALT HOLD mode dropped");
+                self.ap_state.alt_hold_mode = false;
+                modes_changed = true;
+            }
+
+            if self.ap_state.vnav_mode && !validity.all_valid(&[AdcParameter::AltMsl, AdcParameter::Vs]) {
+                println!("[AP] Altitude invalid, VNAV mode dropped");
+                self.ap_state.vnav_mode = false;
+                modes_changed = true;
+            }
+
+            if self.ap_state.vs_mode && !validity.is_valid(AdcParameter::Vs) {
+                println!("[AP] Vertical speed invalid, VS mode dropped");
+                self.ap_state.vs_mode = false;
+                modes_changed = true;
+            }
+
+            if self.ap_state.heading_mode && !validity.is_valid(AdcParameter::Heading) {
+                println!("[AP] Heading invalid, HDG mode dropped");
+                self.ap_state.heading_mode = false;
+                modes_changed = true;
+            }
+
+            if self.ap_state.auto_throttle_mode && !validity.is_valid(AdcParameter::Ias) {
+                println!("[AP] Speed invalid, A/THR mode dropped");
+                self.ap_state.auto_throttle_mode = false;
+                modes_changed = true;
+            }
+
+            if modes_changed {
+                self.notify_observers();
+            }
         }
 
         fn handle_ap_cmd_message(&mut self, ap_cmd: APCmdPayload) {
@@ -57,30 +217,278 @@ pub mod autopilot {
 
             match ap_cmd {
                 APCmdPayload::SetAlt(alt) => self.set_ap_alt(alt),
+                APCmdPayload::APEngage(true) => self.engage(),
+                APCmdPayload::APEngage(false) => self.disengage(),
+                APCmdPayload::EnableLnavMode(lnav_mode) => self.set_lnav_mode(lnav_mode),
+                APCmdPayload::EnableVnavMode(vnav_mode) => self.set_vnav_mode(vnav_mode),
                 _ => ()
             }
 
-            let ap_state: APStateMessage = self.ap_state.clone();
+            self.notify_observers();
+        }
+
+        fn handle_link_state_message(&mut self, link_state: LinkState) {
+
+            println!("[AP][LINK] {:?}", link_state);
+
+            if link_state == LinkState::Lost && self.ap_state.engaged {
+                self.disengage();
+                self.notify_observers();
+            }
+        }
+
+        // Never engaged without attitude (AHRS aligning, invalid sim attitude).
+        fn engage(&mut self) {
+
+            if self.attitude().is_none() {
+                println!("[AP] Attitude invalid, AP not engaged");
+                return;
+            }
+
+            self.ap_state.engaged = true;
+        }
+
+        // Sim link lost or attitude invalid : the aircraft can't be controlled anymore, drop AP and all modes.
+        fn disengage(&mut self) {
+
+            println!("[AP] Disengaged");
+
+            self.ap_state.engaged = false;
+            self.ap_state.alt_hold_mode = false;
+            self.ap_state.vs_mode = false;
+            self.ap_state.heading_mode = false;
+            self.ap_state.auto_throttle_mode = false;
+            self.ap_state.lnav_mode = false;
+            self.ap_state.vnav_mode = false;
+        }
+
+        // LNAV flies the flight plan from the aircraft position, replaces the heading hold.
+        fn set_lnav_mode(&mut self, lnav_mode: bool) {
+
+            if lnav_mode && !(self.navigation.is_active() && self.nav_state.is_some()) {
+                println!("[AP] LNAV not available : no flight plan or no position");
+                return;
+            }
+
+            self.ap_state.lnav_mode = lnav_mode;
+            if lnav_mode {
+                self.ap_state.heading_mode = false;
+            }
+        }
+
+        // VNAV flies the flight plan vertical profile, replaces the altitude hold and VS modes.
+        fn set_vnav_mode(&mut self, vnav_mode: bool) {
+
+            if vnav_mode && self.nav_state.and_then(|nav_state| nav_state.vnav).is_none() {
+                println!("[AP] VNAV not available : no altitude constraint or no position");
+                return;
+            }
 
-            self.ap_tx_gui.send(BusMessage::APState(ap_state)).unwrap();
+            self.ap_state.vnav_mode = vnav_mode;
+            if vnav_mode {
+                self.ap_state.alt_hold_mode = false;
+                self.ap_state.vs_mode = false;
+            }
         }
 
         fn set_ap_alt(&mut self, alt: f32) {
 
             self.ap_state.alt = alt;
-
-            self.notify_observers();
         }
 
-        // Notify GUI
+        // Publish the AP state (GUI)
         fn notify_observers(&self) {
 
             let ap_state: APStateMessage = self.ap_state.clone();
-            self.ap_tx_gui.send(BusMessage::APState(ap_state)).unwrap();
+            self.publisher.publish(BusMessage::APState(ap_state));
+        }
+
+    }
+
+    impl EventDrivenTask for Autopilot {
+
+        /// Handle the ADC data and GUI AP commands as soon as received, until the next control cycle.
+        fn handle_events_until(&mut self, deadline: Instant) {
+
+            loop {
+                let now: Instant = Instant::now();
+                if now >= deadline {
+                    return;
+                }
+
+                match self.subscription.recv_timeout(deadline - now) {
+                    Some(envelope) => self.handle_bus_envelope(envelope),
+                    None => return,
+                }
+            }
         }
 
+        /// Control cycle, `dt` is the time (s) since the previous cycle.
+        fn step(&mut self, dt: f32) {
+
+            let (bank_cmd, vs_cmd): (Option<f32>, Option<f32>) = match (&self.adc_data, self.nav_state) {
+                (Some(_), Some(nav_state)) if self.ap_state.engaged => (
+                    Some(nav_state.bank_cmd).filter(|_| self.ap_state.lnav_mode),
+                    nav_state.vnav.map(|vnav| vnav.vs_cmd).filter(|_| self.ap_state.vnav_mode)),
+                _ => (None, None)
+            };
+
+            let mut cmd: FlightCtrlsCommand = FlightCtrlsCommand::new();
+
+            if let (Some(adc_data), Some((pitch, roll))) = (&self.adc_data, self.attitude()) {
+                cmd.roll = bank_cmd.map(|bank_cmd| self.roll_controller.update(bank_cmd, roll, dt));
+                cmd.pitch = vs_cmd.map(|vs_cmd| self.pitch_controller.update(vs_cmd, adc_data.vs, pitch, dt));
+            }
+
+            if cmd.roll.is_none() {
+                self.roll_controller.reset();
+            }
+            if cmd.pitch.is_none() {
+                self.pitch_controller.reset();
+            }
+
+            let roll_driven: bool = cmd.roll.is_some();
+            let pitch_driven: bool = cmd.pitch.is_some();
+
+            // Axes no more driven given back once at neutral : the simulator holds the last command otherwise.
+            if !roll_driven && self.roll_driven {
+                cmd.roll = Some(0.0);
+            }
+            if !pitch_driven && self.pitch_driven {
+                cmd.pitch = Some(0.0);
+            }
+
+            if cmd.roll.is_some() || cmd.pitch.is_some() {
+                self.flcs.send(&cmd);
+            }
+            self.roll_driven = roll_driven;
+            self.pitch_driven = pitch_driven;
+
+            if let Some(nav_state) = self.nav_state {
+                self.publisher.publish(BusMessage::NavState(nav_state));
+            }
+        }
     }
 
 }
 
 pub use autopilot::Autopilot;
+
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+#[cfg(test)]
+use yaml_rust::Yaml;
+#[cfg(test)]
+use crate::avionics::autopilot::roll_controller::{RollController, RollControllerConfig};
+#[cfg(test)]
+use crate::bus::{AdcDataMessage, AdcParameter, AhrsDataMessage, AhrsStatus, APCmdPayload, Bus, BusMessage, NavStateMessage, OverflowPolicy, ParamStatus, ParamValidity, QueueConfig, Subscription, Topic};
+#[cfg(test)]
+use crate::flight_ctrl::{FlightCtrlsCommand, FlightCtrlsProvider};
+#[cfg(test)]
+use crate::scheduler::EventDrivenTask;
+
+#[cfg(test)]
+struct TestFlightCtrlsProvider {
+    commands: Mutex<Vec<FlightCtrlsCommand>>,
+}
+
+#[cfg(test)]
+impl FlightCtrlsProvider for TestFlightCtrlsProvider {
+
+    fn send(&self, cmd: &FlightCtrlsCommand) {
+
+        self.commands.lock().unwrap().push(cmd.clone());
+    }
+
+    fn release(&self) {
+    }
+}
+
+#[cfg(test)]
+fn test_autopilot() -> (Autopilot, Arc<TestFlightCtrlsProvider>) {
+
+    let flcs = Arc::new(TestFlightCtrlsProvider { commands: Mutex::new(Vec::new()) });
+    let bus: Bus = Bus::new();
+    let queue_config: QueueConfig = QueueConfig { capacity: 8, overflow: OverflowPolicy::DropOldest };
+
+    (Autopilot::from(flcs.clone(), &bus, queue_config, &Yaml::BadValue), flcs)
+}
+
+#[cfg(test)]
+fn test_adc_data(pitch: f32, roll: f32) -> AdcDataMessage {
+
+    let mut adc_data: AdcDataMessage = AdcDataMessage::new();
+    adc_data.pitch_angle = pitch;
+    adc_data.roll_angle = roll;
+
+    for param in AdcParameter::ALL {
+        adc_data.validity.set(param, ParamValidity { status: ParamStatus::Valid, timestamp_ms: Some(1_000) });
+    }
+
+    adc_data
+}
+
+#[test]
+fn autopilot_ahrs_attitude() {
+
+    let (mut autopilot, flcs) = test_autopilot();
+
+    // No attitude yet : not engaged.
+    autopilot.handle_bus_message(BusMessage::APCmd(APCmdPayload::APEngage(true)));
+    assert!(!autopilot.ap_state.engaged);
+
+    // Sim attitude without AHRS inertial data.
+    autopilot.handle_bus_message(BusMessage::AdcData(Box::new(test_adc_data(2.0, 5.0))));
+    autopilot.handle_bus_message(BusMessage::APCmd(APCmdPayload::APEngage(true)));
+    assert!(autopilot.ap_state.engaged);
+
+    // Aligned AHRS attitude flown by the roll controller.
+    let mut ahrs_data: AhrsDataMessage = AhrsDataMessage::new();
+    ahrs_data.status = AhrsStatus::Aligned;
+    ahrs_data.pitch = 1.0;
+    ahrs_data.roll = 10.0;
+    autopilot.handle_bus_message(BusMessage::AhrsData(ahrs_data));
+
+    autopilot.nav_state = Some(NavStateMessage {
+        active_waypoint: Some(1), desired_track: 90.0, cross_track_nm: 0.0, distance_to_go_nm: 10.0, bank_cmd: 20.0, vnav: None });
+    autopilot.ap_state.lnav_mode = true;
+    autopilot.step(0.05);
+
+    let expected_roll: f32 = RollController::new(RollControllerConfig::from(&Yaml::BadValue)).update(20.0, 10.0, 0.05);
+    assert_eq!(Some(expected_roll), flcs.commands.lock().unwrap().last().unwrap().roll);
+
+    // AHRS realigning : no attitude, AP disengaged, engage rejected.
+    ahrs_data.status = AhrsStatus::Aligning;
+    autopilot.handle_bus_message(BusMessage::AhrsData(ahrs_data));
+    assert!(!autopilot.ap_state.engaged);
+    autopilot.handle_bus_message(BusMessage::APCmd(APCmdPayload::APEngage(true)));
+    assert!(!autopilot.ap_state.engaged);
+
+    // Roll given back once at neutral, pitch never driven.
+    autopilot.step(0.05);
+    let commands: usize = flcs.commands.lock().unwrap().len();
+    let release: FlightCtrlsCommand = flcs.commands.lock().unwrap().last().unwrap().clone();
+    assert_eq!((Some(0.0), None), (release.roll, release.pitch));
+    autopilot.step(0.05);
+    assert_eq!(commands, flcs.commands.lock().unwrap().len());
+}
+
+#[test]
+fn autopilot_command_publishes_state_once() {
+
+    let flcs = Arc::new(TestFlightCtrlsProvider { commands: Mutex::new(Vec::new()) });
+    let bus: Bus = Bus::new();
+    let queue_config: QueueConfig = QueueConfig { capacity: 8, overflow: OverflowPolicy::DropOldest };
+    let mut autopilot: Autopilot = Autopilot::from(flcs, &bus, queue_config, &Yaml::BadValue);
+    let gui: Subscription = bus.subscribe("gui", &[Topic::ApState], queue_config);
+
+    for ap_cmd in [APCmdPayload::SetAlt(5_000.0), APCmdPayload::APEngage(false)] {
+
+        autopilot.handle_bus_message(BusMessage::APCmd(ap_cmd));
+
+        assert!(gui.try_recv().is_some());
+        assert!(gui.try_recv().is_none());
+    }
+
+    assert_eq!(5_000.0, autopilot.ap_state.alt);
+}
@@ -0,0 +1,101 @@
+/**
+ * Pitch controller
+ * Elevator command (-1.0 to 1.0, positive nose up) flying a vertical speed command :
+ * - pitch command : pitch angle corrected by the vertical speed error ("vs_gain" deg per fpm), limited to "max_pitch_deg"
+ * - elevator : proportional to the pitch error ("kp" per deg), damped by the pitch rate ("kd" per deg/s, pitch angle
+ *   derivative over the AP cycle), limited to "max_elevator"
+ *
+ * Config ("autopilot" "pitch" section).
+ */
+extern crate yaml_rust;
+
+use yaml_rust::Yaml;
+
+use crate::config::get_positive_f32;
+
+const DEFAULT_VS_GAIN: f32 = 0.005;
+const DEFAULT_MAX_PITCH_DEG: f32 = 15.0;
+const DEFAULT_KP: f32 = 0.05;
+const DEFAULT_KD: f32 = 0.02;
+const DEFAULT_MAX_ELEVATOR: f32 = 0.5;
+
+/// Above this AP cycle period the pitch rate is not derived (first cycle, thread stalled).
+const MAX_CYCLE_PERIOD_S: f32 = 1.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PitchControllerConfig {
+    pub vs_gain: f32,
+    pub max_pitch_deg: f32,
+    pub kp: f32,
+    pub kd: f32,
+    pub max_elevator: f32,
+}
+
+impl From<&Yaml> for PitchControllerConfig {
+
+    /// "autopilot" "pitch" section.
+    fn from(config: &Yaml) -> Self {
+
+        PitchControllerConfig {
+            vs_gain: get_positive_f32(&config["vs_gain"]).unwrap_or(DEFAULT_VS_GAIN),
+            max_pitch_deg: get_positive_f32(&config["max_pitch_deg"]).unwrap_or(DEFAULT_MAX_PITCH_DEG),
+            kp: get_positive_f32(&config["kp"]).unwrap_or(DEFAULT_KP),
+            kd: get_positive_f32(&config["kd"]).unwrap_or(DEFAULT_KD),
+            max_elevator: get_positive_f32(&config["max_elevator"]).unwrap_or(DEFAULT_MAX_ELEVATOR).min(1.0),
+        }
+    }
+}
+
+pub struct PitchController {
+    config: PitchControllerConfig,
+    last_pitch: Option<f32>,
+}
+
+impl PitchController {
+
+    pub fn new(config: PitchControllerConfig) -> PitchController {
+
+        PitchController { config, last_pitch: None }
+    }
+
+    /// Elevator command for the vertical speed command and vertical speed (fpm), pitch angle (deg),
+    /// `dt` is the time (s) since the previous update.
+    pub fn update(&mut self, vs_cmd: f32, vs: f32, pitch: f32, dt: f32) -> f32 {
+
+        let pitch_rate: f32 = match self.last_pitch {
+            Some(last_pitch) if dt > 0.0 && dt <= MAX_CYCLE_PERIOD_S => (pitch - last_pitch) / dt,
+            _ => 0.0
+        };
+        self.last_pitch = Some(pitch);
+
+        let pitch_cmd: f32 = (pitch + self.config.vs_gain * (vs_cmd - vs)).clamp(-self.config.max_pitch_deg, self.config.max_pitch_deg);
+
+        (self.config.kp * (pitch_cmd - pitch) - self.config.kd * pitch_rate).clamp(-self.config.max_elevator, self.config.max_elevator)
+    }
+
+    /// Pitch axis released : no pitch rate from the last driven cycle.
+    pub fn reset(&mut self) {
+
+        self.last_pitch = None;
+    }
+}
+
+#[test]
+fn pitch_controller() {
+
+    let mut controller: PitchController = PitchController::new(PitchControllerConfig::from(&Yaml::BadValue));
+
+    // 1000 fpm climb commanded in level flight : 5 deg pitch up.
+    assert!((controller.update(1000.0, 0.0, 2.0, 0.05) - 0.25).abs() < 1e-6);
+
+    // Pitching up at 10 deg/s : damped.
+    assert!((controller.update(1000.0, 0.0, 2.5, 0.05) - (0.25 - 0.2)).abs() < 1e-5);
+
+    // Pitch command limited : 14 deg nose up at most 1 deg more.
+    controller.reset();
+    assert!((controller.update(3000.0, 0.0, 14.0, 0.05) - 0.05).abs() < 1e-6);
+
+    // Elevator limited.
+    controller.reset();
+    assert_eq!(-0.5, controller.update(-6000.0, 0.0, 0.0, 0.05));
+}
@@ -0,0 +1,85 @@
+/**
+ * Roll controller
+ * Aileron command (-1.0 to 1.0) holding a bank angle command : proportional to the bank error ("kp" per deg),
+ * damped by the roll rate ("kd" per deg/s, roll angle derivative over the AP cycle), limited to "max_aileron".
+ * Config ("autopilot" "roll" section).
+ */
+extern crate yaml_rust;
+
+use yaml_rust::Yaml;
+
+use crate::config::get_positive_f32;
+
+const DEFAULT_KP: f32 = 0.04;
+const DEFAULT_KD: f32 = 0.01;
+const DEFAULT_MAX_AILERON: f32 = 0.5;
+
+/// Above this AP cycle period the roll rate is not derived (first cycle, thread stalled).
+const MAX_CYCLE_PERIOD_S: f32 = 1.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollControllerConfig {
+    pub kp: f32,
+    pub kd: f32,
+    pub max_aileron: f32,
+}
+
+impl From<&Yaml> for RollControllerConfig {
+
+    /// "autopilot" "roll" section.
+    fn from(config: &Yaml) -> Self {
+
+        RollControllerConfig {
+            kp: get_positive_f32(&config["kp"]).unwrap_or(DEFAULT_KP),
+            kd: get_positive_f32(&config["kd"]).unwrap_or(DEFAULT_KD),
+            max_aileron: get_positive_f32(&config["max_aileron"]).unwrap_or(DEFAULT_MAX_AILERON).min(1.0),
+        }
+    }
+}
+
+pub struct RollController {
+    config: RollControllerConfig,
+    last_roll: Option<f32>,
+}
+
+impl RollController {
+
+    pub fn new(config: RollControllerConfig) -> RollController {
+
+        RollController { config, last_roll: None }
+    }
+
+    /// Aileron command for the bank command and the roll angle (deg), `dt` is the time (s) since the previous update.
+    pub fn update(&mut self, bank_cmd: f32, roll: f32, dt: f32) -> f32 {
+
+        let roll_rate: f32 = match self.last_roll {
+            Some(last_roll) if dt > 0.0 && dt <= MAX_CYCLE_PERIOD_S => (roll - last_roll) / dt,
+            _ => 0.0
+        };
+        self.last_roll = Some(roll);
+
+        (self.config.kp * (bank_cmd - roll) - self.config.kd * roll_rate).clamp(-self.config.max_aileron, self.config.max_aileron)
+    }
+
+    /// Roll axis released : no roll rate from the last driven cycle.
+    pub fn reset(&mut self) {
+
+        self.last_roll = None;
+    }
+}
+
+#[test]
+fn roll_controller() {
+
+    let mut controller: RollController = RollController::new(RollControllerConfig::from(&Yaml::BadValue));
+
+    // Right bank commanded from wings level.
+    assert!((controller.update(10.0, 0.0, 0.05) - 0.4).abs() < 1e-6);
+
+    // Rolling right at 20 deg/s : damped.
+    assert!((controller.update(10.0, 1.0, 0.05) - (0.36 - 0.2)).abs() < 1e-5);
+
+    // Limited, no roll rate after a reset.
+    controller.reset();
+    assert_eq!(-0.5, controller.update(-25.0, 5.0, 0.05));
+}
@@ -0,0 +1,201 @@
+pub mod flight_plan;
+pub mod geo;
+pub mod lnav;
+pub mod vnav;
+
+pub mod navigation {
+
+    use crate::avionics::ahrs::attitude::wrap_180;
+    use crate::avionics::navigation::geo::{bearing_deg, distance_nm};
+    use crate::avionics::navigation::lnav::{bank_command, final_course, leg_guidance, track_command, turn_anticipation_nm, LegGuidance, LnavConfig};
+    use crate::avionics::navigation::vnav::{vnav_guidance, AltitudeConstraint, VnavConfig};
+    use crate::bus::{AdcDataMessage, FlightPlanMessage, GeoPosition, NavStateMessage, VnavStateMessage, Waypoint};
+
+    /**
+     * Navigation
+     * Holds the active flight plan and the active (TO) waypoint, computes the LNAV guidance from the aircraft position,
+     * the VNAV guidance from the waypoints altitude constraints, and sequences the waypoints :
+     * - the first leg goes direct to the first waypoint from the position at the first update
+     * - the next leg starts at the turn anticipation distance before the active waypoint, or once abeam the waypoint
+     * - the plan is flown once abeam the last waypoint
+     */
+    pub struct Navigation {
+        config: LnavConfig,
+        vnav_config: VnavConfig,
+        waypoints: Vec<Waypoint>,
+        active: Option<usize>,
+        leg_start: Option<GeoPosition>,
+    }
+
+    impl Navigation {
+
+        pub fn new(config: LnavConfig, vnav_config: VnavConfig) -> Self {
+
+            Self { config, vnav_config, waypoints: Vec::new(), active: None, leg_start: None }
+        }
+
+        /// Replace the flight plan, the first waypoint becomes active.
+        pub fn load(&mut self, flight_plan: FlightPlanMessage) {
+
+            println!("[NAV] Flight plan loaded : {}", flight_plan.waypoints.iter().map(|w| w.ident.as_str()).collect::<Vec<&str>>().join(" "));
+
+            self.active = if flight_plan.waypoints.is_empty() { None } else { Some(0) };
+            self.waypoints = flight_plan.waypoints;
+            self.leg_start = None;
+        }
+
+        /// Flight plan loaded and not flown yet.
+        pub fn is_active(&self) -> bool {
+
+            self.active.is_some()
+        }
+
+        /// Guidance at the aircraft position, None without position or active waypoint.
+        /// The VNAV vertical speed command levels off at the MCP altitude `mcp_alt` (ft).
+        pub fn update(&mut self, adc_data: &AdcDataMessage, mcp_alt: f32) -> Option<NavStateMessage> {
+
+            let position: GeoPosition = adc_data.position?;
+            let mut active: usize = self.active?;
+
+            // Without GNSS ground speed and track : air data (no wind correction).
+            let ground_speed: f32 = adc_data.ground_speed.unwrap_or(adc_data.tas);
+            let track: f32 = adc_data.track.unwrap_or(adc_data.heading);
+
+            let leg_start: GeoPosition = *self.leg_start.get_or_insert(position);
+            let mut leg: (GeoPosition, LegGuidance) = (leg_start, leg_guidance(&leg_start, &self.waypoints[active].position, &position));
+
+            loop {
+                let (start, guidance) = leg;
+                let to: &GeoPosition = &self.waypoints[active].position;
+
+                let anticipation: f32 = match self.waypoints.get(active + 1) {
+                    Some(next) => {
+                        let turn_angle: f32 = wrap_180(bearing_deg(to, &next.position) as f32 - final_course(&start, to));
+                        turn_anticipation_nm(ground_speed, turn_angle, &self.config)
+                    },
+                    None => 0.0
+                };
+
+                if guidance.distance_to_go_nm > anticipation {
+                    break;
+                }
+
+                println!("[NAV] {} sequenced", self.waypoints[active].ident);
+
+                if active + 1 == self.waypoints.len() {
+                    println!("[NAV] Flight plan flown");
+                    self.active = None;
+                    return Some(NavStateMessage {
+                        active_waypoint: None,
+                        desired_track: guidance.desired_track,
+                        cross_track_nm: 0.0,
+                        distance_to_go_nm: 0.0,
+                        bank_cmd: 0.0,
+                        vnav: None,
+                    });
+                }
+
+                active += 1;
+                self.active = Some(active);
+                self.leg_start = Some(*to);
+                leg = (*to, leg_guidance(to, &self.waypoints[active].position, &position));
+            }
+
+            let guidance: LegGuidance = leg.1;
+            let track_cmd: f32 = track_command(&guidance, ground_speed, &self.config);
+
+            let constraints: Vec<AltitudeConstraint> = self.altitude_constraints(active, guidance.distance_to_go_nm);
+            let vnav: Option<VnavStateMessage> = vnav_guidance(&constraints, adc_data.alt_msl, ground_speed, mcp_alt, &self.vnav_config);
+
+            Some(NavStateMessage {
+                active_waypoint: Some(active),
+                desired_track: guidance.desired_track,
+                cross_track_nm: guidance.cross_track_nm,
+                distance_to_go_nm: guidance.distance_to_go_nm,
+                bank_cmd: bank_command(track_cmd, track, &self.config),
+                vnav,
+            })
+        }
+
+        /// Waypoints altitude constraints along the route, from the distance to go to the active waypoint.
+        fn altitude_constraints(&self, active: usize, distance_to_go_nm: f32) -> Vec<AltitudeConstraint> {
+
+            let leg_nm = |i: usize| distance_nm(&self.waypoints[i].position, &self.waypoints[i + 1].position) as f32;
+
+            // Distances along the route : legs added after the active waypoint, subtracted before it.
+            let mut distances: Vec<f32> = vec![distance_to_go_nm; self.waypoints.len()];
+            for i in (0..active).rev() {
+                distances[i] = distances[i + 1] - leg_nm(i);
+            }
+            for i in active + 1..self.waypoints.len() {
+                distances[i] = distances[i - 1] + leg_nm(i - 1);
+            }
+
+            self.waypoints.iter().zip(distances)
+                        .filter_map(|(waypoint, distance_nm)| waypoint.altitude.map(|altitude| AltitudeConstraint { distance_nm, altitude }))
+                        .collect()
+        }
+    }
+}
+
+pub use navigation::Navigation;
+
+#[cfg(test)]
+fn test_adc_data(position: crate::bus::GeoPosition, track: f32) -> crate::bus::AdcDataMessage {
+
+    let mut adc_data: crate::bus::AdcDataMessage = crate::bus::AdcDataMessage::new();
+    adc_data.position = Some(position);
+    adc_data.ground_speed = Some(240.0);
+    adc_data.track = Some(track);
+    adc_data
+}
+
+#[test]
+fn navigation_sequencing() {
+
+    use yaml_rust::Yaml;
+    use crate::avionics::navigation::geo::destination;
+    use crate::avionics::navigation::lnav::LnavConfig;
+    use crate::avionics::navigation::vnav::VnavConfig;
+    use crate::bus::{FlightPlanMessage, GeoPosition, NavStateMessage, VnavStateMessage, Waypoint};
+
+    let origin: GeoPosition = GeoPosition { lat: 45.0, lon: 5.0 };
+    let wpt1: GeoPosition = destination(&origin, 0.0, 20.0);
+    let wpt2: GeoPosition = destination(&wpt1, 90.0, 20.0);
+    let waypoint = |ident: &str, position: GeoPosition, altitude: f32| Waypoint { ident: ident.to_string(), position, altitude: Some(altitude) };
+
+    let mut navigation: Navigation = Navigation::new(LnavConfig::from(&Yaml::BadValue), VnavConfig::from(&Yaml::BadValue));
+    assert_eq!(None, navigation.update(&test_adc_data(origin, 0.0), 15000.0));
+
+    navigation.load(FlightPlanMessage { waypoints: vec![waypoint("WPT1", wpt1, 8000.0), waypoint("WPT2", wpt2, 3000.0)] });
+    assert!(navigation.is_active());
+
+    // Direct to WPT1 from the present position.
+    let nav_state: NavStateMessage = navigation.update(&test_adc_data(origin, 0.0), 15000.0).unwrap();
+    assert_eq!(Some(0), nav_state.active_waypoint);
+    assert!((nav_state.distance_to_go_nm - 20.0).abs() < 0.01);
+    assert!(nav_state.cross_track_nm.abs() < 0.01);
+    assert!(nav_state.bank_cmd.abs() < 0.1);
+
+    // Climb to 8000 ft at WPT1 (TOC 21.3 NM at 1500 fpm), descent to 3000 ft at WPT2 (3 deg path : TOD 24.3 NM).
+    let vnav: VnavStateMessage = nav_state.vnav.unwrap();
+    assert_eq!(8000.0, vnav.cruise_altitude);
+    assert!((vnav.top_of_climb_nm.unwrap() - 21.33).abs() < 0.01);
+    assert!((vnav.top_of_descent_nm.unwrap() - 24.3).abs() < 0.1);
+
+    // 90 deg right turn at WPT1 : 1.8 NM anticipation at 240 kt.
+    let nav_state: NavStateMessage = navigation.update(&test_adc_data(destination(&origin, 0.0, 18.0), 0.0), 15000.0).unwrap();
+    assert_eq!(Some(0), nav_state.active_waypoint);
+    let nav_state: NavStateMessage = navigation.update(&test_adc_data(destination(&origin, 0.0, 18.3), 0.0), 15000.0).unwrap();
+    assert_eq!(Some(1), nav_state.active_waypoint);
+    assert!((nav_state.desired_track - 90.0).abs() < 1.0);
+    assert_eq!(25.0, nav_state.bank_cmd);
+    // WPT1 passed : still the cruise altitude.
+    assert_eq!(8000.0, nav_state.vnav.unwrap().cruise_altitude);
+
+    // Abeam the last waypoint : plan flown.
+    let nav_state: NavStateMessage = navigation.update(&test_adc_data(destination(&wpt2, 90.0, 0.5), 90.0), 15000.0).unwrap();
+    assert_eq!(None, nav_state.active_waypoint);
+    assert!(!navigation.is_active());
+    assert_eq!(None, navigation.update(&test_adc_data(origin, 0.0), 15000.0));
+}
@@ -0,0 +1,342 @@
+/**
+ * Flight plan files
+ * Route files built by the pilots, loaded into the navigation (nav/plan topic), format from the file extension :
+ * - .fms : X-Plane flight plan, version 3 (X-Plane 9/10) or 1100 (X-Plane 11), one waypoint per line
+ *   "type ident altitude lat lon" (v3) or "type ident via altitude lat lon" (v11), altitude (ft) 0 without constraint
+ * - .gpx : GPX route (first "rte", "rtept" lat/lon, "name" ident, "ele" altitude in m), waypoints ("wpt") without route
+ * - .csv : header "ident,lat,lon" and optional "altitude" column (ft), empty altitude without constraint
+ *
+ * Every waypoint is validated (ident, latitude -90 to 90, longitude -180 to 180), errors report the line or the point.
+ */
+extern crate roxmltree;
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::bus::{FlightPlanMessage, GeoPosition, Waypoint};
+use crate::units::M_TO_FT;
+
+/// Flight plan file extensions, GUI file picker filter.
+pub const FLIGHT_PLAN_EXTENSIONS: [&str; 3] = ["fms", "gpx", "csv"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlightPlanError(pub String);
+
+impl fmt::Display for FlightPlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Flight plan error: {}", self.0)
+    }
+}
+
+impl Error for FlightPlanError {}
+
+fn error<T>(message: String) -> Result<T, FlightPlanError> {
+
+    Err(FlightPlanError(message))
+}
+
+/// Load the flight plan file, format from its extension.
+pub fn load_flight_plan(path: &str) -> Result<FlightPlanMessage, FlightPlanError> {
+
+    let extension: String = Path::new(path).extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| extension.to_lowercase())
+                .unwrap_or_default();
+
+    if !FLIGHT_PLAN_EXTENSIONS.contains(&extension.as_str()) {
+        return error(format!("{} : unsupported format (.fms, .gpx or .csv)", path));
+    }
+
+    let content: String = fs::read_to_string(path)
+                .map_err(|e| FlightPlanError(format!("{} : {}", path, e)))?;
+
+    let flight_plan: Result<FlightPlanMessage, FlightPlanError> = match extension.as_str() {
+        "fms" => parse_fms(&content),
+        "gpx" => parse_gpx(&content),
+        _ => parse_csv(&content),
+    };
+
+    flight_plan.map_err(|e| FlightPlanError(format!("{} : {}", path, e.0)))
+}
+
+fn waypoint(ident: &str, lat: f64, lon: f64, altitude: Option<f32>, location: &str) -> Result<Waypoint, FlightPlanError> {
+
+    if ident.is_empty() {
+        return error(format!("{} : missing ident", location));
+    }
+    if !(-90.0..=90.0).contains(&lat) {
+        return error(format!("{} : {} latitude {} out of range", location, ident, lat));
+    }
+    if !(-180.0..=180.0).contains(&lon) {
+        return error(format!("{} : {} longitude {} out of range", location, ident, lon));
+    }
+    if let Some(altitude) = altitude.filter(|altitude| !altitude.is_finite()) {
+        return error(format!("{} : {} altitude {} invalid", location, ident, altitude));
+    }
+
+    Ok(Waypoint { ident: ident.to_string(), position: GeoPosition { lat, lon }, altitude })
+}
+
+fn flight_plan(waypoints: Vec<Waypoint>) -> Result<FlightPlanMessage, FlightPlanError> {
+
+    if waypoints.is_empty() {
+        return error("no waypoint".to_string());
+    }
+
+    Ok(FlightPlanMessage { waypoints })
+}
+
+fn parse_number<T: std::str::FromStr>(field: &str, name: &str, location: &str) -> Result<T, FlightPlanError> {
+
+    field.parse::<T>().map_err(|_| FlightPlanError(format!("{} : invalid {} '{}'", location, name, field)))
+}
+
+/// Altitude (ft) constraint, none when 0.
+fn fms_altitude(field: &str, location: &str) -> Result<Option<f32>, FlightPlanError> {
+
+    let altitude: f32 = parse_number(field, "altitude", location)?;
+
+    Ok(if altitude == 0.0 { None } else { Some(altitude) })
+}
+
+pub fn parse_fms(content: &str) -> Result<FlightPlanMessage, FlightPlanError> {
+
+    let mut lines = content.lines().enumerate()
+                .map(|(i, line)| (format!("line {}", i + 1), line.trim()))
+                .filter(|(_, line)| !line.is_empty());
+
+    match lines.next() {
+        Some((_, "I")) | Some((_, "A")) => (),
+        _ => return error("not an X-Plane flight plan (\"I\" or \"A\" first line expected)".to_string()),
+    }
+
+    let version: &str = lines.next()
+                .and_then(|(_, line)| line.split_whitespace().next())
+                .unwrap_or("");
+
+    let mut waypoints: Vec<Waypoint> = Vec::new();
+
+    match version {
+        "3" => {
+            // Cycle and last waypoint index (waypoints count - 1) lines, then the waypoints.
+            lines.next();
+            let last_index: usize = match lines.next() {
+                Some((location, line)) => parse_number(line, "last waypoint index", &location)?,
+                None => return error("missing last waypoint index".to_string()),
+            };
+
+            for (location, line) in lines {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                if fields.len() < 5 {
+                    return error(format!("{} : 5 fields expected (type ident altitude lat lon)", location));
+                }
+
+                waypoints.push(waypoint(
+                    fields[1],
+                    parse_number(fields[3], "latitude", &location)?,
+                    parse_number(fields[4], "longitude", &location)?,
+                    fms_altitude(fields[2], &location)?,
+                    &location)?);
+            }
+
+            if waypoints.len() != last_index + 1 {
+                return error(format!("last waypoint index {} but {} waypoints", last_index, waypoints.len()));
+            }
+        },
+        "1100" => {
+            // Header keywords up to the waypoints count.
+            let mut count: Option<usize> = None;
+            for (location, line) in lines.by_ref() {
+                if let Some(numenr) = line.strip_prefix("NUMENR") {
+                    count = Some(parse_number(numenr.trim(), "NUMENR", &location)?);
+                    break;
+                }
+            }
+
+            let count: usize = match count {
+                Some(count) => count,
+                None => return error("missing NUMENR".to_string()),
+            };
+
+            for (location, line) in lines {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                if fields.len() < 6 {
+                    return error(format!("{} : 6 fields expected (type ident via altitude lat lon)", location));
+                }
+
+                waypoints.push(waypoint(
+                    fields[1],
+                    parse_number(fields[4], "latitude", &location)?,
+                    parse_number(fields[5], "longitude", &location)?,
+                    fms_altitude(fields[3], &location)?,
+                    &location)?);
+            }
+
+            if waypoints.len() != count {
+                return error(format!("NUMENR {} but {} waypoints", count, waypoints.len()));
+            }
+        },
+        _ => return error(format!("unsupported version '{}' (3 or 1100)", version)),
+    }
+
+    flight_plan(waypoints)
+}
+
+pub fn parse_gpx(content: &str) -> Result<FlightPlanMessage, FlightPlanError> {
+
+    let document: roxmltree::Document = roxmltree::Document::parse(content)
+                .map_err(|e| FlightPlanError(format!("invalid GPX : {}", e)))?;
+
+    let root: roxmltree::Node = document.root_element();
+    if root.tag_name().name() != "gpx" {
+        return error(format!("invalid GPX : <gpx> root expected, <{}> found", root.tag_name().name()));
+    }
+
+    let child = |node: roxmltree::Node<'_, '_>, name: &str| node.children()
+                .find(|child| child.tag_name().name() == name)
+                .and_then(|child| child.text())
+                .map(|text| text.trim().to_string());
+
+    // First route, or the waypoints without route.
+    let points: Vec<roxmltree::Node> = match root.children().find(|node| node.tag_name().name() == "rte") {
+        Some(route) => route.children().filter(|node| node.tag_name().name() == "rtept").collect(),
+        None => root.children().filter(|node| node.tag_name().name() == "wpt").collect(),
+    };
+
+    let mut waypoints: Vec<Waypoint> = Vec::new();
+
+    for (i, point) in points.iter().enumerate() {
+        let location: String = format!("point {}", i + 1);
+
+        let coordinate = |name: &str| -> Result<f64, FlightPlanError> {
+            match point.attribute(name) {
+                Some(value) => parse_number(value, name, &location),
+                None => error(format!("{} : missing {}", location, name)),
+            }
+        };
+
+        let altitude: Option<f32> = match child(*point, "ele") {
+            Some(ele) => Some(parse_number::<f32>(&ele, "ele", &location)? * M_TO_FT),
+            None => None,
+        };
+
+        // Unnamed points : numbered.
+        let ident: String = child(*point, "name").unwrap_or(format!("WPT{:02}", i + 1));
+
+        waypoints.push(waypoint(&ident, coordinate("lat")?, coordinate("lon")?, altitude, &location)?);
+    }
+
+    flight_plan(waypoints)
+}
+
+pub fn parse_csv(content: &str) -> Result<FlightPlanMessage, FlightPlanError> {
+
+    let mut lines = content.lines().enumerate()
+                .map(|(i, line)| (format!("line {}", i + 1), line.trim()))
+                .filter(|(_, line)| !line.is_empty());
+
+    let header: Vec<String> = match lines.next() {
+        Some((_, line)) => line.split(',').map(|c| c.trim().to_lowercase()).collect(),
+        None => return error("empty file".to_string()),
+    };
+
+    let column = |name: &str| header.iter().position(|c| c == name);
+    let (ident, lat, lon) = match (column("ident"), column("lat"), column("lon")) {
+        (Some(ident), Some(lat), Some(lon)) => (ident, lat, lon),
+        _ => return error("header with ident, lat and lon columns expected".to_string()),
+    };
+    let altitude: Option<usize> = column("altitude");
+
+    let mut waypoints: Vec<Waypoint> = Vec::new();
+
+    for (location, line) in lines {
+        let fields: Vec<&str> = line.split(',').map(|c| c.trim()).collect();
+        if fields.len() != header.len() {
+            return error(format!("{} : {} fields expected, {} found", location, header.len(), fields.len()));
+        }
+
+        let altitude: Option<f32> = match altitude.map(|i| fields[i]).filter(|field| !field.is_empty()) {
+            Some(field) => Some(parse_number(field, "altitude", &location)?),
+            None => None,
+        };
+
+        waypoints.push(waypoint(
+            fields[ident],
+            parse_number(fields[lat], "latitude", &location)?,
+            parse_number(fields[lon], "longitude", &location)?,
+            altitude,
+            &location)?);
+    }
+
+    flight_plan(waypoints)
+}
+
+#[test]
+fn flight_plan_formats() {
+
+    let fms_v11: &str = "I\n1100 Version\nCYCLE 1710\nADEP LFLL\nADES LFPG\nNUMENR 3\n\
+                1 LFLL ADEP 0.000000 45.725556 5.081111\n\
+                11 MOLUS DRCT 25000.000000 46.445000 4.795833\n\
+                1 LFPG ADES 0.000000 49.009722 2.547778\n";
+    let fms_v3: &str = "I\n3 version\n1\n2\n1 LFLL 0.000000 45.725556 5.081111\n11 MOLUS 25000.000000 46.445000 4.795833\n1 LFPG 0.000000 49.009722 2.547778\n";
+    let gpx: &str = r#"<?xml version="1.0"?>
+        <gpx version="1.1" xmlns="http://www.topografix.com/GPX/1/1">
+          <rte>
+            <rtept lat="45.725556" lon="5.081111"><name>LFLL</name></rtept>
+            <rtept lat="46.445" lon="4.795833"><name>MOLUS</name><ele>7620</ele></rtept>
+            <rtept lat="49.009722" lon="2.547778"><name>LFPG</name></rtept>
+          </rte>
+        </gpx>"#;
+    let csv: &str = "ident,lat,lon,altitude\nLFLL,45.725556,5.081111,\nMOLUS,46.445,4.795833,25000\nLFPG,49.009722,2.547778,\n";
+
+    for flight_plan in [parse_fms(fms_v11), parse_fms(fms_v3), parse_gpx(gpx), parse_csv(csv)] {
+        let waypoints: Vec<Waypoint> = flight_plan.unwrap().waypoints;
+
+        assert_eq!(vec!["LFLL", "MOLUS", "LFPG"], waypoints.iter().map(|w| w.ident.as_str()).collect::<Vec<&str>>());
+        assert_eq!(GeoPosition { lat: 46.445, lon: 4.795833 }, waypoints[1].position);
+        assert_eq!(None, waypoints[0].altitude);
+        assert!((waypoints[1].altitude.unwrap() - 25000.0).abs() < 1.0);
+    }
+
+    // Waypoints of a GPX file without route.
+    let gpx: &str = r#"<gpx><wpt lat="45.0" lon="5.0"/><wpt lat="46.0" lon="5.0"><name>NORTH</name></wpt></gpx>"#;
+    let waypoints: Vec<Waypoint> = parse_gpx(gpx).unwrap().waypoints;
+    assert_eq!(vec!["WPT01", "NORTH"], waypoints.iter().map(|w| w.ident.as_str()).collect::<Vec<&str>>());
+}
+
+#[test]
+fn flight_plan_file() {
+
+    let waypoints: Vec<Waypoint> = load_flight_plan("conf/flight_plans/lfll-lfpg.fms").unwrap().waypoints;
+
+    assert_eq!(vec!["LFLL", "MOLUS", "MOU", "OKRIX", "LFPG"], waypoints.iter().map(|w| w.ident.as_str()).collect::<Vec<&str>>());
+    assert_eq!(vec![Some(821.0), None, Some(25000.0), Some(11000.0), Some(392.0)], waypoints.iter().map(|w| w.altitude).collect::<Vec<Option<f32>>>());
+    assert_eq!(GeoPosition { lat: 49.009722, lon: 2.547778 }, waypoints[4].position);
+}
+
+#[test]
+fn flight_plan_errors() {
+
+    let message = |flight_plan: Result<FlightPlanMessage, FlightPlanError>| flight_plan.unwrap_err().0;
+
+    assert_eq!("not an X-Plane flight plan (\"I\" or \"A\" first line expected)", message(parse_fms("ident,lat,lon\n")));
+    assert_eq!("unsupported version '1200' (3 or 1100)", message(parse_fms("I\n1200 Version\n")));
+    assert_eq!("NUMENR 2 but 1 waypoints", message(parse_fms("I\n1100 Version\nNUMENR 2\n1 LFLL ADEP 0.0 45.7 5.0\n")));
+    assert_eq!("line 4 : invalid latitude 'N45'", message(parse_fms("I\n1100 Version\nNUMENR 1\n1 LFLL ADEP 0.0 N45 5.0\n")));
+    assert_eq!("line 5 : 5 fields expected (type ident altitude lat lon)", message(parse_fms("I\n3 version\n1\n0\n1 LFLL 45.7 5.0\n")));
+    assert_eq!("last waypoint index 2 but 2 waypoints", message(parse_fms("I\n3 version\n1\n2\n1 LFLL 0.0 45.7 5.0\n1 LFPG 0.0 49.0 2.5\n")));
+    assert_eq!("line 4 : invalid last waypoint index 'x'", message(parse_fms("I\n3 version\n1\nx\n")));
+
+    assert!(message(parse_gpx("<gpx><rte>")).starts_with("invalid GPX"));
+    assert_eq!("point 1 : missing lon", message(parse_gpx(r#"<gpx><rte><rtept lat="45.0"/></rte></gpx>"#)));
+    assert_eq!("no waypoint", message(parse_gpx("<gpx/>")));
+
+    assert_eq!("header with ident, lat and lon columns expected", message(parse_csv("name,lat,lon\n")));
+    assert_eq!("line 2 : LFLL latitude 95 out of range", message(parse_csv("ident,lat,lon\nLFLL,95,5.0\n")));
+    assert_eq!("line 3 : 3 fields expected, 2 found", message(parse_csv("ident,lat,lon\nLFLL,45.7,5.0\nLFPG,49.0\n")));
+    assert_eq!("line 2 : missing ident", message(parse_csv("ident,lat,lon\n,45.7,5.0\n")));
+
+    assert_eq!("Flight plan error: route.kml : unsupported format (.fms, .gpx or .csv)", load_flight_plan("route.kml").unwrap_err().to_string());
+}
@@ -0,0 +1,98 @@
+/**
+ * Great circle navigation on a spherical earth
+ * Distances in nautical miles, bearings in degrees true (0 to 360), cross track distance positive right of the path.
+ */
+use crate::bus::GeoPosition;
+
+/// Mean earth radius (NM).
+pub const EARTH_RADIUS_NM: f64 = 3_440.065;
+
+/// Angular distance (rad) between two positions (haversine).
+fn angular_distance(from: &GeoPosition, to: &GeoPosition) -> f64 {
+
+    let (lat1, lat2) = (from.lat.to_radians(), to.lat.to_radians());
+    let d_lat: f64 = lat2 - lat1;
+    let d_lon: f64 = (to.lon - from.lon).to_radians();
+
+    let a: f64 = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+
+    2.0 * a.sqrt().min(1.0).asin()
+}
+
+pub fn distance_nm(from: &GeoPosition, to: &GeoPosition) -> f64 {
+
+    angular_distance(from, to) * EARTH_RADIUS_NM
+}
+
+/// Initial bearing of the great circle from `from` to `to`.
+pub fn bearing_deg(from: &GeoPosition, to: &GeoPosition) -> f64 {
+
+    let (lat1, lat2) = (from.lat.to_radians(), to.lat.to_radians());
+    let d_lon: f64 = (to.lon - from.lon).to_radians();
+
+    let y: f64 = d_lon.sin() * lat2.cos();
+    let x: f64 = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_lon.cos();
+
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// Cross track distance of `position` from the great circle `start` to `end`, positive right of the path.
+pub fn cross_track_nm(start: &GeoPosition, end: &GeoPosition, position: &GeoPosition) -> f64 {
+
+    let d13: f64 = angular_distance(start, position);
+    let d_bearing: f64 = (bearing_deg(start, position) - bearing_deg(start, end)).to_radians();
+
+    (d13.sin() * d_bearing.sin()).asin() * EARTH_RADIUS_NM
+}
+
+/// Distance from `start` to the point of the great circle `start` to `end` abeam `position` (negative behind `start`).
+pub fn along_track_nm(start: &GeoPosition, end: &GeoPosition, position: &GeoPosition) -> f64 {
+
+    let d13: f64 = angular_distance(start, position);
+    let dxt: f64 = cross_track_nm(start, end, position) / EARTH_RADIUS_NM;
+    let d_bearing: f64 = (bearing_deg(start, position) - bearing_deg(start, end)).to_radians();
+
+    let along: f64 = (d13.cos() / dxt.cos()).clamp(-1.0, 1.0).acos();
+
+    along.copysign(d_bearing.cos()) * EARTH_RADIUS_NM
+}
+
+/// Position at `distance` (NM) from `start` on the initial bearing `bearing` (deg).
+pub fn destination(start: &GeoPosition, bearing: f64, distance: f64) -> GeoPosition {
+
+    let lat1: f64 = start.lat.to_radians();
+    let delta: f64 = distance / EARTH_RADIUS_NM;
+    let theta: f64 = bearing.to_radians();
+
+    let lat2: f64 = (lat1.sin() * delta.cos() + lat1.cos() * delta.sin() * theta.cos()).asin();
+    let lon2: f64 = start.lon.to_radians() + (theta.sin() * delta.sin() * lat1.cos()).atan2(delta.cos() - lat1.sin() * lat2.sin());
+
+    GeoPosition { lat: lat2.to_degrees(), lon: (lon2.to_degrees() + 540.0).rem_euclid(360.0) - 180.0 }
+}
+
+#[test]
+fn geo_great_circle() {
+
+    let lfll: GeoPosition = GeoPosition { lat: 45.7256, lon: 5.0811 };
+    let lfpg: GeoPosition = GeoPosition { lat: 49.0097, lon: 2.5479 };
+
+    // Lyon - Paris CDG : 222 NM, initial bearing 333 deg.
+    assert!((distance_nm(&lfll, &lfpg) - 222.4).abs() < 0.5);
+    assert!((bearing_deg(&lfll, &lfpg) - 333.3).abs() < 0.5);
+
+    // 60 NM north along the meridian : about 1 deg of latitude.
+    let north: GeoPosition = destination(&lfll, 0.0, 60.0);
+    assert!((north.lat - lfll.lat - 60.0 / (EARTH_RADIUS_NM.to_radians())).abs() < 1e-9);
+    assert!((north.lon - lfll.lon).abs() < 1e-9);
+
+    // Eastbound leg on the equator, aircraft 2 NM north (left) and 10 NM along.
+    let start: GeoPosition = GeoPosition { lat: 0.0, lon: 0.0 };
+    let end: GeoPosition = destination(&start, 90.0, 100.0);
+    let position: GeoPosition = destination(&destination(&start, 90.0, 10.0), 0.0, 2.0);
+    assert!((cross_track_nm(&start, &end, &position) + 2.0).abs() < 1e-3);
+    assert!((along_track_nm(&start, &end, &position) - 10.0).abs() < 1e-3);
+
+    // Behind the leg start.
+    let behind: GeoPosition = destination(&start, 270.0, 5.0);
+    assert!((along_track_nm(&start, &end, &behind) + 5.0).abs() < 1e-3);
+}
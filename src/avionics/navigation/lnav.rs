@@ -0,0 +1,147 @@
+/**
+ * LNAV guidance
+ * Leg from the previous waypoint (or the present position, direct to) to the active waypoint :
+ * - desired track (great circle track abeam the aircraft), cross track error, distance to go
+ * - track command : desired track corrected by an intercept angle, atan(cross track / lookahead distance)
+ *   with a lookahead of "lookahead_s" at the ground speed, limited to "max_intercept_deg"
+ * - bank command : track error x "bank_gain", limited to "max_bank_deg"
+ * - turn anticipation : the next leg is started at the distance R.tan(turn angle / 2) before the waypoint,
+ *   R the turn radius at the ground speed and max bank, to roll out on the next leg instead of overshooting it.
+ *
+ * Config ("autopilot" "lnav" section).
+ */
+extern crate yaml_rust;
+
+use yaml_rust::Yaml;
+
+use crate::avionics::ahrs::attitude::wrap_180;
+use crate::avionics::navigation::geo::{along_track_nm, bearing_deg, cross_track_nm, destination, distance_nm};
+use crate::bus::GeoPosition;
+use crate::config::get_positive_f32;
+use crate::units::{G, KT_TO_MS, M_TO_NM};
+
+const DEFAULT_MAX_BANK_DEG: f32 = 25.0;
+const DEFAULT_BANK_GAIN: f32 = 1.5;
+const DEFAULT_LOOKAHEAD_S: f32 = 60.0;
+const DEFAULT_MAX_INTERCEPT_DEG: f32 = 45.0;
+
+/// Lookahead distance (NM) at low ground speed.
+const MIN_LOOKAHEAD_NM: f32 = 0.5;
+
+/// Turn angle limit of the anticipation (tan(angle / 2) diverges for a U-turn).
+const MAX_ANTICIPATED_TURN_DEG: f32 = 150.0;
+
+/// Close to the waypoint the desired track is the leg final course (bearing to the waypoint not defined).
+const FINAL_COURSE_DISTANCE_NM: f64 = 0.1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LnavConfig {
+    pub max_bank_deg: f32,
+    pub bank_gain: f32,
+    pub lookahead_s: f32,
+    pub max_intercept_deg: f32,
+}
+
+impl From<&Yaml> for LnavConfig {
+
+    /// "autopilot" "lnav" section.
+    fn from(config: &Yaml) -> Self {
+
+        LnavConfig {
+            max_bank_deg: get_positive_f32(&config["max_bank_deg"]).unwrap_or(DEFAULT_MAX_BANK_DEG),
+            bank_gain: get_positive_f32(&config["bank_gain"]).unwrap_or(DEFAULT_BANK_GAIN),
+            lookahead_s: get_positive_f32(&config["lookahead_s"]).unwrap_or(DEFAULT_LOOKAHEAD_S),
+            max_intercept_deg: get_positive_f32(&config["max_intercept_deg"]).unwrap_or(DEFAULT_MAX_INTERCEPT_DEG),
+        }
+    }
+}
+
+/// Aircraft position relative to a leg.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LegGuidance {
+    pub desired_track: f32,
+    pub cross_track_nm: f32,
+    pub distance_to_go_nm: f32,
+}
+
+/// Course (deg) of the leg at its end waypoint.
+pub fn final_course(start: &GeoPosition, end: &GeoPosition) -> f32 {
+
+    ((bearing_deg(end, start) + 180.0).rem_euclid(360.0)) as f32
+}
+
+pub fn leg_guidance(start: &GeoPosition, end: &GeoPosition, position: &GeoPosition) -> LegGuidance {
+
+    let along: f64 = along_track_nm(start, end, position);
+    let distance_to_go: f64 = distance_nm(start, end) - along;
+
+    let desired_track: f32 = if distance_to_go > FINAL_COURSE_DISTANCE_NM {
+        let abeam: GeoPosition = destination(start, bearing_deg(start, end), along);
+        bearing_deg(&abeam, end) as f32
+    } else {
+        final_course(start, end)
+    };
+
+    LegGuidance {
+        desired_track,
+        cross_track_nm: cross_track_nm(start, end, position) as f32,
+        distance_to_go_nm: distance_to_go as f32,
+    }
+}
+
+/// Distance (NM) before the waypoint to start a turn of `turn_angle` (deg) at the ground speed (knots).
+pub fn turn_anticipation_nm(ground_speed: f32, turn_angle: f32, config: &LnavConfig) -> f32 {
+
+    let speed: f32 = ground_speed.max(0.0) * KT_TO_MS;
+    let radius_nm: f32 = speed * speed / (G * config.max_bank_deg.to_radians().tan()) * M_TO_NM;
+    let turn_angle: f32 = turn_angle.abs().min(MAX_ANTICIPATED_TURN_DEG);
+
+    radius_nm * (turn_angle.to_radians() / 2.0).tan()
+}
+
+/// Track (deg) to fly to intercept the leg.
+pub fn track_command(leg: &LegGuidance, ground_speed: f32, config: &LnavConfig) -> f32 {
+
+    let lookahead_nm: f32 = (ground_speed * config.lookahead_s / 3600.0).max(MIN_LOOKAHEAD_NM);
+    let intercept: f32 = leg.cross_track_nm.atan2(lookahead_nm).to_degrees()
+                .clamp(-config.max_intercept_deg, config.max_intercept_deg);
+
+    // Right of the leg (positive cross track) : turn left to intercept.
+    (leg.desired_track - intercept).rem_euclid(360.0)
+}
+
+/// Bank (deg, positive right) to turn from the current track to the commanded track.
+pub fn bank_command(track_cmd: f32, track: f32, config: &LnavConfig) -> f32 {
+
+    (wrap_180(track_cmd - track) * config.bank_gain).clamp(-config.max_bank_deg, config.max_bank_deg)
+}
+
+#[test]
+fn lnav_guidance() {
+
+    let config: LnavConfig = LnavConfig::from(&Yaml::BadValue);
+
+    // Northbound leg, aircraft 1 NM right (east) of the leg, 20 NM to go.
+    let start: GeoPosition = GeoPosition { lat: 45.0, lon: 5.0 };
+    let end: GeoPosition = destination(&start, 0.0, 30.0);
+    let position: GeoPosition = destination(&destination(&start, 0.0, 10.0), 90.0, 1.0);
+
+    let leg: LegGuidance = leg_guidance(&start, &end, &position);
+    assert!(leg.desired_track.abs() < 0.1 || (leg.desired_track - 360.0).abs() < 0.1);
+    assert!((leg.cross_track_nm - 1.0).abs() < 0.01);
+    assert!((leg.distance_to_go_nm - 20.0).abs() < 0.01);
+
+    // 240 kt : 4 NM lookahead, 14 deg intercept to the left.
+    let track_cmd: f32 = track_command(&leg, 240.0, &config);
+    assert!((wrap_180(track_cmd) + 14.04).abs() < 0.1);
+    assert!((bank_command(track_cmd, 0.0, &config) + 21.06).abs() < 0.2);
+
+    // Far from the leg : intercept and bank limited.
+    let far: LegGuidance = LegGuidance { desired_track: 0.0, cross_track_nm: -30.0, distance_to_go_nm: 20.0 };
+    assert_eq!(45.0, track_command(&far, 240.0, &config));
+    assert_eq!(25.0, bank_command(90.0, 0.0, &config));
+
+    // 90 deg turn at 240 kt and 25 deg bank : radius 1.8 NM.
+    assert!((turn_anticipation_nm(240.0, 90.0, &config) - 1.80).abs() < 0.01);
+    assert_eq!(0.0, turn_anticipation_nm(240.0, 0.0, &config));
+}
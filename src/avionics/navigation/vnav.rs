@@ -0,0 +1,185 @@
+/**
+ * VNAV guidance
+ * Vertical profile of the flight plan from its altitude constraints (distances along the route, negative once passed) :
+ * - cruise altitude : highest constraint, the constraints after it are the descent constraints
+ * - top of climb : distance to reach the cruise altitude at "climb_fpm" and the ground speed
+ * - top of descent : first point where the geometric descent path ("fpa_deg" flight path angle, down to each
+ *   descent constraint) leaves the cruise altitude
+ * - vertical deviation (ft, positive above the path) once past the top of descent
+ * - vertical speed command : climb or level off at the cruise altitude, path vertical speed corrected by the deviation
+ *   ("path_gain" fpm per ft) in descent (level below the path), limited to "climb_fpm" and "max_descent_fpm"
+ *
+ * The MCP altitude is a ceiling while climbing and a floor while descending : the command levels off at the MCP
+ * altitude, never climbs or descends toward it.
+ * Config ("autopilot" "vnav" section).
+ */
+extern crate yaml_rust;
+
+use yaml_rust::Yaml;
+
+use crate::bus::VnavStateMessage;
+use crate::config::get_positive_f32;
+use crate::units::{KT_TO_FPM, NM_TO_FT};
+
+const DEFAULT_FPA_DEG: f32 = 3.0;
+const DEFAULT_CLIMB_FPM: f32 = 1500.0;
+const DEFAULT_MAX_DESCENT_FPM: f32 = 3000.0;
+const DEFAULT_PATH_GAIN: f32 = 5.0;
+
+/// Altitude capture gain (fpm per ft) toward the cruise and MCP altitudes.
+const CAPTURE_GAIN: f32 = 5.0;
+
+/// Below this ground speed the top of climb is not computed (on ground, not moving).
+const MIN_GROUND_SPEED_KT: f32 = 30.0;
+
+/// Cruise altitude reached within this tolerance (ft).
+const CRUISE_TOLERANCE_FT: f32 = 50.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VnavConfig {
+    pub fpa_deg: f32,
+    pub climb_fpm: f32,
+    pub max_descent_fpm: f32,
+    pub path_gain: f32,
+}
+
+impl From<&Yaml> for VnavConfig {
+
+    /// "autopilot" "vnav" section.
+    fn from(config: &Yaml) -> Self {
+
+        VnavConfig {
+            fpa_deg: get_positive_f32(&config["fpa_deg"]).unwrap_or(DEFAULT_FPA_DEG),
+            climb_fpm: get_positive_f32(&config["climb_fpm"]).unwrap_or(DEFAULT_CLIMB_FPM),
+            max_descent_fpm: get_positive_f32(&config["max_descent_fpm"]).unwrap_or(DEFAULT_MAX_DESCENT_FPM),
+            path_gain: get_positive_f32(&config["path_gain"]).unwrap_or(DEFAULT_PATH_GAIN),
+        }
+    }
+}
+
+/// Altitude constraint of a waypoint, at its distance (NM) along the route from the aircraft (negative once passed).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AltitudeConstraint {
+    pub distance_nm: f32,
+    pub altitude: f32,
+}
+
+/// Descent path altitude loss (ft) per NM.
+fn path_gradient(config: &VnavConfig) -> f32 {
+
+    config.fpa_deg.to_radians().tan() * NM_TO_FT
+}
+
+/// Vertical profile at the aircraft altitude (ft) and ground speed (kt), None without altitude constraint.
+pub fn vnav_guidance(constraints: &[AltitudeConstraint], altitude: f32, ground_speed: f32, mcp_alt: f32, config: &VnavConfig) -> Option<VnavStateMessage> {
+
+    // Last of the highest constraints : cruise, descent constraints after it.
+    let cruise: usize = constraints.iter().enumerate()
+                .fold(None, |cruise: Option<usize>, (i, constraint)| match cruise {
+                    Some(c) if constraints[c].altitude > constraint.altitude => cruise,
+                    _ => Some(i)
+                })?;
+    let cruise_altitude: f32 = constraints[cruise].altitude;
+    let descent: &[AltitudeConstraint] = &constraints[cruise + 1..];
+
+    let gradient: f32 = path_gradient(config);
+
+    let top_of_descent_nm: Option<f32> = descent.iter()
+                .map(|constraint| constraint.distance_nm - (cruise_altitude - constraint.altitude) / gradient)
+                .reduce(f32::min);
+    let descending: bool = top_of_descent_nm.map(|tod| tod <= 0.0).unwrap_or(false);
+
+    let top_of_climb_nm: Option<f32> = if !descending && altitude < cruise_altitude - CRUISE_TOLERANCE_FT && ground_speed >= MIN_GROUND_SPEED_KT {
+        Some((cruise_altitude - altitude) / (config.climb_fpm / ground_speed * 60.0))
+    } else {
+        None
+    };
+
+    let (path_altitude, vs_cmd): (Option<f32>, f32) = if descending {
+        // Geometric path : the lowest of the paths down to each descent constraint.
+        let path_altitude: f32 = descent.iter()
+                    .map(|constraint| constraint.altitude + constraint.distance_nm.max(0.0) * gradient)
+                    .fold(cruise_altitude, f32::min);
+        let path_vs: f32 = -ground_speed.max(0.0) * KT_TO_FPM * config.fpa_deg.to_radians().tan();
+
+        // Well below the path : level until the path is intercepted, never climbing back to it.
+        (Some(path_altitude), (path_vs - config.path_gain * (altitude - path_altitude)).min(0.0))
+    } else {
+        (None, CAPTURE_GAIN * (cruise_altitude - altitude))
+    };
+
+    // MCP altitude : ceiling while climbing, floor while descending.
+    let mcp_capture: f32 = CAPTURE_GAIN * (mcp_alt - altitude);
+    let vs_cmd: f32 = if vs_cmd > 0.0 {
+        vs_cmd.min(mcp_capture.max(0.0))
+    } else if vs_cmd < 0.0 {
+        vs_cmd.max(mcp_capture.min(0.0))
+    } else {
+        vs_cmd
+    };
+
+    Some(VnavStateMessage {
+        cruise_altitude,
+        top_of_climb_nm,
+        top_of_descent_nm: top_of_descent_nm.filter(|tod| *tod > 0.0),
+        path_altitude,
+        vertical_deviation: path_altitude.map(|path_altitude| altitude - path_altitude),
+        vs_cmd: vs_cmd.clamp(-config.max_descent_fpm, config.climb_fpm),
+    })
+}
+
+#[test]
+fn vnav_profile() {
+
+    let config: VnavConfig = VnavConfig::from(&Yaml::BadValue);
+    let constraint = |distance_nm: f32, altitude: f32| AltitudeConstraint { distance_nm, altitude };
+
+    assert_eq!(None, vnav_guidance(&[], 5000.0, 240.0, 15000.0, &config));
+
+    // Climb to FL250 at 60 NM, descent to 3000 ft at 200 NM : 3 deg path, 318 ft/NM.
+    let constraints: [AltitudeConstraint; 2] = [constraint(60.0, 25000.0), constraint(200.0, 3000.0)];
+    let gradient: f32 = path_gradient(&config);
+    assert!((gradient - 318.4).abs() < 0.1);
+
+    // Climbing through 10000 ft at 240 kt, 1500 fpm : 375 ft/NM, TOC 40 NM, TOD 130.9 NM.
+    let vnav = vnav_guidance(&constraints, 10000.0, 240.0, 35000.0, &config).unwrap();
+    assert_eq!(25000.0, vnav.cruise_altitude);
+    assert!((vnav.top_of_climb_nm.unwrap() - 40.0).abs() < 0.01);
+    assert!((vnav.top_of_descent_nm.unwrap() - 130.9).abs() < 0.1);
+    assert_eq!(None, vnav.vertical_deviation);
+    assert_eq!(1500.0, vnav.vs_cmd);
+
+    // MCP altitude ceiling : level off at 11000 ft, no descent to an MCP altitude below.
+    let vnav = vnav_guidance(&constraints, 10900.0, 240.0, 11000.0, &config).unwrap();
+    assert_eq!(500.0, vnav.vs_cmd);
+    let vnav = vnav_guidance(&constraints, 10900.0, 240.0, 9000.0, &config).unwrap();
+    assert_eq!(0.0, vnav.vs_cmd);
+
+    // Cruise : MCP altitude below not descended to.
+    let vnav = vnav_guidance(&constraints, 24980.0, 240.0, 20000.0, &config).unwrap();
+    assert_eq!(0.0, vnav.vs_cmd);
+    let vnav = vnav_guidance(&constraints, 25000.0, 240.0, 20000.0, &config).unwrap();
+    assert_eq!(0.0, vnav.vs_cmd);
+
+    // In descent, 100 ft above the path 20 NM before the last constraint.
+    let constraints: [AltitudeConstraint; 1] = [constraint(20.0, 3000.0)];
+    let vnav = vnav_guidance(&[constraint(-1.0, 25000.0), constraints[0]], 3000.0 + 20.0 * gradient + 100.0, 240.0, 0.0, &config).unwrap();
+    assert_eq!(None, vnav.top_of_climb_nm);
+    assert_eq!(None, vnav.top_of_descent_nm);
+    assert!((vnav.vertical_deviation.unwrap() - 100.0).abs() < 0.1);
+    // Path : 240 kt x 318 ft/NM = 1274 fpm, 500 fpm deviation correction.
+    assert!((vnav.vs_cmd + 1274.0 + 500.0).abs() < 1.0);
+
+    // Below the path : level.
+    let vnav = vnav_guidance(&[constraint(-1.0, 25000.0), constraints[0]], 3000.0 + 20.0 * gradient - 500.0, 240.0, 0.0, &config).unwrap();
+    assert_eq!(0.0, vnav.vs_cmd);
+
+    // MCP altitude floor : 500 ft above the path, level off 68 ft above the MCP altitude.
+    let altitude: f32 = 3000.0 + 20.0 * gradient + 500.0;
+    let vnav = vnav_guidance(&[constraint(-1.0, 25000.0), constraints[0]], altitude, 240.0, altitude - 68.0, &config).unwrap();
+    assert!((vnav.vs_cmd + 340.0).abs() < 0.1);
+
+    // MCP altitude above in descent : level, no climb to it.
+    let vnav = vnav_guidance(&[constraint(-1.0, 25000.0), constraints[0]], altitude, 240.0, altitude + 1000.0, &config).unwrap();
+    assert_eq!(0.0, vnav.vs_cmd);
+}
@@ -1,17 +1,30 @@
+pub mod bridge;
+pub mod codec;
+pub mod envelope;
+pub mod pubsub;
+
 
 pub mod bus {
     use std::fmt;
 
-    #[derive(Debug, Clone)]
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub enum BusMessage {
-        AdcData(AdcDataMessage),
+        AdcData(Box<AdcDataMessage>),
         APState(APStateMessage),
-        APCmd(APCmdPayload)
+        APCmd(APCmdPayload),
+        LinkState(LinkState),
+        AdcHealth(AdcHealthMessage),
+        AhrsData(AhrsDataMessage),
+        TaskStats(TaskStatsMessage),
+        FlightPlan(FlightPlanMessage),
+        NavState(NavStateMessage)
     }
 
     // ADC Messages
     
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub struct AdcDataMessage {
         pub ias: f32,
         pub alt_msl: f32,
@@ -23,6 +36,30 @@ pub mod bus {
         pub g_load: f32,
         pub pitch_angle: f32,
         pub roll_angle: f32,
+
+        // Derived air data (ISA model)
+        pub tas: f32,
+        pub eas: f32,
+        pub pressure_alt: f32,
+        pub density_alt: f32,
+        pub sat: f32,
+
+        // Trend vectors : IAS variation (knots) in 10 s, altitude variation (feets) in 6 s
+        pub ias_trend: f32,
+        pub alt_trend: f32,
+
+        // Ground speed (knots) and ground track (deg true), when provided by the sensors
+        pub ground_speed: Option<f32>,
+        pub track: Option<f32>,
+
+        // Aircraft position, when provided by the sensors (GNSS)
+        pub position: Option<GeoPosition>,
+
+        // Estimated wind, None without ground speed/track or at low speed
+        pub wind: Option<WindData>,
+
+        // Status and sample timestamp of each parameter
+        pub validity: AdcValidity,
     }
 
     impl AdcDataMessage {
@@ -39,19 +76,247 @@ pub mod bus {
                 g_load: 0f32,
                 pitch_angle: 0f32,
                 roll_angle: 0f32,
+                tas: 0f32,
+                eas: 0f32,
+                pressure_alt: 0f32,
+                density_alt: 0f32,
+                sat: 0f32,
+                ias_trend: 0f32,
+                alt_trend: 0f32,
+                ground_speed: None,
+                track: None,
+                position: None,
+                wind: None,
+                validity: AdcValidity::new(),
+            }
+        }
+    }
+
+    impl AdcDataMessage {
+
+        /// Value of a parameter.
+        pub fn get(&self, param: AdcParameter) -> f32 {
+
+            match param {
+                AdcParameter::Ias => self.ias,
+                AdcParameter::AltMsl => self.alt_msl,
+                AdcParameter::AltAgl => self.alt_agl,
+                AdcParameter::Heading => self.heading,
+                AdcParameter::Vs => self.vs,
+                AdcParameter::Aoa => self.aoa,
+                AdcParameter::Mach => self.mach,
+                AdcParameter::GLoad => self.g_load,
+                AdcParameter::Pitch => self.pitch_angle,
+                AdcParameter::Roll => self.roll_angle,
+            }
+        }
+
+        pub fn set(&mut self, param: AdcParameter, value: f32) {
+
+            match param {
+                AdcParameter::Ias => self.ias = value,
+                AdcParameter::AltMsl => self.alt_msl = value,
+                AdcParameter::AltAgl => self.alt_agl = value,
+                AdcParameter::Heading => self.heading = value,
+                AdcParameter::Vs => self.vs = value,
+                AdcParameter::Aoa => self.aoa = value,
+                AdcParameter::Mach => self.mach = value,
+                AdcParameter::GLoad => self.g_load = value,
+                AdcParameter::Pitch => self.pitch_angle = value,
+                AdcParameter::Roll => self.roll_angle = value,
             }
         }
     }
 
     impl fmt::Display for AdcDataMessage {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "ias: {}, alt MSL: {}, alt AGL: {}, hdg: {}, vs: {}, aoa: {}, mach: {}, g_load: {}, pitch: {}, roll: {}", 
-            self.ias, self.alt_msl, self.alt_agl, self.heading, self.vs, self.aoa, self.mach, self.g_load, self.pitch_angle, self.roll_angle)
+            write!(f, "ias: {}, alt MSL: {}, alt AGL: {}, hdg: {}, vs: {}, aoa: {}, mach: {}, g_load: {}, pitch: {}, roll: {}, tas: {}, eas: {}, pressure alt: {}, density alt: {}, sat: {}, ias trend: {}, alt trend: {}, gs: {:?}, track: {:?}, position: {:?}, wind: {:?}", 
+            self.ias, self.alt_msl, self.alt_agl, self.heading, self.vs, self.aoa, self.mach, self.g_load, self.pitch_angle, self.roll_angle,
+            self.tas, self.eas, self.pressure_alt, self.density_alt, self.sat, self.ias_trend, self.alt_trend,
+            self.ground_speed, self.track, self.position, self.wind)
+        }
+    }
+
+    /**
+     * Estimated wind
+     * Direction (deg true) the wind is blowing from and speed (knots), components relative to the aircraft heading :
+     * headwind (knots, negative for tailwind) and crosswind (knots, positive from the right).
+     */
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct WindData {
+        pub direction: f32,
+        pub speed: f32,
+        pub headwind: f32,
+        pub crosswind: f32,
+    }
+
+    // AHRS Messages
+
+    /// Alignment state of the AHRS.
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub enum AhrsStatus {
+        NoData,
+        Aligning,
+        Aligned,
+    }
+
+    /**
+     * Attitude and heading computed by the AHRS from the body rates and accelerations (deg),
+     * alignment progress (0 to 1) and drift from the sim attitude (AHRS - sim, deg, None without sim attitude).
+     */
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct AhrsDataMessage {
+        pub pitch: f32,
+        pub roll: f32,
+        pub heading: f32,
+        pub status: AhrsStatus,
+        pub alignment: f32,
+        pub pitch_drift: Option<f32>,
+        pub roll_drift: Option<f32>,
+        pub heading_drift: Option<f32>,
+    }
+
+    impl AhrsDataMessage {
+
+        pub const fn new() -> Self {
+            Self {
+                pitch: 0f32,
+                roll: 0f32,
+                heading: 0f32,
+                status: AhrsStatus::NoData,
+                alignment: 0f32,
+                pitch_drift: None,
+                roll_drift: None,
+                heading_drift: None,
+            }
+        }
+    }
+
+    // Scheduler Messages
+
+    /**
+     * Real-time statistics of a scheduled task over the last second : cycles, execution time, start jitter (delay after
+     * the deadline) and time between cycle starts (ms), overruns (cycles ending after the next deadline) since start.
+     */
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct TaskStatsMessage {
+        pub task: String,
+        pub frequency_hz: f32,
+        pub cycles: u32,
+        pub exec_time_avg_ms: f32,
+        pub exec_time_max_ms: f32,
+        pub jitter_avg_ms: f32,
+        pub jitter_max_ms: f32,
+        pub dt_min_ms: f32,
+        pub dt_max_ms: f32,
+        pub overruns: u64,
+    }
+
+    /// ADC parameters, index of the validity.
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub enum AdcParameter {
+        Ias,
+        AltMsl,
+        AltAgl,
+        Heading,
+        Vs,
+        Aoa,
+        Mach,
+        GLoad,
+        Pitch,
+        Roll
+    }
+
+    pub const ADC_PARAMETER_COUNT: usize = 10;
+
+    impl AdcParameter {
+
+        pub const ALL: [AdcParameter; ADC_PARAMETER_COUNT] = [
+            AdcParameter::Ias,
+            AdcParameter::AltMsl,
+            AdcParameter::AltAgl,
+            AdcParameter::Heading,
+            AdcParameter::Vs,
+            AdcParameter::Aoa,
+            AdcParameter::Mach,
+            AdcParameter::GLoad,
+            AdcParameter::Pitch,
+            AdcParameter::Roll];
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub enum ParamStatus {
+        Valid,
+        NoData,
+        Stale,
+        OutOfRange,
+        // Redundant ADC : channels disagree and the failed one can't be identified
+        Miscompare
+    }
+
+    /// Status of a parameter and time (clock ms) of the sample it comes from.
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct ParamValidity {
+        pub status: ParamStatus,
+        pub timestamp_ms: Option<u64>,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct AdcValidity {
+        params: [ParamValidity; ADC_PARAMETER_COUNT]
+    }
+
+    impl AdcValidity {
+
+        /// No data for all the parameters.
+        pub const fn new() -> Self {
+            Self {
+                params: [ParamValidity{ status: ParamStatus::NoData, timestamp_ms: None }; ADC_PARAMETER_COUNT]
+            }
+        }
+
+        pub fn get(&self, param: AdcParameter) -> ParamValidity {
+
+            self.params[param as usize]
         }
+
+        pub fn set(&mut self, param: AdcParameter, validity: ParamValidity) {
+
+            self.params[param as usize] = validity;
+        }
+
+        pub fn is_valid(&self, param: AdcParameter) -> bool {
+
+            self.get(param).status == ParamStatus::Valid
+        }
+
+        pub fn all_valid(&self, params: &[AdcParameter]) -> bool {
+
+            params.iter().all(|p| self.is_valid(*p))
+        }
+    }
+
+    // Redundant ADC health Messages
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub enum AdcChannelHealth {
+        // Used by the voter
+        Ok,
+        // Disagrees with the voted value, isolated if it lasts
+        Miscompare,
+        // Excluded from the vote until restart
+        Isolated,
+        // No data from the channel sensors
+        NoData
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct AdcHealthMessage {
+        pub channels: Vec<AdcChannelHealth>,
     }
 
     // AP Messages
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub struct APStateMessage {
 
         pub engaged: bool,
@@ -61,6 +326,8 @@ pub mod bus {
         pub vs_mode: bool,
         pub heading_mode: bool,
         pub auto_throttle_mode: bool,
+        pub lnav_mode: bool,
+        pub vnav_mode: bool,
 
         // Modes values
 
@@ -82,6 +349,8 @@ pub mod bus {
                 vs_mode: false,
                 heading_mode: false,
                 auto_throttle_mode: false,
+                lnav_mode: false,
+                vnav_mode: false,
                 alt: 15_000f32,
                 heading: 180f32,
                 speed: 250f32,
@@ -92,7 +361,7 @@ pub mod bus {
         }
     }
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub enum  APCmdPayload {
 
         APEngage(bool),
@@ -102,6 +371,8 @@ pub mod bus {
         EnaleVSMode(bool),
         EnalbeHeadingHoldMode(bool),
         EnableAutoThrottleMode(bool),
+        EnableLnavMode(bool),
+        EnableVnavMode(bool),
 
         // Modes values
 
@@ -113,25 +384,108 @@ pub mod bus {
 
     }
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub enum APTurnSide {
         Left,
         Right
     }
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub enum SpeedUnit {
         IAS,
         MACH
     }
 
+    // Provider link Messages
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub enum LinkState {
+        Connecting,
+        Receiving,
+        Stale,
+        Lost
+    }
+
+    // Navigation Messages
+
+    /// Latitude and longitude (deg, WGS84).
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct GeoPosition {
+        pub lat: f64,
+        pub lon: f64,
+    }
+
+    /// Flight plan waypoint, optional altitude constraint (ft MSL).
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Waypoint {
+        pub ident: String,
+        pub position: GeoPosition,
+        pub altitude: Option<f32>,
+    }
+
+    /// Flight plan loaded into the navigation, waypoints flown in order.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct FlightPlanMessage {
+        pub waypoints: Vec<Waypoint>,
+    }
+
+    /**
+     * Lateral navigation state : active (TO) waypoint index in the flight plan, None once the plan is flown,
+     * desired track (deg true), cross track error (NM, positive right of the leg), distance to go (NM) to the active waypoint
+     * and bank command (deg, positive right).
+     */
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct NavStateMessage {
+        pub active_waypoint: Option<usize>,
+        pub desired_track: f32,
+        pub cross_track_nm: f32,
+        pub distance_to_go_nm: f32,
+        pub bank_cmd: f32,
+
+        // Vertical guidance, None without altitude constraint in the flight plan
+        pub vnav: Option<VnavStateMessage>,
+    }
+
+    /**
+     * Vertical navigation state : cruise altitude (ft, highest constraint), distances (NM) to the top of climb and top of descent
+     * (None once reached), descent path altitude (ft) and vertical deviation (ft, positive above the path) once past the top of descent,
+     * vertical speed command (fpm) limited by the MCP altitude.
+     */
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct VnavStateMessage {
+        pub cruise_altitude: f32,
+        pub top_of_climb_nm: Option<f32>,
+        pub top_of_descent_nm: Option<f32>,
+        pub path_altitude: Option<f32>,
+        pub vertical_deviation: Option<f32>,
+        pub vs_cmd: f32,
+    }
+
     // GUI Messages
 
 }
 
 pub use bus::BusMessage;
+pub use envelope::{BusEnvelope, SequenceMonitor, SourceId};
+pub use pubsub::{Bus, OverflowPolicy, Publisher, QueueConfig, Subscription, Topic};
 pub use bus::AdcDataMessage;
+pub use bus::AdcParameter;
+pub use bus::AdcValidity;
+pub use bus::AdcChannelHealth;
+pub use bus::AdcHealthMessage;
+pub use bus::ParamStatus;
+pub use bus::ParamValidity;
+pub use bus::WindData;
+pub use bus::AhrsDataMessage;
+pub use bus::AhrsStatus;
+pub use bus::TaskStatsMessage;
 pub use bus::APStateMessage;
 pub use bus::SpeedUnit;
 pub use bus::APTurnSide;
-pub use bus::APCmdPayload;
\ No newline at end of file
+pub use bus::APCmdPayload;
+pub use bus::LinkState;
+pub use bus::GeoPosition;
+pub use bus::Waypoint;
+pub use bus::FlightPlanMessage;
+pub use bus::NavStateMessage;
+pub use bus::VnavStateMessage;
\ No newline at end of file
@@ -0,0 +1,622 @@
+/**
+ * Network bus bridge
+ * Streams the bus messages of the bridged topics as JSON lines over a local TCP or UDP socket, and publishes
+ * on the bus the AP commands (APCmdPayload JSON) received the same way, for external tools (monitoring, scripts).
+ * - tcp : the bridge listens on "address", every connected client receives the messages (one JSON per line)
+ *   and can send commands (one JSON per line)
+ * - udp : the bridge is bound to "address", messages sent (one JSON per datagram) to "peer" and to every client
+ *   which sent a datagram (an empty datagram registers a client), commands received one per datagram
+ *
+ * Outgoing line : {"topic":"adc/data","source":"ADC","sequence":12,"timestamp_ms":1250,"version":4,"message":{...}}
+ * Incoming command : {"SetAlt":12000.0}, {"APEngage":true}, {"SetHeading":{"heading":90,"turn_side":"Left"}}...
+ * or its versioned bus record (bus/codec.rs) : {"version":4,"message":{"APCmd":{"SetAlt":12000.0}}}.
+ * Binary format (udp only) : every datagram is a versioned bus record in bincode, the messages sent alone
+ * (without topic, source, sequence and timestamp), the commands received as APCmd records.
+ */
+extern crate yaml_rust;
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::time::Duration;
+use serde_json::{json, Value};
+use yaml_rust::Yaml;
+
+use crate::bus::codec::{self, BUS_FORMAT_VERSION};
+use crate::bus::{APCmdPayload, Bus, BusEnvelope, BusMessage, OverflowPolicy, Publisher, QueueConfig, SourceId, Subscription, Topic};
+use crate::lifecycle::Lifecycle;
+
+const DEFAULT_BRIDGE_ADDRESS: &str = "127.0.0.1:49100";
+const DEFAULT_BRIDGE_TOPICS: [Topic; 3] = [Topic::AdcData, Topic::ApState, Topic::LinkState];
+const BRIDGE_QUEUE: QueueConfig = QueueConfig { capacity: 256, overflow: OverflowPolicy::DropOldest };
+
+/// Bus wait of a bridge cycle, received commands and new clients handled in between.
+const BRIDGE_POLL_TIMEOUT: Duration = Duration::from_millis(20);
+
+const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+/// TCP client not reading its lines : disconnected above this size of pending output (bytes).
+const MAX_CLIENT_BACKLOG: usize = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BridgeProtocol {
+    Tcp,
+    Udp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BridgeFormat {
+    Json,
+    Binary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BridgeConfig {
+    pub protocol: BridgeProtocol,
+    pub format: BridgeFormat,
+    pub address: String,
+    pub peer: Option<String>,
+    pub topics: Vec<Topic>,
+}
+
+impl From<&Yaml> for BridgeConfig {
+
+    /// "bridge" section.
+    fn from(config: &Yaml) -> Self {
+
+        let protocol: BridgeProtocol = match config["protocol"].as_str() {
+            Some("udp") => BridgeProtocol::Udp,
+            Some("tcp") | None => BridgeProtocol::Tcp,
+            Some(protocol) => {
+                println!("[BRIDGE] Unknown protocol : {}, tcp used", protocol);
+                BridgeProtocol::Tcp
+            }
+        };
+
+        let format: BridgeFormat = match config["format"].as_str() {
+            Some("binary") if protocol == BridgeProtocol::Udp => BridgeFormat::Binary,
+            Some("binary") => {
+                println!("[BRIDGE] Binary format over udp only, json used");
+                BridgeFormat::Json
+            },
+            Some("json") | None => BridgeFormat::Json,
+            Some(format) => {
+                println!("[BRIDGE] Unknown format : {}, json used", format);
+                BridgeFormat::Json
+            }
+        };
+
+        let topics: Vec<Topic> = match config["topics"].as_vec() {
+            Some(topics) => topics.iter()
+                        .filter_map(|topic| {
+                            let name: &str = topic.as_str().unwrap_or("");
+                            let topic: Option<Topic> = Topic::from_name(name).filter(|topic| bridged(*topic));
+                            if topic.is_none() {
+                                println!("[BRIDGE] Topic not bridged : {}", name);
+                            }
+                            topic
+                        })
+                        .collect(),
+            None => DEFAULT_BRIDGE_TOPICS.to_vec()
+        };
+
+        BridgeConfig {
+            protocol,
+            format,
+            address: config["address"].as_str().unwrap_or(DEFAULT_BRIDGE_ADDRESS).to_string(),
+            peer: config["peer"].as_str().map(|peer| peer.to_string()),
+            topics,
+        }
+    }
+}
+
+/// Topics with a JSON format.
+fn bridged(topic: Topic) -> bool {
+
+    DEFAULT_BRIDGE_TOPICS.contains(&topic)
+}
+
+/// JSON line of a bus message, None for the topics not bridged.
+pub fn to_json(envelope: &BusEnvelope) -> Option<String> {
+
+    let message: Value = match &envelope.message {
+        BusMessage::AdcData(adc_data) => serde_json::to_value(adc_data),
+        BusMessage::APState(ap_state) => serde_json::to_value(ap_state),
+        BusMessage::LinkState(link_state) => serde_json::to_value(link_state),
+        _ => return None
+    }.ok()?;
+
+    let line: Value = json!({
+        "topic": envelope.message.topic().name(),
+        "source": envelope.source.to_string(),
+        "sequence": envelope.sequence,
+        "timestamp_ms": envelope.timestamp_ms,
+        "version": BUS_FORMAT_VERSION,
+        "message": message,
+    });
+
+    Some(line.to_string())
+}
+
+#[derive(Debug)]
+pub struct BridgeError(String);
+
+impl fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Bridge error: {}", self.0)
+    }
+}
+
+impl Error for BridgeError {}
+
+fn ap_command(message: BusMessage) -> Result<APCmdPayload, BridgeError> {
+
+    match message {
+        BusMessage::APCmd(ap_cmd) => Ok(ap_cmd),
+        message => Err(BridgeError(format!("{} record, AP command expected", message.topic().name())))
+    }
+}
+
+/// AP command of a JSON line, the command alone or its versioned bus record.
+pub fn parse_command(line: &str) -> Result<APCmdPayload, BridgeError> {
+
+    let value: Value = serde_json::from_str(line).map_err(|e| BridgeError(e.to_string()))?;
+
+    if value.get("version").is_none() {
+        return serde_json::from_value(value).map_err(|e| BridgeError(e.to_string()));
+    }
+
+    ap_command(codec::from_json(line).map_err(|e| BridgeError(e.to_string()))?)
+}
+
+/// AP command of a binary datagram.
+pub fn parse_binary_command(data: &[u8]) -> Result<APCmdPayload, BridgeError> {
+
+    ap_command(codec::from_binary(data).map_err(|e| BridgeError(e.to_string()))?)
+}
+
+/// Socket side of the bridge.
+trait BridgeTransport {
+
+    /// Send a message : a line (tcp) or a datagram (udp).
+    fn send(&mut self, data: &[u8]);
+
+    /// Lines (tcp, without end of line) or datagrams (udp) received since the last call, without blocking.
+    fn receive(&mut self) -> Vec<Vec<u8>>;
+
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+}
+
+struct TcpClient {
+    stream: TcpStream,
+    peer: SocketAddr,
+    buffer: Vec<u8>,
+    pending: Vec<u8>,
+    connected: bool,
+}
+
+impl TcpClient {
+
+    /// Queue the line and write the pending output without blocking.
+    fn send_line(&mut self, line: &[u8]) {
+
+        self.pending.extend_from_slice(line);
+        self.pending.push(b'\n');
+
+        while !self.pending.is_empty() {
+            match self.stream.write(&self.pending) {
+                Ok(0) => {
+                    self.connected = false;
+                    return;
+                },
+                Ok(size) => {
+                    self.pending.drain(..size);
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    self.connected = false;
+                    return;
+                }
+            }
+        }
+
+        if self.pending.len() > MAX_CLIENT_BACKLOG {
+            println!("[BRIDGE] Client too slow : {}", self.peer);
+            self.connected = false;
+        }
+    }
+
+    fn receive_lines(&mut self, lines: &mut Vec<Vec<u8>>) {
+
+        let mut data: [u8; 4096] = [0; 4096];
+
+        loop {
+            match self.stream.read(&mut data) {
+                Ok(0) => {
+                    self.connected = false;
+                    break;
+                },
+                Ok(size) => self.buffer.extend_from_slice(&data[..size]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    self.connected = false;
+                    break;
+                }
+            }
+        }
+
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let mut line: Vec<u8> = self.buffer.drain(..=end).collect();
+            line.pop();
+            lines.push(line);
+        }
+    }
+}
+
+struct TcpTransport {
+    listener: TcpListener,
+    clients: Vec<TcpClient>,
+}
+
+impl TcpTransport {
+
+    fn accept_clients(&mut self) {
+
+        loop {
+            match self.listener.accept() {
+                Ok((stream, peer)) => {
+                    if stream.set_nonblocking(true).is_ok() {
+                        println!("[BRIDGE] Client connected : {}", peer);
+                        self.clients.push(TcpClient { stream, peer, buffer: Vec::new(), pending: Vec::new(), connected: true });
+                    }
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    println!("[BRIDGE] Accept error : {:?}", e);
+                    break;
+                }
+            }
+        }
+    }
+
+    fn remove_disconnected(&mut self) {
+
+        self.clients.retain(|client| {
+            if !client.connected {
+                println!("[BRIDGE] Client disconnected : {}", client.peer);
+            }
+            client.connected
+        });
+    }
+}
+
+impl BridgeTransport for TcpTransport {
+
+    fn send(&mut self, data: &[u8]) {
+
+        for client in self.clients.iter_mut() {
+            client.send_line(data);
+        }
+
+        self.remove_disconnected();
+    }
+
+    fn receive(&mut self) -> Vec<Vec<u8>> {
+
+        self.accept_clients();
+
+        let mut lines: Vec<Vec<u8>> = Vec::new();
+        for client in self.clients.iter_mut() {
+            client.receive_lines(&mut lines);
+        }
+
+        self.remove_disconnected();
+
+        lines
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+
+        self.listener.local_addr()
+    }
+}
+
+struct UdpTransport {
+    socket: UdpSocket,
+    peers: Vec<SocketAddr>,
+}
+
+impl BridgeTransport for UdpTransport {
+
+    fn send(&mut self, data: &[u8]) {
+
+        for peer in self.peers.iter() {
+            let _ = self.socket.send_to(data, peer);
+        }
+    }
+
+    fn receive(&mut self) -> Vec<Vec<u8>> {
+
+        let mut datagrams: Vec<Vec<u8>> = Vec::new();
+        let mut data: Vec<u8> = vec![0; MAX_DATAGRAM_SIZE];
+
+        loop {
+            match self.socket.recv_from(&mut data) {
+                Ok((size, peer)) => {
+                    if !self.peers.contains(&peer) {
+                        println!("[BRIDGE] Client registered : {}", peer);
+                        self.peers.push(peer);
+                    }
+                    datagrams.push(data[..size].to_vec());
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                // ICMP port unreachable of a closed peer (Windows), nothing received.
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                Err(e) => {
+                    println!("[BRIDGE] Receive error : {:?}", e);
+                    break;
+                }
+            }
+        }
+
+        datagrams
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+
+        self.socket.local_addr()
+    }
+}
+
+pub struct NetworkBridge {
+    subscription: Subscription,
+    publisher: Publisher,
+    transport: Box<dyn BridgeTransport + Send>,
+    format: BridgeFormat,
+}
+
+impl NetworkBridge {
+
+    /// Bridge bound to the config address, subscribed to the bridged topics.
+    pub fn bind(config: &BridgeConfig, bus: &Bus) -> io::Result<NetworkBridge> {
+
+        let transport: Box<dyn BridgeTransport + Send> = match config.protocol {
+            BridgeProtocol::Tcp => {
+                let listener: TcpListener = TcpListener::bind(&config.address)?;
+                listener.set_nonblocking(true)?;
+                Box::new(TcpTransport { listener, clients: Vec::new() })
+            },
+            BridgeProtocol::Udp => {
+                let socket: UdpSocket = UdpSocket::bind(&config.address)?;
+                socket.set_nonblocking(true)?;
+                let peers: Vec<SocketAddr> = match &config.peer {
+                    Some(peer) => vec![peer.parse().map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?],
+                    None => Vec::new()
+                };
+                Box::new(UdpTransport { socket, peers })
+            }
+        };
+
+        println!("[BRIDGE] {:?} {:?} bridge on {}", config.protocol, config.format, transport.local_addr()?);
+
+        Ok(NetworkBridge {
+            subscription: bus.subscribe("BRIDGE", &config.topics, BRIDGE_QUEUE),
+            publisher: bus.publisher(SourceId::Bridge),
+            transport,
+            format: config.format,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn local_addr(&self) -> SocketAddr {
+
+        self.transport.local_addr().unwrap()
+    }
+
+    /// Publish the received commands, then stream the bus messages received during `timeout` at most.
+    pub fn poll(&mut self, timeout: Duration) {
+
+        for data in self.transport.receive() {
+            let ap_cmd: Result<APCmdPayload, BridgeError> = match self.format {
+                BridgeFormat::Json => {
+                    let line: String = String::from_utf8_lossy(&data).trim().to_string();
+                    if line.is_empty() {
+                        continue;
+                    }
+                    parse_command(&line)
+                },
+                BridgeFormat::Binary => {
+                    if data.is_empty() {
+                        continue;
+                    }
+                    parse_binary_command(&data)
+                }
+            };
+
+            match ap_cmd {
+                Ok(ap_cmd) => {
+                    self.publisher.publish(BusMessage::APCmd(ap_cmd));
+                },
+                Err(e) => println!("[BRIDGE] Invalid command : {}", e),
+            }
+        }
+
+        let mut next_envelope: Option<BusEnvelope> = self.subscription.recv_timeout(timeout);
+        while let Some(envelope) = next_envelope {
+            let data: Option<Vec<u8>> = match self.format {
+                BridgeFormat::Json => to_json(&envelope).map(String::into_bytes),
+                BridgeFormat::Binary => codec::to_binary(&envelope.message).ok(),
+            };
+            if let Some(data) = data {
+                self.transport.send(&data);
+            }
+            next_envelope = self.subscription.recv_timeout(Duration::ZERO);
+        }
+    }
+
+    pub fn run(&mut self, lifecycle: &Lifecycle) {
+
+        while lifecycle.is_running() {
+            self.poll(BRIDGE_POLL_TIMEOUT);
+        }
+    }
+}
+
+#[cfg(test)]
+fn test_bridge(protocol: BridgeProtocol, format: BridgeFormat, bus: &Bus) -> NetworkBridge {
+
+    let config: BridgeConfig = BridgeConfig { protocol, format, address: "127.0.0.1:0".to_string(), peer: None, topics: DEFAULT_BRIDGE_TOPICS.to_vec() };
+
+    NetworkBridge::bind(&config, bus).unwrap()
+}
+
+#[test]
+fn bridge_tcp_loopback() {
+
+    use std::io::{BufRead, BufReader};
+    use crate::bus::{AdcDataMessage, LinkState};
+
+    let bus: Bus = Bus::new();
+    let adc: Publisher = bus.publisher(SourceId::Adc);
+    let ap: Subscription = bus.subscribe("AP", &[Topic::ApCmd], QueueConfig { capacity: 8, overflow: OverflowPolicy::DropOldest });
+    let mut bridge: NetworkBridge = test_bridge(BridgeProtocol::Tcp, BridgeFormat::Json, &bus);
+
+    let mut client: TcpStream = TcpStream::connect(bridge.local_addr()).unwrap();
+    client.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    bridge.poll(Duration::ZERO);
+
+    // ADC data streamed, AHRS data not bridged.
+    let mut adc_data: AdcDataMessage = AdcDataMessage::new();
+    adc_data.ias = 250.0;
+    adc.publish(BusMessage::AhrsData(crate::bus::AhrsDataMessage::new()));
+    adc.publish(BusMessage::AdcData(Box::new(adc_data)));
+    adc.publish(BusMessage::LinkState(LinkState::Receiving));
+    bridge.poll(Duration::from_millis(10));
+
+    let mut reader: BufReader<TcpStream> = BufReader::new(client.try_clone().unwrap());
+    let mut line: String = String::new();
+    reader.read_line(&mut line).unwrap();
+    let message: Value = serde_json::from_str(&line).unwrap();
+    assert_eq!("adc/data", message["topic"]);
+    assert_eq!("ADC", message["source"]);
+    assert_eq!(BUS_FORMAT_VERSION, message["version"]);
+    assert_eq!(250.0, message["message"]["ias"]);
+
+    line.clear();
+    reader.read_line(&mut line).unwrap();
+    let message: Value = serde_json::from_str(&line).unwrap();
+    assert_eq!("adc/link", message["topic"]);
+    assert_eq!("Receiving", message["message"]);
+
+    // Commands and versioned command records published on the bus, invalid ones ignored.
+    client.write_all(b"{\"SetAlt\":12000.0}\n{\"Unknown\":1}\n{\"SetHeading\":{\"heading\":90,\"turn_side\":\"Left\"}}\n").unwrap();
+    client.write_all(b"{\"version\":3,\"message\":{\"APCmd\":{\"SetAlt\":9000.0}}}\n{\"version\":4,\"message\":{\"APCmd\":{\"SetVs\":-500.0}}}\n").unwrap();
+    for _ in 0..50 {
+        bridge.poll(Duration::from_millis(5));
+    }
+
+    let envelope: BusEnvelope = ap.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(SourceId::Bridge, envelope.source);
+    assert!(matches!(envelope.message, BusMessage::APCmd(APCmdPayload::SetAlt(alt)) if alt == 12000.0));
+    let envelope: BusEnvelope = ap.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert!(matches!(envelope.message, BusMessage::APCmd(APCmdPayload::SetHeading{ heading: 90, .. })));
+    let envelope: BusEnvelope = ap.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert!(matches!(envelope.message, BusMessage::APCmd(APCmdPayload::SetVs(vs)) if vs == -500.0));
+}
+
+#[test]
+fn bridge_udp_loopback() {
+
+    use crate::bus::APStateMessage;
+
+    let bus: Bus = Bus::new();
+    let ap: Publisher = bus.publisher(SourceId::Ap);
+    let ap_cmds: Subscription = bus.subscribe("AP", &[Topic::ApCmd], QueueConfig { capacity: 8, overflow: OverflowPolicy::DropOldest });
+    let mut bridge: NetworkBridge = test_bridge(BridgeProtocol::Udp, BridgeFormat::Json, &bus);
+
+    let client: UdpSocket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+
+    // Command registers the client.
+    client.send_to(b"{\"APEngage\":true}", bridge.local_addr()).unwrap();
+    for _ in 0..50 {
+        bridge.poll(Duration::from_millis(5));
+        if let Some(envelope) = ap_cmds.recv_timeout(Duration::ZERO) {
+            assert!(matches!(envelope.message, BusMessage::APCmd(APCmdPayload::APEngage(true))));
+            break;
+        }
+    }
+
+    ap.publish(BusMessage::APState(APStateMessage::new()));
+    bridge.poll(Duration::from_millis(10));
+
+    let mut data: [u8; 2048] = [0; 2048];
+    let (size, _) = client.recv_from(&mut data).unwrap();
+    let message: Value = serde_json::from_slice(&data[..size]).unwrap();
+    assert_eq!("ap/state", message["topic"]);
+    assert_eq!(15000.0, message["message"]["alt"]);
+    assert_eq!("IAS", message["message"]["speed_unit"]);
+}
+
+#[test]
+fn bridge_udp_binary() {
+
+    use crate::bus::LinkState;
+
+    let bus: Bus = Bus::new();
+    let adc: Publisher = bus.publisher(SourceId::Adc);
+    let ap_cmds: Subscription = bus.subscribe("AP", &[Topic::ApCmd], QueueConfig { capacity: 8, overflow: OverflowPolicy::DropOldest });
+    let mut bridge: NetworkBridge = test_bridge(BridgeProtocol::Udp, BridgeFormat::Binary, &bus);
+
+    let client: UdpSocket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+
+    // Empty datagram registers the client, records other than AP commands ignored.
+    client.send_to(&[], bridge.local_addr()).unwrap();
+    client.send_to(&codec::to_binary(&BusMessage::LinkState(LinkState::Stale)).unwrap(), bridge.local_addr()).unwrap();
+    client.send_to(&codec::to_binary(&BusMessage::APCmd(APCmdPayload::SetAlt(8000.0))).unwrap(), bridge.local_addr()).unwrap();
+    let mut ap_cmd: Option<BusEnvelope> = None;
+    for _ in 0..50 {
+        bridge.poll(Duration::from_millis(5));
+        ap_cmd = ap_cmds.recv_timeout(Duration::ZERO);
+        if ap_cmd.is_some() {
+            break;
+        }
+    }
+    assert!(matches!(ap_cmd.unwrap().message, BusMessage::APCmd(APCmdPayload::SetAlt(alt)) if alt == 8000.0));
+    assert!(ap_cmds.recv_timeout(Duration::ZERO).is_none());
+
+    adc.publish(BusMessage::LinkState(LinkState::Receiving));
+    bridge.poll(Duration::from_millis(10));
+
+    let mut data: [u8; 2048] = [0; 2048];
+    let (size, _) = client.recv_from(&mut data).unwrap();
+    assert_eq!(BusMessage::LinkState(LinkState::Receiving), codec::from_binary(&data[..size]).unwrap());
+}
+
+#[test]
+fn bridge_config() {
+
+    use yaml_rust::YamlLoader;
+
+    let config: BridgeConfig = BridgeConfig::from(&YamlLoader::load_from_str("protocol: udp\npeer: 127.0.0.1:49101\ntopics: [ap/state, ahrs/data]").unwrap()[0]);
+    assert_eq!(BridgeProtocol::Udp, config.protocol);
+    assert_eq!(BridgeFormat::Json, config.format);
+    assert_eq!(DEFAULT_BRIDGE_ADDRESS, config.address);
+    assert_eq!(Some("127.0.0.1:49101".to_string()), config.peer);
+    assert_eq!(vec![Topic::ApState], config.topics);
+
+    let config: BridgeConfig = BridgeConfig::from(&YamlLoader::load_from_str("protocol: udp\nformat: binary").unwrap()[0]);
+    assert_eq!(BridgeFormat::Binary, config.format);
+
+    // Binary over udp only.
+    let config: BridgeConfig = BridgeConfig::from(&YamlLoader::load_from_str("format: binary").unwrap()[0]);
+    assert_eq!(BridgeFormat::Json, config.format);
+
+    let config: BridgeConfig = BridgeConfig::from(&Yaml::BadValue);
+    assert_eq!(BridgeProtocol::Tcp, config.protocol);
+    assert_eq!(DEFAULT_BRIDGE_TOPICS.to_vec(), config.topics);
+}
@@ -0,0 +1,163 @@
+/**
+ * Bus messages persisted format
+ * Every bus message type serializes to bincode (compact, bridge binary format) and reads from JSON (bridge commands),
+ * wrapped in a versioned record : {"version":4,"message":{"APCmd":{...}}}.
+ * The version is increased on any incompatible change of a message (field renamed, removed or retyped),
+ * a record of another version is rejected instead of being misread.
+ * Version 2 : aircraft position in the ADC data, AP LNAV mode, flight plan and navigation state messages.
+ * Version 3 : waypoint altitude constraint.
+ * Version 4 : AP VNAV mode, vertical navigation state.
+ */
+extern crate bincode;
+
+use std::error::Error;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::bus::BusMessage;
+
+/// Version of the bus messages format.
+pub const BUS_FORMAT_VERSION: u16 = 4;
+
+#[derive(Debug)]
+pub enum CodecError {
+    Json(serde_json::Error),
+    Binary(bincode::Error),
+    UnsupportedVersion(u16),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodecError::Json(e) => write!(f, "Codec error: JSON {}", e),
+            CodecError::Binary(e) => write!(f, "Codec error: binary {}", e),
+            CodecError::UnsupportedVersion(version) => write!(f, "Codec error: version {} unsupported (version {} expected)", version, BUS_FORMAT_VERSION),
+        }
+    }
+}
+
+impl Error for CodecError {}
+
+#[derive(Serialize)]
+struct VersionedRef<'a> {
+    version: u16,
+    message: &'a BusMessage,
+}
+
+/// Version checked on the header first, read here for the binary layout.
+#[derive(Deserialize)]
+struct Versioned {
+    #[serde(rename = "version")]
+    _version: u16,
+    message: BusMessage,
+}
+
+/// Version read first : the message of another version is not decoded.
+#[derive(Deserialize)]
+struct VersionHeader {
+    version: u16,
+}
+
+fn versioned(message: &BusMessage) -> VersionedRef<'_> {
+
+    VersionedRef { version: BUS_FORMAT_VERSION, message }
+}
+
+fn check_version(version: u16) -> Result<(), CodecError> {
+
+    if version == BUS_FORMAT_VERSION {
+        Ok(())
+    } else {
+        Err(CodecError::UnsupportedVersion(version))
+    }
+}
+
+pub fn from_json(json: &str) -> Result<BusMessage, CodecError> {
+
+    let header: VersionHeader = serde_json::from_str(json).map_err(CodecError::Json)?;
+    check_version(header.version)?;
+
+    let record: Versioned = serde_json::from_str(json).map_err(CodecError::Json)?;
+
+    Ok(record.message)
+}
+
+pub fn to_binary(message: &BusMessage) -> Result<Vec<u8>, CodecError> {
+
+    bincode::serialize(&versioned(message)).map_err(CodecError::Binary)
+}
+
+pub fn from_binary(data: &[u8]) -> Result<BusMessage, CodecError> {
+
+    // Version first in the record : the header is a prefix of the data.
+    let header: VersionHeader = bincode::deserialize(data).map_err(CodecError::Binary)?;
+    check_version(header.version)?;
+
+    let record: Versioned = bincode::deserialize(data).map_err(CodecError::Binary)?;
+
+    Ok(record.message)
+}
+
+#[cfg(test)]
+fn test_messages() -> Vec<BusMessage> {
+
+    use crate::bus::*;
+
+    let mut adc_data: AdcDataMessage = AdcDataMessage::new();
+    adc_data.ias = 250.5;
+    adc_data.ground_speed = Some(270.0);
+    adc_data.position = Some(GeoPosition{ lat: 45.7256, lon: 5.0811 });
+    adc_data.wind = Some(WindData{ direction: 270.0, speed: 20.0, headwind: -1.5, crosswind: 19.9 });
+    adc_data.validity.set(AdcParameter::Ias, ParamValidity{ status: ParamStatus::Valid, timestamp_ms: Some(1250) });
+
+    let mut ahrs_data: AhrsDataMessage = AhrsDataMessage::new();
+    ahrs_data.status = AhrsStatus::Aligned;
+    ahrs_data.roll_drift = Some(-0.25);
+
+    vec![
+        BusMessage::AdcData(Box::new(adc_data)),
+        BusMessage::APState(APStateMessage::new()),
+        BusMessage::APCmd(APCmdPayload::SetHeading{ heading: 90, turn_side: APTurnSide::Left }),
+        BusMessage::APCmd(APCmdPayload::SetSpeed{ speed: 0.78, unit: SpeedUnit::MACH }),
+        BusMessage::APCmd(APCmdPayload::SetBankAngle(-25)),
+        BusMessage::LinkState(LinkState::Stale),
+        BusMessage::AdcHealth(AdcHealthMessage{ channels: vec![AdcChannelHealth::Ok, AdcChannelHealth::Isolated] }),
+        BusMessage::AhrsData(ahrs_data),
+        BusMessage::FlightPlan(FlightPlanMessage{ waypoints: vec![Waypoint{ ident: "LFLL".to_string(), position: GeoPosition{ lat: 45.7256, lon: 5.0811 }, altitude: Some(3000.0) }] }),
+        BusMessage::NavState(NavStateMessage{ active_waypoint: Some(1), desired_track: 92.5, cross_track_nm: -0.4, distance_to_go_nm: 12.3, bank_cmd: 5.0,
+                    vnav: Some(VnavStateMessage{ cruise_altitude: 25000.0, top_of_climb_nm: None, top_of_descent_nm: Some(42.0), path_altitude: None, vertical_deviation: None, vs_cmd: 0.0 }) }),
+        BusMessage::TaskStats(TaskStatsMessage{ task: "ADC".to_string(), frequency_hz: 50.0, cycles: 50, exec_time_avg_ms: 0.2,
+                    exec_time_max_ms: 1.1, jitter_avg_ms: 0.05, jitter_max_ms: 0.4, dt_min_ms: 19.6, dt_max_ms: 20.4, overruns: 2 }),
+    ]
+}
+
+#[test]
+fn codec_round_trips() {
+
+    for message in test_messages() {
+
+        let json: String = serde_json::to_string(&versioned(&message)).unwrap();
+        assert_eq!(message, from_json(&json).unwrap(), "JSON {}", json);
+
+        let binary: Vec<u8> = to_binary(&message).unwrap();
+        assert_eq!(message, from_binary(&binary).unwrap());
+        assert!(binary.len() < json.len());
+    }
+}
+
+#[test]
+fn codec_stable_format() {
+
+    use crate::bus::{APCmdPayload, LinkState};
+
+    // Persisted records : a change of these strings requires a new format version.
+    assert_eq!(r#"{"version":4,"message":{"APCmd":{"SetAlt":12000.0}}}"#, serde_json::to_string(&versioned(&BusMessage::APCmd(APCmdPayload::SetAlt(12000.0)))).unwrap());
+    assert_eq!(r#"{"version":4,"message":{"LinkState":"Receiving"}}"#, serde_json::to_string(&versioned(&BusMessage::LinkState(LinkState::Receiving))).unwrap());
+    assert_eq!(vec![4, 0, 3, 0, 0, 0, 1, 0, 0, 0], to_binary(&BusMessage::LinkState(LinkState::Receiving)).unwrap());
+
+    // Other versions rejected.
+    assert!(matches!(from_json(r#"{"version":3,"message":{"LinkState":"Receiving"}}"#), Err(CodecError::UnsupportedVersion(3))));
+    assert!(matches!(from_binary(&[3, 0, 3, 0, 0, 0, 1, 0, 0, 0]), Err(CodecError::UnsupportedVersion(3))));
+    assert!(matches!(from_json(r#"{"version":4,"message":{"Unknown":1}}"#), Err(CodecError::Json(_))));
+    assert!(matches!(from_binary(&[4, 0, 3]), Err(CodecError::Binary(_))));
+}
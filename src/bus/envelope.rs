@@ -0,0 +1,131 @@
+/**
+ * Bus message envelope
+ * Every bus message is sent wrapped in an envelope :
+ * - timestamp : clock time (ms) when the message was sent, gives the message age to the consumers
+ * - source : producer of the message (ADC, ADC channel, AHRS, AP, GUI, scheduler statistics of a task)
+ * - sequence : number of the message in the stream of its source on its topic, gaps tell the consumers messages were lost
+ *
+ * Each publisher numbers its messages from 0 on each topic (see pubsub.rs).
+ */
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use crate::bus::{BusMessage, Topic};
+use crate::clock;
+
+/// Producer of a bus message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceId {
+    Adc,
+    AdcChannel(u8),
+    Ahrs,
+    Ap,
+    Gui,
+    Bridge,
+    Mavlink,
+    Arinc429,
+    FlightPlanFile,
+    Scheduler(&'static str),
+}
+
+impl fmt::Display for SourceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SourceId::Adc => write!(f, "ADC"),
+            SourceId::AdcChannel(channel) => write!(f, "ADC{}", channel),
+            SourceId::Ahrs => write!(f, "AHRS"),
+            SourceId::Ap => write!(f, "AP"),
+            SourceId::Gui => write!(f, "GUI"),
+            SourceId::Bridge => write!(f, "BRIDGE"),
+            SourceId::Mavlink => write!(f, "MAVLINK"),
+            SourceId::Arinc429 => write!(f, "A429"),
+            SourceId::FlightPlanFile => write!(f, "FPL"),
+            SourceId::Scheduler(task) => write!(f, "SCHED {}", task),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BusEnvelope {
+    pub timestamp_ms: u64,
+    pub source: SourceId,
+    pub sequence: u64,
+    pub message: BusMessage,
+}
+
+impl BusEnvelope {
+
+    /// Time elapsed since the message was sent.
+    pub fn age(&self) -> Duration {
+
+        self.age_at(clock::now_ms())
+    }
+
+    /// Age of the message at `now_ms` (clock ms).
+    pub fn age_at(&self, now_ms: u64) -> Duration {
+
+        Duration::from_millis(now_ms.saturating_sub(self.timestamp_ms))
+    }
+}
+
+/// Sequence numbers check of a consumer, per source and topic.
+pub struct SequenceMonitor {
+    last_sequences: HashMap<(SourceId, Topic), u64>,
+    lost: u64,
+}
+
+impl SequenceMonitor {
+
+    pub fn new() -> SequenceMonitor {
+
+        SequenceMonitor { last_sequences: HashMap::new(), lost: 0 }
+    }
+
+    /// Number of messages of the source lost on the topic before this one (0 in sequence, the first message received starts the sequence).
+    pub fn check(&mut self, envelope: &BusEnvelope) -> u64 {
+
+        let key: (SourceId, Topic) = (envelope.source, envelope.message.topic());
+
+        let gap: u64 = match self.last_sequences.get(&key) {
+            Some(last_sequence) if envelope.sequence > *last_sequence => envelope.sequence - last_sequence - 1,
+            _ => 0
+        };
+
+        self.last_sequences.insert(key, envelope.sequence);
+        self.lost += gap;
+
+        gap
+    }
+
+    /// Messages lost since start, all sources.
+    pub fn lost(&self) -> u64 {
+
+        self.lost
+    }
+}
+
+#[test]
+fn envelope_sequences_and_gaps() {
+
+    use crate::bus::{AhrsDataMessage, LinkState};
+
+    let envelope = |source: SourceId, sequence: u64, message: BusMessage| BusEnvelope { timestamp_ms: 1000, source, sequence, message };
+    let link_state = || BusMessage::LinkState(LinkState::Receiving);
+
+    assert_eq!("ADC", SourceId::Adc.to_string());
+    assert_eq!("ADC2", SourceId::AdcChannel(2).to_string());
+
+    // Second ADC link state lost, sequences independent per source and topic.
+    let mut monitor: SequenceMonitor = SequenceMonitor::new();
+    assert_eq!(0, monitor.check(&envelope(SourceId::Adc, 0, link_state())));
+    assert_eq!(0, monitor.check(&envelope(SourceId::Adc, 0, BusMessage::AhrsData(AhrsDataMessage::new()))));
+    assert_eq!(1, monitor.check(&envelope(SourceId::Adc, 2, link_state())));
+    assert_eq!(0, monitor.check(&envelope(SourceId::Ahrs, 5, link_state())));
+    assert_eq!(0, monitor.check(&envelope(SourceId::Ahrs, 6, link_state())));
+    assert_eq!(1, monitor.lost());
+
+    let envelope: BusEnvelope = envelope(SourceId::Adc, 0, link_state());
+    assert_eq!(Duration::from_millis(250), envelope.age_at(envelope.timestamp_ms + 250));
+    assert_eq!(Duration::ZERO, envelope.age_at(0));
+}
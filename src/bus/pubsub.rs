@@ -0,0 +1,407 @@
+/**
+ * Publish/subscribe bus
+ * Messages are published on named topics (one topic per message type), a producer publishes each message once :
+ * - publisher : source of the messages, envelopes numbered per topic
+ * - subscription : consumer of a set of topics, own bounded queue with an overflow policy
+ *   (drop the oldest message, drop the newest message, or block the publisher for a while then drop the newest)
+ *
+ * New consumers (recorder, monitor, network bridge) subscribe to the bus without any change of the producers.
+ */
+extern crate yaml_rust;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::time::Duration;
+use yaml_rust::Yaml;
+
+use crate::bus::{BusEnvelope, BusMessage, SourceId};
+use crate::clock;
+
+pub const TOPIC_COUNT: usize = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Topic {
+    AdcData,
+    AdcHealth,
+    LinkState,
+    AhrsData,
+    ApState,
+    ApCmd,
+    TaskStats,
+    FlightPlan,
+    NavState,
+}
+
+impl Topic {
+
+    pub const ALL: [Topic; TOPIC_COUNT] = [
+        Topic::AdcData,
+        Topic::AdcHealth,
+        Topic::LinkState,
+        Topic::AhrsData,
+        Topic::ApState,
+        Topic::ApCmd,
+        Topic::TaskStats,
+        Topic::FlightPlan,
+        Topic::NavState,
+    ];
+
+    pub fn name(&self) -> &'static str {
+
+        match self {
+            Topic::AdcData => "adc/data",
+            Topic::AdcHealth => "adc/health",
+            Topic::LinkState => "adc/link",
+            Topic::AhrsData => "ahrs/data",
+            Topic::ApState => "ap/state",
+            Topic::ApCmd => "ap/cmd",
+            Topic::TaskStats => "tasks/stats",
+            Topic::FlightPlan => "nav/plan",
+            Topic::NavState => "nav/state",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Topic> {
+
+        Topic::ALL.into_iter().find(|topic| topic.name() == name)
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl BusMessage {
+
+    /// Topic the message is published on.
+    pub fn topic(&self) -> Topic {
+
+        match self {
+            BusMessage::AdcData(_) => Topic::AdcData,
+            BusMessage::AdcHealth(_) => Topic::AdcHealth,
+            BusMessage::LinkState(_) => Topic::LinkState,
+            BusMessage::AhrsData(_) => Topic::AhrsData,
+            BusMessage::APState(_) => Topic::ApState,
+            BusMessage::APCmd(_) => Topic::ApCmd,
+            BusMessage::TaskStats(_) => Topic::TaskStats,
+            BusMessage::FlightPlan(_) => Topic::FlightPlan,
+            BusMessage::NavState(_) => Topic::NavState,
+        }
+    }
+}
+
+/// Policy of a full subscription queue.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverflowPolicy {
+    DropOldest,
+    DropNewest,
+    /// Publisher blocked until the queue has room, at most this delay (then the new message is dropped).
+    Block(Duration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueueConfig {
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+}
+
+/**
+ * Queue config of a subscriber section ("capacity", "overflow" : drop_oldest, drop_newest or block with "block_ms"),
+ * `default` for the missing values.
+ */
+pub fn queue_config(config: &Yaml, default: QueueConfig) -> QueueConfig {
+
+    let capacity: usize = match config["capacity"].as_i64() {
+        Some(capacity) if capacity > 0 => capacity as usize,
+        _ => default.capacity
+    };
+
+    let overflow: OverflowPolicy = match config["overflow"].as_str() {
+        Some("drop_oldest") => OverflowPolicy::DropOldest,
+        Some("drop_newest") => OverflowPolicy::DropNewest,
+        Some("block") => OverflowPolicy::Block(Duration::from_millis(config["block_ms"].as_i64().filter(|ms| *ms > 0).unwrap_or(10) as u64)),
+        Some(overflow) => {
+            println!("[BUS] Unknown overflow policy : {}", overflow);
+            default.overflow
+        },
+        None => default.overflow
+    };
+
+    QueueConfig { capacity, overflow }
+}
+
+struct Queue {
+    name: String,
+    topics: Vec<Topic>,
+    config: QueueConfig,
+    envelopes: Mutex<VecDeque<BusEnvelope>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    dropped: AtomicU64,
+}
+
+impl Queue {
+
+    fn push(&self, envelope: BusEnvelope) {
+
+        let mut envelopes = self.envelopes.lock().unwrap();
+
+        if envelopes.len() >= self.config.capacity {
+            match self.config.overflow {
+                OverflowPolicy::DropOldest => {
+                    envelopes.pop_front();
+                    self.dropped.fetch_add(1, Ordering::SeqCst);
+                },
+                OverflowPolicy::DropNewest => {
+                    self.dropped.fetch_add(1, Ordering::SeqCst);
+                    return;
+                },
+                OverflowPolicy::Block(timeout) => {
+                    let (guard, result) = self.not_full
+                                .wait_timeout_while(envelopes, timeout, |envelopes| envelopes.len() >= self.config.capacity)
+                                .unwrap();
+                    envelopes = guard;
+
+                    if result.timed_out() {
+                        self.dropped.fetch_add(1, Ordering::SeqCst);
+                        return;
+                    }
+                }
+            }
+        }
+
+        envelopes.push_back(envelope);
+        self.not_empty.notify_one();
+    }
+
+    fn pop(&self, timeout: Duration) -> Option<BusEnvelope> {
+
+        let envelopes = self.envelopes.lock().unwrap();
+        let (mut envelopes, _) = self.not_empty
+                    .wait_timeout_while(envelopes, timeout, |envelopes| envelopes.is_empty())
+                    .unwrap();
+
+        let envelope: Option<BusEnvelope> = envelopes.pop_front();
+        if envelope.is_some() {
+            self.not_full.notify_one();
+        }
+
+        envelope
+    }
+}
+
+/// Bus shared by the publishers and subscribers (cloned for each component).
+#[derive(Clone)]
+pub struct Bus {
+    queues: Arc<RwLock<Vec<Arc<Queue>>>>,
+}
+
+impl Bus {
+
+    pub fn new() -> Bus {
+
+        Bus { queues: Arc::new(RwLock::new(Vec::new())) }
+    }
+
+    pub fn publisher(&self, source: SourceId) -> Publisher {
+
+        Publisher {
+            bus: self.clone(),
+            source,
+            sequences: Default::default(),
+        }
+    }
+
+    /// Subscribe `name` to the messages of `topics`, received in a queue of its own.
+    pub fn subscribe(&self, name: &str, topics: &[Topic], config: QueueConfig) -> Subscription {
+
+        let queue: Arc<Queue> = Arc::new(Queue {
+            name: name.to_string(),
+            topics: topics.to_vec(),
+            config,
+            envelopes: Mutex::new(VecDeque::with_capacity(config.capacity)),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            dropped: AtomicU64::new(0),
+        });
+
+        let topics: Vec<&str> = topics.iter().map(|topic| topic.name()).collect();
+        println!("[BUS] {} subscribed to {} (capacity {}, {:?})", name, topics.join(", "), config.capacity, config.overflow);
+
+        self.queues.write().unwrap().push(queue.clone());
+
+        Subscription { bus: self.clone(), queue }
+    }
+
+    fn deliver(&self, envelope: BusEnvelope) -> usize {
+
+        let topic: Topic = envelope.message.topic();
+
+        // Queues copied : a blocking queue must not hold the subscribers list.
+        let queues: Vec<Arc<Queue>> = self.queues.read().unwrap()
+                    .iter()
+                    .filter(|queue| queue.topics.contains(&topic))
+                    .cloned()
+                    .collect();
+
+        for queue in queues.iter() {
+            queue.push(envelope.clone());
+        }
+
+        queues.len()
+    }
+}
+
+/// Publishing side of a source, messages timestamped and numbered per topic.
+pub struct Publisher {
+    bus: Bus,
+    source: SourceId,
+    sequences: [AtomicU64; TOPIC_COUNT],
+}
+
+impl Publisher {
+
+    /// Publish the message to the subscribers of its topic, returns the number of subscribers.
+    pub fn publish(&self, message: BusMessage) -> usize {
+
+        let topic: Topic = message.topic();
+
+        let envelope: BusEnvelope = BusEnvelope {
+            timestamp_ms: clock::now_ms(),
+            source: self.source,
+            sequence: self.sequences[topic as usize].fetch_add(1, Ordering::SeqCst),
+            message,
+        };
+
+        self.bus.deliver(envelope)
+    }
+}
+
+/// Receiving side of a subscriber, unsubscribed when dropped.
+pub struct Subscription {
+    bus: Bus,
+    queue: Arc<Queue>,
+}
+
+impl Subscription {
+
+    pub fn name(&self) -> &str {
+
+        &self.queue.name
+    }
+
+    /// Wait for a message during `timeout` at most.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<BusEnvelope> {
+
+        self.queue.pop(timeout)
+    }
+
+    #[cfg(test)]
+    pub fn try_recv(&self) -> Option<BusEnvelope> {
+
+        self.queue.pop(Duration::ZERO)
+    }
+
+    /// Messages dropped by the overflow policy since start.
+    pub fn dropped(&self) -> u64 {
+
+        self.queue.dropped.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for Subscription {
+
+    fn drop(&mut self) {
+
+        self.bus.queues.write().unwrap().retain(|queue| !Arc::ptr_eq(queue, &self.queue));
+    }
+}
+
+#[cfg(test)]
+fn test_queue(capacity: usize, overflow: OverflowPolicy) -> QueueConfig {
+
+    QueueConfig { capacity, overflow }
+}
+
+#[test]
+fn bus_topics_subscriptions() {
+
+    use crate::bus::LinkState;
+
+    let bus: Bus = Bus::new();
+    let adc: Publisher = bus.publisher(SourceId::Adc);
+    let ap: Subscription = bus.subscribe("AP", &[Topic::LinkState, Topic::ApCmd], test_queue(8, OverflowPolicy::DropOldest));
+    let monitor: Subscription = bus.subscribe("MONITOR", &[Topic::LinkState, Topic::AhrsData, Topic::TaskStats], test_queue(8, OverflowPolicy::DropOldest));
+
+    assert_eq!(2, adc.publish(BusMessage::LinkState(LinkState::Receiving)));
+    assert_eq!(1, adc.publish(BusMessage::AhrsData(crate::bus::AhrsDataMessage::new())));
+    assert_eq!(2, adc.publish(BusMessage::LinkState(LinkState::Lost)));
+
+    // Topics filtered, sequence per topic.
+    let envelope: BusEnvelope = ap.try_recv().unwrap();
+    assert_eq!((SourceId::Adc, 0), (envelope.source, envelope.sequence));
+    assert_eq!(1, ap.recv_timeout(Duration::from_millis(10)).unwrap().sequence);
+    assert!(ap.try_recv().is_none());
+
+    let topics: Vec<Topic> = std::iter::from_fn(|| monitor.try_recv()).map(|e| e.message.topic()).collect();
+    assert_eq!(vec![Topic::LinkState, Topic::AhrsData, Topic::LinkState], topics);
+
+    // Unsubscribed when dropped.
+    drop(monitor);
+    assert_eq!(1, adc.publish(BusMessage::LinkState(LinkState::Receiving)));
+    assert_eq!("AP", ap.name());
+}
+
+#[test]
+fn bus_overflow_policies() {
+
+    use crate::bus::LinkState;
+
+    let bus: Bus = Bus::new();
+    let adc: Publisher = bus.publisher(SourceId::Adc);
+    let oldest: Subscription = bus.subscribe("OLDEST", &[Topic::LinkState], test_queue(2, OverflowPolicy::DropOldest));
+    let newest: Subscription = bus.subscribe("NEWEST", &[Topic::LinkState], test_queue(2, OverflowPolicy::DropNewest));
+    let block: Subscription = bus.subscribe("BLOCK", &[Topic::LinkState], test_queue(2, OverflowPolicy::Block(Duration::from_millis(5))));
+
+    for _ in 0..4 {
+        adc.publish(BusMessage::LinkState(LinkState::Receiving));
+    }
+
+    let sequences = |subscription: &Subscription| std::iter::from_fn(|| subscription.try_recv()).map(|e| e.sequence).collect::<Vec<u64>>();
+    assert_eq!(vec![2, 3], sequences(&oldest));
+    assert_eq!(vec![0, 1], sequences(&newest));
+    assert_eq!(vec![0, 1], sequences(&block));
+    assert_eq!((2, 2, 2), (oldest.dropped(), newest.dropped(), block.dropped()));
+
+    // Blocked publisher released by the subscriber.
+    let waiting: Subscription = bus.subscribe("WAITING", &[Topic::AdcHealth], test_queue(1, OverflowPolicy::Block(Duration::from_secs(1))));
+    adc.publish(BusMessage::AdcHealth(crate::bus::AdcHealthMessage{ channels: Vec::new() }));
+    let consumer = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        let envelope: Option<BusEnvelope> = waiting.try_recv();
+        (envelope.map(|e| e.sequence), waiting)
+    });
+    adc.publish(BusMessage::AdcHealth(crate::bus::AdcHealthMessage{ channels: Vec::new() }));
+    let (first, waiting) = consumer.join().unwrap();
+    assert_eq!(Some(0), first);
+    assert_eq!(Some(1), waiting.try_recv().map(|e| e.sequence));
+    assert_eq!(0, waiting.dropped());
+}
+
+#[test]
+fn bus_queue_config() {
+
+    use yaml_rust::YamlLoader;
+
+    let default: QueueConfig = test_queue(64, OverflowPolicy::DropOldest);
+    let config = |yaml: &str| queue_config(&YamlLoader::load_from_str(yaml).unwrap()[0], default);
+
+    assert_eq!(test_queue(8, OverflowPolicy::DropNewest), config("capacity: 8\noverflow: drop_newest"));
+    assert_eq!(test_queue(64, OverflowPolicy::Block(Duration::from_millis(5))), config("overflow: block\nblock_ms: 5"));
+    assert_eq!(default, config("capacity: 0\noverflow: unknown"));
+    assert_eq!(default, queue_config(&Yaml::BadValue, default));
+}
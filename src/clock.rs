@@ -0,0 +1,28 @@
+pub mod clock {
+
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    /**
+     * Monotonic clock shared by all threads
+     * Time in ms since the first call (process start), used to timestamp the samples and check their freshness.
+     */
+    static START: OnceLock<Instant> = OnceLock::new();
+
+    pub fn now_ms() -> u64 {
+
+        START.get_or_init(Instant::now).elapsed().as_millis() as u64
+    }
+}
+
+pub use clock::now_ms;
+
+#[test]
+fn clock_is_monotonic() {
+
+    let t1: u64 = now_ms();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    let t2: u64 = now_ms();
+
+    assert!(t2 >= t1 + 5);
+}
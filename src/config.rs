@@ -0,0 +1,38 @@
+/**
+ * YAML config helpers
+ * Numbers of the config sections, integer or real ("frequency_hz: 50" or "frequency_hz: 50.0").
+ */
+extern crate yaml_rust;
+
+use yaml_rust::Yaml;
+
+/// YAML number (integer or real).
+pub fn get_f32(value: &Yaml) -> Option<f32> {
+
+    match value {
+        Yaml::Real(_) => value.as_f64().map(|v| v as f32),
+        Yaml::Integer(i) => Some(*i as f32),
+        _ => None
+    }
+}
+
+/// YAML number (integer or real) > 0.
+pub fn get_positive_f32(value: &Yaml) -> Option<f32> {
+
+    get_f32(value).filter(|v| *v > 0.0)
+}
+
+#[test]
+fn config_numbers() {
+
+    let config: Yaml = yaml_rust::YamlLoader::load_from_str("{ real: 2.5, integer: 3, negative: -1, zero: 0, text: abc }").unwrap().remove(0);
+
+    assert_eq!(Some(2.5), get_f32(&config["real"]));
+    assert_eq!(Some(3.0), get_f32(&config["integer"]));
+    assert_eq!(None, get_f32(&config["text"]));
+    assert_eq!(None, get_f32(&config["missing"]));
+
+    assert_eq!(Some(3.0), get_positive_f32(&config["integer"]));
+    assert_eq!(None, get_positive_f32(&config["negative"]));
+    assert_eq!(None, get_positive_f32(&config["zero"]));
+}
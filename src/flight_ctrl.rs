@@ -1,9 +1,40 @@
+
 pub mod flight_ctrls {
 
     pub trait  FlightCtrlsProvider {
 
-        fn send(&self);
+        fn send(&self, cmd: &FlightCtrlsCommand);
+
+        /// Give the flight controls back to the sim/pilot (release overrides) on shutdown.
+        fn release(&self);
+    }
+
+    /**
+     * Flight controls command sent by the AP
+     * Ratios from -1.0 to 1.0 (throttle from 0.0 to 1.0), None when the axis is not driven by the AP.
+     */
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct FlightCtrlsCommand {
+
+        pub pitch: Option<f32>,
+        pub roll: Option<f32>,
+        pub yaw: Option<f32>,
+        pub throttle: Option<f32>,
+    }
+
+    impl FlightCtrlsCommand {
+
+        pub const fn new() -> FlightCtrlsCommand {
+
+            FlightCtrlsCommand {
+                pitch: None,
+                roll: None,
+                yaw: None,
+                throttle: None
+            }
+        }
     }
 }
 
 pub use flight_ctrls::FlightCtrlsProvider;
+pub use flight_ctrls::FlightCtrlsCommand;
@@ -4,23 +4,30 @@ mod altitude_indicator;
 mod attitude_indicator;
 mod common;
 mod constants;
+mod fault_panel;
+mod flight_plan_panel;
 mod heading_indicator;
+mod navigation_display;
 mod gui_utils;
 mod pfd;
 mod speed_indicator;
+mod tasks_panel;
 
 extern crate egui;
 
 
 pub mod gui {
 
-    use std::sync::{mpsc::{Sender, Receiver, TryRecvError}, Arc, Mutex, MutexGuard};
+    use std::sync::{Arc, Mutex, MutexGuard};
+    use std::time::Duration;
 
     use egui::Pos2;
 
-    use crate::bus::{BusMessage, AdcDataMessage, APCmdPayload, APStateMessage};
+    use crate::bus::{Bus, BusMessage, Publisher, QueueConfig, SequenceMonitor, Subscription, Topic, AdcDataMessage, AdcHealthMessage, AhrsDataMessage, TaskStatsMessage, APCmdPayload, APStateMessage, LinkState, FlightPlanMessage, NavStateMessage};
     use crate::gui::common::APBusMessageSender;
-    use super::{pfd::PrimaryFligthDisplay, ap_panel::AutopilotPanel};
+    use crate::lifecycle::Lifecycle;
+    use crate::sensors::fault_injection::FaultSwitches;
+    use super::{pfd::PrimaryFligthDisplay, navigation_display::NavigationDisplay, ap_panel::AutopilotPanel, fault_panel::FaultPanel, flight_plan_panel::FlightPlanPanel, tasks_panel::TasksPanel};
 
     fn setup_custom_fonts(ctx: &egui::Context) {
         // Start with the default fonts (we will be adding to them rather than replacing them).
@@ -56,6 +63,15 @@ pub mod gui {
     pub struct GuiState {
         pub adc_state: AdcDataMessage,
         pub ap_state: APStateMessage,
+        pub link_state: LinkState,
+        pub adc_health: Option<AdcHealthMessage>,
+        pub ahrs_state: AhrsDataMessage,
+        pub tasks_stats: Vec<TaskStatsMessage>,
+        pub flight_plan: Option<FlightPlanMessage>,
+        pub nav_state: Option<NavStateMessage>,
+        // ND range (NM) and heading/track up selected by the crew
+        pub nd_range_nm: f32,
+        pub nd_track_up: bool,
     }
 
 
@@ -63,31 +79,44 @@ pub mod gui {
 
         pub const fn new() -> Self {
             
-            Self{ adc_state: AdcDataMessage::new(), ap_state: APStateMessage::new() }
+            Self{ adc_state: AdcDataMessage::new(), ap_state: APStateMessage::new(), link_state: LinkState::Connecting, adc_health: None, ahrs_state: AhrsDataMessage::new(), tasks_stats: Vec::new(), flight_plan: None, nav_state: None, nd_range_nm: 40.0, nd_track_up: false }
         }
     }
 
     pub struct GuiApp {
         pub state: Arc<Mutex<GuiState>>,
-        pub gui_tx_ap: Sender<BusMessage>,
+        pub publisher: Publisher,
+        lifecycle: Lifecycle,
         ap_panel: AutopilotPanel,
         pfd: PrimaryFligthDisplay,
+        nd: NavigationDisplay,
+        fault_panel: FaultPanel,
+        flight_plan_panel: FlightPlanPanel,
+        tasks_panel: TasksPanel,
     }
 
     impl GuiApp {
 
-        pub fn new(cc: &eframe::CreationContext<'_>, state: Arc<Mutex<GuiState>>, gui_tx_ap: Sender<BusMessage>) -> Self {
+        pub fn new(cc: &eframe::CreationContext<'_>, state: Arc<Mutex<GuiState>>, publisher: Publisher, fault_switches: Arc<FaultSwitches>, lifecycle: Lifecycle) -> Self {
             
             setup_custom_fonts(&cc.egui_ctx);
             
             Self { 
                 state: state, 
-                gui_tx_ap: gui_tx_ap, 
+                publisher: publisher,
+                lifecycle: lifecycle,
                 ap_panel: AutopilotPanel{}, 
                 pfd: PrimaryFligthDisplay::new(
                     Pos2{x: 250.0, y: 15.0},
                     500.0,
-                    605.0)
+                    605.0),
+                nd: NavigationDisplay::new(
+                    Pos2{x: 760.0, y: 15.0},
+                    500.0,
+                    605.0),
+                fault_panel: FaultPanel::new(fault_switches),
+                flight_plan_panel: FlightPlanPanel::new(),
+                tasks_panel: TasksPanel{},
             }
         }
     }
@@ -97,13 +126,18 @@ pub mod gui {
         fn send_ap_cmd(&self, ap_cmd_payload: APCmdPayload) {
 
             let bus_message: BusMessage = BusMessage::APCmd(ap_cmd_payload);
-            let _ = self.gui_tx_ap.send(bus_message);
+            self.publisher.publish(bus_message);
         }
     }
 
     impl eframe::App for GuiApp {
 
-        fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+
+            // Shutdown requested by another source (signal, thread failure) : close the window.
+            if !self.lifecycle.is_running() {
+                frame.close();
+            }
 
             let mut state: MutexGuard<GuiState> = self.state.lock().unwrap();
 
@@ -113,40 +147,61 @@ pub mod gui {
 
                 self.ap_panel.view_update(&mut state, ctx, ui, self);
                 self.pfd.view_update(&mut state, ctx, ui);
+                self.nd.view_update(&mut state, ctx, ui);
+                self.fault_panel.view_update(ctx, ui);
+                self.flight_plan_panel.view_update(ctx, ui, &self.publisher);
+                self.tasks_panel.view_update(&mut state, ctx, ui);
             });
 
             ctx.request_repaint();
         }
     }
 
+    /// Topics shown by the GUI.
+    pub const GUI_TOPICS: [Topic; 8] = [Topic::AdcData, Topic::AdcHealth, Topic::LinkState, Topic::AhrsData, Topic::ApState, Topic::TaskStats, Topic::FlightPlan, Topic::NavState];
+
     pub struct Gui {
         pub state: Arc<Mutex<GuiState>>,
-        pub rx_gui: Receiver<BusMessage>,
+        pub subscription: Subscription,
+        pub seq_monitor: SequenceMonitor,
     }
 
     impl Gui {
 
-        pub fn handle_bus_message(&mut self) {
+        pub fn from(state: Arc<Mutex<GuiState>>, bus: &Bus, queue_config: QueueConfig) -> Self {
 
-            //let d: std::time::Duration = std::time::Duration::from_millis(50);
+            Self {
+                state,
+                subscription: bus.subscribe("GUI", &GUI_TOPICS, queue_config),
+                seq_monitor: SequenceMonitor::new(),
+            }
+        }
+
+        /// Wait for a message during `timeout` at most and handle it.
+        pub fn handle_bus_message(&mut self, timeout: Duration) {
 
-            match self.rx_gui.try_recv() {
-                Ok(bus_message) => {
-                    match bus_message {
-                        BusMessage::AdcData(adc_data) => self.handle_adc_data_message(adc_data),
+            match self.subscription.recv_timeout(timeout) {
+                Some(envelope) => {
+                    let lost: u64 = self.seq_monitor.check(&envelope);
+                    if lost > 0 {
+                        println!("[GUI] {} message(s) lost from {} ({} since start, {} dropped by the {} queue)",
+                            lost, envelope.source, self.seq_monitor.lost(), self.subscription.dropped(), self.subscription.name());
+                    }
+
+                    match envelope.message {
+                        BusMessage::AdcData(adc_data) => self.handle_adc_data_message(*adc_data),
                         BusMessage::APState(ap_state) => self.handle_ap_state_message(ap_state),
+                        BusMessage::LinkState(link_state) => self.handle_link_state_message(link_state),
+                        BusMessage::AdcHealth(adc_health) => self.handle_adc_health_message(adc_health),
+                        BusMessage::AhrsData(ahrs_data) => self.handle_ahrs_data_message(ahrs_data),
+                        BusMessage::TaskStats(task_stats) => self.handle_task_stats_message(task_stats),
+                        BusMessage::FlightPlan(flight_plan) => self.handle_flight_plan_message(flight_plan),
+                        BusMessage::NavState(nav_state) => self.handle_nav_state_message(nav_state),
                         _ => (),
                     };           
                 },
-                Err(e) => {
-                    match e {
-                        TryRecvError::Empty => (),
-                        TryRecvError::Disconnected => println!("[GUI] Message processing error : {:?}", e)
-                    }   
-                }
+                None => (),
             }
-
-            //std::thread::sleep(d);
         }
 
         fn handle_adc_data_message(&mut self, adc_data: AdcDataMessage) {
@@ -162,6 +217,49 @@ pub mod gui {
             let mut state: MutexGuard<GuiState> = self.state.lock().unwrap();
             state.ap_state = ap_cmd;
         }
+
+        fn handle_link_state_message(&mut self, link_state: LinkState) {
+            println!("[GUI][LINK] {:?}", link_state);
+
+            let mut state: MutexGuard<GuiState> = self.state.lock().unwrap();
+            state.link_state = link_state;
+        }
+
+        fn handle_adc_health_message(&mut self, adc_health: AdcHealthMessage) {
+            println!("[GUI][ADC HEALTH] {:?}", adc_health);
+
+            let mut state: MutexGuard<GuiState> = self.state.lock().unwrap();
+            state.adc_health = Some(adc_health);
+        }
+
+        fn handle_ahrs_data_message(&mut self, ahrs_data: AhrsDataMessage) {
+
+            let mut state: MutexGuard<GuiState> = self.state.lock().unwrap();
+            state.ahrs_state = ahrs_data;
+        }
+
+        fn handle_task_stats_message(&mut self, task_stats: TaskStatsMessage) {
+
+            let mut state: MutexGuard<GuiState> = self.state.lock().unwrap();
+
+            match state.tasks_stats.iter_mut().find(|stats| stats.task == task_stats.task) {
+                Some(stats) => *stats = task_stats,
+                None => state.tasks_stats.push(task_stats)
+            }
+        }
+
+        fn handle_flight_plan_message(&mut self, flight_plan: FlightPlanMessage) {
+
+            let mut state: MutexGuard<GuiState> = self.state.lock().unwrap();
+            state.flight_plan = Some(flight_plan);
+            state.nav_state = None;
+        }
+
+        fn handle_nav_state_message(&mut self, nav_state: NavStateMessage) {
+
+            let mut state: MutexGuard<GuiState> = self.state.lock().unwrap();
+            state.nav_state = Some(nav_state);
+        }
     }
 
 }
@@ -2,6 +2,7 @@ use std::sync::MutexGuard;
 
 use egui::epaint::{RectShape, PathShape};
 use egui::{Pos2, Ui, Rect, Painter, Rounding, Color32, Stroke, Shape, Align, TextureId};
+use crate::bus::AdcParameter;
 use crate::gui::gui::GuiState;
 use crate::gui::gui_utils;
 
@@ -67,9 +68,16 @@ impl AltitudeIndicator {
         // Call painter to draw objects
         ui.painter().add(Shape::Rect(box_rect));
 
+        if !state.adc_state.validity.is_valid(AdcParameter::AltMsl) {
+            gui_utils::draw_failure_flag(&cliped_painter, ctx, "ALT", Pos2{x: self.x_middle_pos, y: self.y_middle_pos});
+            return;
+        }
+
         let alt_msl: f32 = state.adc_state.alt_msl;
+        let alt_trend: f32 = state.adc_state.alt_trend;
 
         self.draw_alt_msl_scale(alt_msl, ctx, &cliped_painter);
+        self.draw_alt_trend(alt_trend, &cliped_painter);
         self.draw_alt_msl(alt_msl, ctx, &cliped_painter);
     }
 
@@ -119,6 +127,21 @@ impl AltitudeIndicator {
 
     }
 
+    fn draw_alt_trend(&self, alt_trend: f32, cliped_painter: &Painter) {
+
+        const TOTAL_ALT_VISIBLE: f32 = 750.0;
+        const MIN_ALT_TREND: f32 = 20.0;
+
+        if alt_trend.abs() < MIN_ALT_TREND {
+            return;
+        }
+
+        let trend_x_pos: f32 = self.box_min_x + 15.0;
+        let trend_y_pos: f32 = self.y_middle_pos - (alt_trend.clamp(-TOTAL_ALT_VISIBLE / 2.0, TOTAL_ALT_VISIBLE / 2.0) * self.height) / TOTAL_ALT_VISIBLE;
+
+        gui_utils::draw_trend_arrow(cliped_painter, Pos2{x: trend_x_pos, y: self.y_middle_pos}, trend_y_pos, Color32::GREEN);
+    }
+
     fn draw_alt_msl(&self, alt_msl: f32, ctx: &egui::Context, cliped_painter: &Painter) {
 
         // Alt Rect background
@@ -6,10 +6,12 @@
  * - Altitude (Feets)
  * - Bank angle (deg) (deg)
  * - Vertical speed (feets/min)
+ * - AP engage and LNAV mode, active waypoint, distance to go (NM), desired track and cross track error (NM)
+ * - VNAV mode, cruise altitude, distances to the top of climb/descent (NM), vertical deviation (ft) and VS command (fpm)
  */
 use std::sync::MutexGuard;
 use egui::Ui;
-use crate::bus::APCmdPayload;
+use crate::bus::{APCmdPayload, FlightPlanMessage, NavStateMessage, VnavStateMessage};
 use crate::gui::gui::GuiState;
 use crate::gui::constants::{ALT_100_STEP_VALUE, ALT_500_STEP_VALUE, ALT_MAX_VALUE, ALT_MIN_VALUE};
 use crate::gui::common::{decrement_value, increment_value};
@@ -51,6 +53,56 @@ impl AutopilotPanel {
             }
 
         });
+
+        ui.horizontal(|ui| {
+
+            // Modes changed by the AP state received back.
+            let mut engaged: bool = state.ap_state.engaged;
+            if ui.toggle_value(&mut engaged, "AP").clicked() {
+                ap_msg_sender.send_ap_cmd(APCmdPayload::APEngage(engaged));
+            }
+
+            let mut lnav_mode: bool = state.ap_state.lnav_mode;
+            if ui.toggle_value(&mut lnav_mode, "LNAV").clicked() {
+                ap_msg_sender.send_ap_cmd(APCmdPayload::EnableLnavMode(lnav_mode));
+            }
+
+            if let Some(nav_state) = state.nav_state.as_ref() {
+                ui.label(nav_state_label(nav_state, state.flight_plan.as_ref()));
+            }
+        });
+
+        ui.horizontal(|ui| {
+
+            let mut vnav_mode: bool = state.ap_state.vnav_mode;
+            if ui.toggle_value(&mut vnav_mode, "VNAV").clicked() {
+                ap_msg_sender.send_ap_cmd(APCmdPayload::EnableVnavMode(vnav_mode));
+            }
+
+            if let Some(vnav) = state.nav_state.and_then(|nav_state| nav_state.vnav) {
+                ui.label(vnav_state_label(&vnav));
+            }
+        });
     }
 }
 
+fn nav_state_label(nav_state: &NavStateMessage, flight_plan: Option<&FlightPlanMessage>) -> String {
+
+    let ident: &str = match (nav_state.active_waypoint, flight_plan) {
+        (Some(active), Some(flight_plan)) => flight_plan.waypoints.get(active).map(|w| w.ident.as_str()).unwrap_or("-"),
+        _ => "-"
+    };
+
+    let side: &str = if nav_state.cross_track_nm >= 0.0 { "R" } else { "L" };
+
+    format!("{} {:.1} NM DTK {:03.0} XTK {:.1}{}", ident, nav_state.distance_to_go_nm, nav_state.desired_track, nav_state.cross_track_nm.abs(), side)
+}
+
+fn vnav_state_label(vnav: &VnavStateMessage) -> String {
+
+    let distance = |name: &str, distance_nm: Option<f32>| distance_nm.map(|d| format!(" {} {:.1} NM", name, d)).unwrap_or_default();
+
+    let deviation: String = vnav.vertical_deviation.map(|d| format!(" DEV {:+.0}ft", d)).unwrap_or_default();
+
+    format!("CRZ {:.0}ft{}{}{} VS {:.0}", vnav.cruise_altitude, distance("TOC", vnav.top_of_climb_nm), distance("TOD", vnav.top_of_descent_nm), deviation, vnav.vs_cmd)
+}
@@ -7,6 +7,7 @@ use egui::{Painter, Ui, Pos2, Color32, Stroke, Shape, Rounding,
     epaint::RectShape, epaint::Rect};
 use eframe::{emath::align::Align, epaint::PathShape};
 
+use crate::bus::{AdcParameter, AhrsStatus};
 use crate::gui::gui::GuiState;
 use crate::gui::gui_utils;
 
@@ -59,8 +60,13 @@ impl AttitudeIndicator {
 
     pub fn view_update(&self, state: &mut MutexGuard<GuiState>, ctx: &egui::Context, ui: &mut Ui) {
 
-        let roll_angle: f32 = state.adc_state.roll_angle;
-        let pitch_angle: f32 = state.adc_state.pitch_angle;
+        // Computed attitude once the AHRS is aligned, sim attitude otherwise.
+        let ahrs_aligned: bool = state.ahrs_state.status == AhrsStatus::Aligned;
+        let (roll_angle, pitch_angle) = if ahrs_aligned {
+            (state.ahrs_state.roll, state.ahrs_state.pitch)
+        } else {
+            (state.adc_state.roll_angle, state.adc_state.pitch_angle)
+        };
 
         // Primary rect (external boundaries)
         let clip_rect: Rect = Rect{
@@ -82,6 +88,11 @@ impl AttitudeIndicator {
         // Call painter to draw objects
         ui.painter().add(Shape::Rect(box_rect));
 
+        if !ahrs_aligned && !state.adc_state.validity.all_valid(&[AdcParameter::Pitch, AdcParameter::Roll]) {
+            gui_utils::draw_failure_flag(&cliped_painter, ctx, "ATT", Pos2{x: self.x_middle_pos, y: self.y_middle_pos});
+            return;
+        }
+
         self.draw_aircraft_attitude(ui, ctx, roll_angle, pitch_angle, cliped_painter);
         self.draw_aircraft_wings_pos(ui);
 
@@ -0,0 +1,55 @@
+/**
+ * Fault injection Panel
+ * Display the injected sensors faults :
+ * - Manual faults started/stopped by a checkbox
+ * - ACTIVE when the fault alters the samples
+ */
+use std::sync::Arc;
+
+use egui::{Color32, RichText, Ui};
+
+use crate::sensors::fault_injection::{FaultSwitch, FaultSwitches};
+
+pub struct FaultPanel {
+    switches: Arc<FaultSwitches>,
+}
+
+impl FaultPanel {
+
+    pub fn new(switches: Arc<FaultSwitches>) -> FaultPanel {
+
+        FaultPanel { switches }
+    }
+
+    pub fn view_update(&self, _ctx: &egui::Context, ui: &mut Ui) {
+
+        let switches: Vec<Arc<FaultSwitch>> = self.switches.list();
+
+        if switches.is_empty() {
+            return;
+        }
+
+        ui.separator();
+        ui.label(RichText::new("Sensors faults").strong());
+
+        for switch in switches.iter() {
+
+            ui.horizontal(|ui| {
+
+                if switch.manual {
+                    let mut requested: bool = switch.is_requested();
+
+                    if ui.checkbox(&mut requested, switch.name.as_str()).changed() {
+                        switch.set_requested(requested);
+                    }
+                } else {
+                    ui.label(switch.name.as_str());
+                }
+
+                if switch.is_active() {
+                    ui.label(RichText::new("ACTIVE").color(Color32::from_rgb(255, 170, 0)));
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,126 @@
+/**
+ * Flight plan Panel
+ * File picker of the flight plan files (.fms, .gpx, .csv) :
+ * - Directory browsed from the working directory, sub directories opened by a click, ".." to the parent,
+ *   directory listed once when opened (not at every frame)
+ * - A click on a file loads the flight plan into the navigation (nav/plan topic)
+ * - Result of the last load : waypoints loaded or the file error (red)
+ */
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use egui::{Color32, RichText, Ui};
+
+use crate::avionics::navigation::flight_plan::{load_flight_plan, FLIGHT_PLAN_EXTENSIONS};
+use crate::bus::{BusMessage, FlightPlanMessage, Publisher};
+
+// Sub directories and flight plan files of a directory.
+struct Listing {
+    directory: PathBuf,
+    directories: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+}
+
+// Browsed directory, its listing and last load result, updated by the view.
+pub struct FlightPlanPanel {
+    directory: RefCell<PathBuf>,
+    listing: RefCell<Option<Listing>>,
+    status: RefCell<Option<Result<String, String>>>,
+}
+
+impl FlightPlanPanel {
+
+    pub fn new() -> FlightPlanPanel {
+
+        FlightPlanPanel {
+            directory: RefCell::new(std::env::current_dir().unwrap_or(PathBuf::from("."))),
+            listing: RefCell::new(None),
+            status: RefCell::new(None),
+        }
+    }
+
+    pub fn view_update(&self, _ctx: &egui::Context, ui: &mut Ui, publisher: &Publisher) {
+
+        egui::CollapsingHeader::new("Flight plan").show(ui, |ui| {
+
+            ui.label(self.directory.borrow().display().to_string());
+
+            let mut listing = self.listing.borrow_mut();
+            let listed: bool = matches!(&*listing, Some(listing) if listing.directory == *self.directory.borrow());
+            if !listed {
+                let directory: PathBuf = self.directory.borrow().clone();
+                let (directories, files) = list_directory(&directory);
+                *listing = Some(Listing { directory, directories, files });
+            }
+            let Listing { directories, files, .. } = listing.as_ref().unwrap();
+
+            let mut selected: Option<PathBuf> = None;
+
+            egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+
+                if ui.selectable_label(false, "..").clicked() {
+                    self.directory.borrow_mut().pop();
+                }
+                for directory in directories.iter() {
+                    if ui.selectable_label(false, format!("{}/", file_name(directory))).clicked() {
+                        selected = Some(directory.clone());
+                    }
+                }
+                for file in files.iter() {
+                    if ui.selectable_label(false, file_name(file)).clicked() {
+                        selected = Some(file.clone());
+                    }
+                }
+            });
+
+            match selected {
+                Some(path) if path.is_dir() => *self.directory.borrow_mut() = path,
+                Some(path) => self.load(&path, publisher),
+                None => (),
+            }
+
+            match &*self.status.borrow() {
+                Some(Ok(status)) => { ui.label(status.as_str()); },
+                Some(Err(error)) => { ui.label(RichText::new(error.as_str()).color(Color32::RED)); },
+                None => (),
+            }
+        });
+    }
+
+    fn load(&self, path: &Path, publisher: &Publisher) {
+
+        let flight_plan: Result<FlightPlanMessage, String> = load_flight_plan(&path.display().to_string())
+                    .map_err(|e| e.to_string());
+
+        *self.status.borrow_mut() = Some(flight_plan.map(|flight_plan| {
+            let status: String = format!("{} : {} waypoints loaded", file_name(path), flight_plan.waypoints.len());
+            publisher.publish(BusMessage::FlightPlan(flight_plan));
+            status
+        }));
+    }
+}
+
+/// Sub directories and flight plan files, sorted by name.
+fn list_directory(directory: &Path) -> (Vec<PathBuf>, Vec<PathBuf>) {
+
+    let mut paths: Vec<PathBuf> = match fs::read_dir(directory) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+
+    let is_flight_plan = |path: &PathBuf| path.extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| FLIGHT_PLAN_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+                .unwrap_or(false);
+
+    let (directories, files): (Vec<PathBuf>, Vec<PathBuf>) = paths.into_iter().partition(|path| path.is_dir());
+
+    (directories, files.into_iter().filter(is_flight_plan).collect())
+}
+
+fn file_name(path: &Path) -> String {
+
+    path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+}
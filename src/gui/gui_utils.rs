@@ -1,4 +1,4 @@
-use egui::{Painter, Align, Pos2, FontId, FontFamily, text::LayoutJob, Color32, epaint::TextShape, Stroke};
+use egui::{Painter, Align, Pos2, FontId, FontFamily, text::LayoutJob, Color32, epaint::TextShape, Stroke, Shape, Rect, Rounding};
 
 
 
@@ -56,4 +56,41 @@ pub fn draw_text_label(
     let text_shape: TextShape = TextShape { pos, galley, underline: font_stroke, override_text_color: None, angle: angle * -1.0 };
     
     cliped_painter.add(text_shape);
-}
\ No newline at end of file
+}
+
+/// Vertical trend vector from the tape reference line (origin) to the predicted value (tip_y), with an arrow head.
+pub fn draw_trend_arrow(cliped_painter: &Painter, origin: Pos2, tip_y: f32, color: Color32) {
+
+    let stroke: Stroke = Stroke { width: 2.0, color };
+    let tip: Pos2 = Pos2 { x: origin.x, y: tip_y };
+
+    cliped_painter.add(Shape::LineSegment { points: [origin, tip], stroke });
+
+    // Arrow head pointing up (increasing) or down (decreasing)
+    let head_direction: f32 = if tip_y < origin.y { 1.0 } else { -1.0 };
+    let head_points: [Pos2; 3] = [
+        Pos2 { x: origin.x - 5.0, y: tip_y + head_direction * 8.0 },
+        tip,
+        Pos2 { x: origin.x + 5.0, y: tip_y + head_direction * 8.0 },
+    ];
+
+    cliped_painter.add(Shape::line(head_points.to_vec(), stroke));
+}
+
+/// Failure flag (red boxed label) drawn instead of an invalid parameter.
+pub fn draw_failure_flag(cliped_painter: &Painter, ctx: &egui::Context, text_label: &str, center: Pos2) {
+
+    let font_size: f32 = 20.0;
+    let half_width: f32 = text_label.len() as f32 * font_size * 0.35 + 5.0;
+
+    let flag_rect: Rect = Rect {
+        min: Pos2 { x: center.x - half_width, y: center.y - font_size * 0.75 },
+        max: Pos2 { x: center.x + half_width, y: center.y + font_size * 0.75 }
+    };
+
+    cliped_painter.rect(flag_rect, Rounding::ZERO, Color32::BLACK, Stroke { width: 2.0, color: Color32::RED });
+
+    draw_text_label(cliped_painter, ctx, text_label.to_string(),
+                    font_size, Color32::RED, Stroke::NONE,
+                    Pos2 { x: center.x, y: center.y - font_size / 2.0 }, Align::Center, None);
+}
@@ -5,6 +5,7 @@ use egui::{Pos2, Ui, Rect, Painter, epaint::RectShape, Rounding, Color32, Stroke
 use egui::epaint::PathShape;
 
 use super::{gui_utils, gui::GuiState};
+use crate::bus::AdcParameter;
 
 
 pub struct HeadingIndicator {
@@ -87,6 +88,11 @@ impl HeadingIndicator {
         // Call painter to draw objects
         ui.painter().add(Shape::Rect(box_rect));
 
+        if !state.adc_state.validity.is_valid(AdcParameter::Heading) {
+            gui_utils::draw_failure_flag(&cliped_painter, ctx, "HDG", Pos2{x: self.x_middle_pos, y: self.y_middle_pos});
+            return;
+        }
+
         // heading disc
         let heading_disc_center_pos: Pos2 = Pos2{x: self.x_middle_pos, y: self.box_min_y + (self.width / 2.0) + 15.0};
         let heading_disc_shape: Shape = Shape::circle_filled(heading_disc_center_pos, self.width / 2.0, Color32::GRAY);
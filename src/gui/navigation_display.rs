@@ -0,0 +1,327 @@
+/**
+ * ND for Navigation Display
+ * Display graphically, heading up or track up :
+ * - Aircraft position (symbol at the rose center) and compass rose
+ * - Range rings (half and full range, selectable range in NM)
+ * - Flight plan legs and waypoints (active leg and waypoint magenta)
+ * - AP heading bug and ground track line
+ * - Wind (direction/speed and arrow), ground speed and TAS, active waypoint distance
+ * - Failure flags : heading invalid (HDG), no position (MAP)
+ */
+use std::sync::MutexGuard;
+
+use egui::{Align, Button, Color32, Painter, Pos2, Rect, Rounding, Shape, Stroke, TextureId, Ui, Vec2};
+use egui::epaint::{PathShape, RectShape};
+
+use crate::avionics::navigation::geo::{bearing_deg, distance_nm};
+use crate::bus::{AdcParameter, FlightPlanMessage, GeoPosition, WindData};
+use crate::gui::gui::GuiState;
+
+use super::gui_utils;
+
+/// Selectable ranges (NM), full range ring radius.
+pub const ND_RANGES_NM: [f32; 6] = [5.0, 10.0, 20.0, 40.0, 80.0, 160.0];
+
+const MAGENTA: Color32 = Color32::from_rgb(255, 0, 255);
+
+pub struct NavigationDisplay {
+
+    box_min_x: f32,
+    box_max_x: f32,
+    box_min_y: f32,
+    box_max_y: f32,
+    x_middle_pos: f32,
+
+    // Aircraft symbol, center of the compass rose and range rings
+    center: Pos2,
+    radius: f32,
+}
+
+impl NavigationDisplay {
+
+    const ANGLES_LABEL: [f32; 12] = [0.0, 30.0, 60.0, 90.0, 120.0, 150.0, 180.0, 210.0, 240.0, 270.0, 300.0, 330.0];
+
+    pub fn new(position: Pos2, width: f32, height: f32) -> NavigationDisplay {
+
+        let box_min_x: f32 = position.x;
+        let box_max_x: f32 = position.x + width;
+        let box_min_y: f32 = position.y;
+        let box_max_y: f32 = position.y + height;
+        let x_middle_pos: f32 = gui_utils::get_middle_pos(box_min_x, width);
+
+        NavigationDisplay {
+            box_min_x,
+            box_max_x,
+            box_min_y,
+            box_max_y,
+            x_middle_pos,
+            center: Pos2{x: x_middle_pos, y: box_min_y + height * 0.55},
+            radius: width * 0.4,
+        }
+    }
+
+    pub fn view_update(&self, state: &mut MutexGuard<GuiState>, ctx: &egui::Context, ui: &mut Ui) {
+
+        // Primary rect (external boundaries)
+        let clip_rect: Rect = Rect{
+            min: Pos2{x: self.box_min_x, y: self.box_min_y },
+            max: Pos2{x: self.box_max_x, y: self.box_max_y}
+        };
+
+        let cliped_painter: Painter = ui.painter().with_clip_rect(clip_rect);
+
+        let box_rect: RectShape = RectShape {
+            rect: clip_rect,
+            rounding: Rounding::ZERO,
+            fill: Color32::BLACK,
+            stroke: Stroke { width: 2.0, color: Color32::BLACK },
+            fill_texture_id: TextureId::Managed(0),
+            uv: Rect::ZERO
+        };
+
+        cliped_painter.add(Shape::Rect(box_rect));
+
+        self.draw_controls(state, ui);
+
+        if !state.adc_state.validity.is_valid(AdcParameter::Heading) {
+            gui_utils::draw_failure_flag(&cliped_painter, ctx, "HDG", self.center);
+            return;
+        }
+
+        // Track up only with a ground track.
+        let heading: f32 = state.adc_state.heading;
+        let track: Option<f32> = state.adc_state.track;
+        let (up, track_up): (f32, bool) = match track {
+            Some(track) if state.nd_track_up => (track, true),
+            _ => (heading, false),
+        };
+
+        self.draw_range_rings(ctx, &cliped_painter, state.nd_range_nm);
+        self.draw_compass_rose(ctx, &cliped_painter, up, if track_up { "TRK" } else { "HDG" });
+
+        // Heading up : track line, track up : aircraft symbol rotated by the drift.
+        if let Some(track) = track.filter(|_| !track_up) {
+            let track_line: [Pos2; 2] = [self.center, self.rose_pos(up, track, self.radius)];
+            cliped_painter.extend(Shape::dashed_line(&track_line, Stroke { width: 1.5, color: Color32::GREEN }, 8.0, 6.0));
+        }
+
+        match (state.adc_state.position, state.flight_plan.as_ref()) {
+            (Some(position), Some(flight_plan)) => self.draw_route(ctx, &cliped_painter, state, flight_plan, &position, up),
+            (None, _) => gui_utils::draw_failure_flag(&cliped_painter, ctx, "MAP", Pos2{x: self.center.x, y: self.center.y - self.radius / 2.0}),
+            _ => (),
+        }
+
+        self.draw_heading_bug(&cliped_painter, up, state.ap_state.heading);
+        self.draw_aircraft_symbol(&cliped_painter, up, heading);
+        self.draw_data(ctx, &cliped_painter, state, up);
+    }
+
+    /// Range and heading/track up buttons under the display.
+    fn draw_controls(&self, state: &mut MutexGuard<GuiState>, ui: &mut Ui) {
+
+        let button_size: Vec2 = Vec2{x: 60.0, y: 20.0};
+        let button_rect = |i: f32| Rect::from_min_size(Pos2{x: self.box_min_x + i * (button_size.x + 5.0), y: self.box_max_y + 5.0}, button_size);
+
+        let range_index: usize = ND_RANGES_NM.iter().position(|range| *range == state.nd_range_nm).unwrap_or(0);
+
+        if ui.put(button_rect(0.0), Button::new("RNG -")).clicked() {
+            state.nd_range_nm = ND_RANGES_NM[range_index.saturating_sub(1)];
+        }
+        if ui.put(button_rect(1.0), Button::new("RNG +")).clicked() {
+            state.nd_range_nm = ND_RANGES_NM[(range_index + 1).min(ND_RANGES_NM.len() - 1)];
+        }
+
+        let mode_label: &str = if state.nd_track_up { "TRK UP" } else { "HDG UP" };
+        if ui.put(button_rect(2.0), Button::new(mode_label)).clicked() {
+            state.nd_track_up = !state.nd_track_up;
+        }
+    }
+
+    /// Screen position of the bearing (deg) at the distance (px) from the center, `up` the bearing at the top.
+    fn rose_pos(&self, up: f32, bearing: f32, distance: f32) -> Pos2 {
+
+        let angle_in_radians: f32 = (up - bearing).to_radians();
+        let (x, y) = gui_utils::rotate_pos2(self.center, angle_in_radians, Pos2{x: self.center.x, y: self.center.y - distance});
+
+        Pos2{x, y}
+    }
+
+    fn draw_range_rings(&self, ctx: &egui::Context, cliped_painter: &Painter, range_nm: f32) {
+
+        let stroke: Stroke = Stroke { width: 1.0, color: Color32::GRAY };
+
+        cliped_painter.add(Shape::circle_stroke(self.center, self.radius / 2.0, stroke));
+
+        // Ranges written on the left of the rings.
+        for (radius, range) in [(self.radius / 2.0, range_nm / 2.0), (self.radius, range_nm)] {
+            gui_utils::draw_text_label(cliped_painter, ctx, format!("{}", range),
+                12.0, Color32::GRAY, Stroke::NONE,
+                Pos2{x: self.center.x - radius - 4.0, y: self.center.y - 14.0}, Align::RIGHT, None);
+        }
+    }
+
+    fn draw_compass_rose(&self, ctx: &egui::Context, cliped_painter: &Painter, up: f32, up_label: &str) {
+
+        let stroke: Stroke = Stroke { width: 2.0, color: Color32::WHITE };
+
+        cliped_painter.add(Shape::circle_stroke(self.center, self.radius, stroke));
+
+        for i in 0..72 {
+            let bearing: f32 = i as f32 * 5.0;
+            let tick_length: f32 = if i % 2 == 0 { 12.0 } else { 6.0 };
+
+            let tick: [Pos2; 2] = [self.rose_pos(up, bearing, self.radius), self.rose_pos(up, bearing, self.radius - tick_length)];
+            cliped_painter.add(Shape::line_segment(tick, stroke));
+        }
+
+        for bearing in NavigationDisplay::ANGLES_LABEL.iter() {
+            // Two digits labels (ex: 180° -> 18), rotated with the rose.
+            let label_pos: Pos2 = self.rose_pos(up, *bearing, self.radius - 16.0);
+
+            gui_utils::draw_text_label(cliped_painter, ctx, ((bearing / 10.0) as i32).to_string(),
+                16.0, Color32::WHITE, Stroke::NONE,
+                label_pos, Align::Center, Some((up - bearing).to_radians()));
+        }
+
+        // Lubber line reference triangle and up mode.
+        let top: f32 = self.center.y - self.radius;
+        let trg: Vec<Pos2> = vec![
+            Pos2{x: self.x_middle_pos - 10.0, y: top - 16.0},
+            Pos2{x: self.x_middle_pos + 10.0, y: top - 16.0},
+            Pos2{x: self.x_middle_pos, y: top - 2.0},
+        ];
+        cliped_painter.add(PathShape { points: trg, closed: true, fill: Color32::BLACK, stroke });
+
+        gui_utils::draw_text_label(cliped_painter, ctx, format!("{} {:03}", up_label, (up.round() as i32).rem_euclid(360)),
+            20.0, Color32::GREEN, Stroke::NONE,
+            Pos2{x: self.x_middle_pos, y: top - 42.0}, Align::Center, None);
+    }
+
+    fn draw_heading_bug(&self, cliped_painter: &Painter, up: f32, ap_heading: f32) {
+
+        let angle_in_radians: f32 = (up - ap_heading).to_radians();
+        let top: f32 = self.center.y - self.radius;
+
+        let bug: Vec<Pos2> = [
+            Pos2{x: self.center.x - 8.0, y: top - 8.0},
+            Pos2{x: self.center.x - 8.0, y: top},
+            Pos2{x: self.center.x - 3.0, y: top},
+            Pos2{x: self.center.x, y: top + 5.0},
+            Pos2{x: self.center.x + 3.0, y: top},
+            Pos2{x: self.center.x + 8.0, y: top},
+            Pos2{x: self.center.x + 8.0, y: top - 8.0},
+        ].iter()
+            .map(|pos| gui_utils::rotate_pos2(self.center, angle_in_radians, *pos))
+            .map(|(x, y)| Pos2{x, y})
+            .collect();
+
+        cliped_painter.add(PathShape { points: bug, closed: true, fill: Color32::TRANSPARENT, stroke: Stroke { width: 2.0, color: MAGENTA } });
+    }
+
+    /// Aircraft symbol pointing to the heading.
+    fn draw_aircraft_symbol(&self, cliped_painter: &Painter, up: f32, heading: f32) {
+
+        let angle_in_radians: f32 = (up - heading).to_radians();
+        let stroke: Stroke = Stroke { width: 3.0, color: Color32::YELLOW };
+
+        let lines: [[Pos2; 2]; 3] = [
+            // Fuselage, wings, tail
+            [Pos2{x: self.center.x, y: self.center.y - 15.0}, Pos2{x: self.center.x, y: self.center.y + 20.0}],
+            [Pos2{x: self.center.x - 18.0, y: self.center.y}, Pos2{x: self.center.x + 18.0, y: self.center.y}],
+            [Pos2{x: self.center.x - 7.0, y: self.center.y + 17.0}, Pos2{x: self.center.x + 7.0, y: self.center.y + 17.0}],
+        ];
+
+        for mut line in lines {
+            gui_utils::rotate_line(self.center, angle_in_radians, &mut line);
+            cliped_painter.add(Shape::line_segment(line, stroke));
+        }
+    }
+
+    /// Legs and waypoints at their bearing and distance from the aircraft position, active leg from the nav state.
+    fn draw_route(&self, ctx: &egui::Context, cliped_painter: &Painter, state: &MutexGuard<GuiState>, flight_plan: &FlightPlanMessage,
+                  position: &GeoPosition, up: f32) {
+
+        let active: Option<usize> = state.nav_state.and_then(|nav_state| nav_state.active_waypoint);
+        let scale: f32 = self.radius / state.nd_range_nm;
+
+        let points: Vec<Pos2> = flight_plan.waypoints.iter()
+                    .map(|waypoint| self.rose_pos(up, bearing_deg(position, &waypoint.position) as f32, distance_nm(position, &waypoint.position) as f32 * scale))
+                    .collect();
+
+        // Active leg from the aircraft on the first (direct to) leg.
+        if active == Some(0) {
+            cliped_painter.add(Shape::line_segment([self.center, points[0]], Stroke { width: 2.0, color: MAGENTA }));
+        }
+
+        for (i, leg) in points.windows(2).enumerate() {
+            let color: Color32 = if active == Some(i + 1) { MAGENTA } else { Color32::WHITE };
+            cliped_painter.add(Shape::line_segment([leg[0], leg[1]], Stroke { width: 2.0, color }));
+        }
+
+        for (i, (waypoint, point)) in flight_plan.waypoints.iter().zip(points.iter()).enumerate() {
+            let color: Color32 = if active == Some(i) { MAGENTA } else { Color32::WHITE };
+
+            let diamond: Vec<Pos2> = vec![
+                Pos2{x: point.x, y: point.y - 6.0},
+                Pos2{x: point.x + 6.0, y: point.y},
+                Pos2{x: point.x, y: point.y + 6.0},
+                Pos2{x: point.x - 6.0, y: point.y},
+            ];
+            cliped_painter.add(PathShape { points: diamond, closed: true, fill: Color32::BLACK, stroke: Stroke { width: 2.0, color } });
+
+            gui_utils::draw_text_label(cliped_painter, ctx, waypoint.ident.clone(),
+                14.0, color, Stroke::NONE,
+                Pos2{x: point.x + 9.0, y: point.y - 4.0}, Align::LEFT, None);
+        }
+    }
+
+    /// Ground speed, TAS and wind (top left), active waypoint (top right).
+    fn draw_data(&self, ctx: &egui::Context, cliped_painter: &Painter, state: &MutexGuard<GuiState>, up: f32) {
+
+        let ground_speed: String = state.adc_state.ground_speed.map(|gs| format!("{}", gs.round())).unwrap_or_else(|| "---".into());
+
+        gui_utils::draw_text_label(cliped_painter, ctx, format!("GS {} TAS {}", ground_speed, state.adc_state.tas.round()),
+            16.0, Color32::WHITE, Stroke::NONE,
+            Pos2{x: self.box_min_x + 10.0, y: self.box_min_y + 10.0}, Align::LEFT, None);
+
+        if let Some(wind) = state.adc_state.wind {
+            self.draw_wind(ctx, cliped_painter, &wind, up);
+        }
+
+        let active_waypoint = state.nav_state.and_then(|nav_state| nav_state.active_waypoint.map(|active| (active, nav_state.distance_to_go_nm)));
+
+        if let (Some((active, distance_to_go_nm)), Some(flight_plan)) = (active_waypoint, state.flight_plan.as_ref()) {
+            if let Some(waypoint) = flight_plan.waypoints.get(active) {
+                gui_utils::draw_text_label(cliped_painter, ctx, waypoint.ident.clone(),
+                    16.0, MAGENTA, Stroke::NONE,
+                    Pos2{x: self.box_max_x - 10.0, y: self.box_min_y + 10.0}, Align::RIGHT, None);
+                gui_utils::draw_text_label(cliped_painter, ctx, format!("{:.1} NM", distance_to_go_nm),
+                    16.0, Color32::WHITE, Stroke::NONE,
+                    Pos2{x: self.box_max_x - 10.0, y: self.box_min_y + 30.0}, Align::RIGHT, None);
+            }
+        }
+    }
+
+    /// Wind direction/speed and arrow (blowing direction on the display).
+    fn draw_wind(&self, ctx: &egui::Context, cliped_painter: &Painter, wind: &WindData, up: f32) {
+
+        gui_utils::draw_text_label(cliped_painter, ctx, format!("{:03}°/{}", (wind.direction.round() as i32).rem_euclid(360), wind.speed.round()),
+            16.0, Color32::WHITE, Stroke::NONE,
+            Pos2{x: self.box_min_x + 10.0, y: self.box_min_y + 30.0}, Align::LEFT, None);
+
+        let arrow_center: Pos2 = Pos2{x: self.box_min_x + 30.0, y: self.box_min_y + 80.0};
+        let angle_in_radians: f32 = (up - (wind.direction + 180.0)).to_radians();
+        let stroke: Stroke = Stroke { width: 2.0, color: Color32::WHITE };
+
+        let lines: [[Pos2; 2]; 3] = [
+            [Pos2{x: arrow_center.x, y: arrow_center.y + 18.0}, Pos2{x: arrow_center.x, y: arrow_center.y - 18.0}],
+            [Pos2{x: arrow_center.x - 6.0, y: arrow_center.y - 10.0}, Pos2{x: arrow_center.x, y: arrow_center.y - 18.0}],
+            [Pos2{x: arrow_center.x + 6.0, y: arrow_center.y - 10.0}, Pos2{x: arrow_center.x, y: arrow_center.y - 18.0}],
+        ];
+
+        for mut line in lines {
+            gui_utils::rotate_line(arrow_center, angle_in_radians, &mut line);
+            cliped_painter.add(Shape::line_segment(line, stroke));
+        }
+    }
+}
@@ -7,13 +7,17 @@
  * - Pitch angle (deg)
  * - Vertical speed (feets/min)
  * - Heading (deg)
+ * - AHRS status and drift from the sim attitude
+ * - Ground speed and wind (direction/speed, head/cross components)
+ * - Failure flags of the invalid parameters
  */
 
 
 use std::sync::MutexGuard;
 
-use egui::{Ui, Pos2, epaint::RectShape, Painter, Rect, Rounding, Color32, Stroke, Shape, TextureId};
+use egui::{Ui, Pos2, epaint::RectShape, Painter, Rect, Rounding, Color32, Stroke, Shape, TextureId, Align, RichText};
 
+use crate::bus::{AdcChannelHealth, AdcDataMessage, AdcParameter, AdcValidity, AhrsDataMessage, AhrsStatus, LinkState, WindData};
 use crate::gui::attitude_indicator::AttitudeIndicator;
 use crate::gui::gui::GuiState;
 
@@ -66,25 +70,34 @@ impl PrimaryFligthDisplay {
 
         ui.vertical(|ui| {
 
-            let alt_msl = state.adc_state.alt_msl.round();
-            let alt_agl = state.adc_state.alt_agl.round();
-            let ias = state.adc_state.ias.round();
-            let vs = state.adc_state.vs.round();
-            let heading = state.adc_state.heading.round();
-            let pitch = state.adc_state.pitch_angle.round();
-            let roll = state.adc_state.roll_angle.round();
-            let aoa = state.adc_state.aoa;
-            let g_load = state.adc_state.g_load;
-
-            ui.label(format!("ALT MSL: {} ft", alt_msl));
-            ui.label(format!("ALT AGL: {} ft", alt_agl));
-            ui.label(format!("IAS: {} kts", ias));
-            ui.label(format!("VS: {} ft/min", vs));
-            ui.label(format!("HDG: {}", heading));
-            ui.label(format!("Pitch: {}", pitch));
-            ui.label(format!("Roll: {}", roll));
-            ui.label(format!("AoA: {:.1}", aoa));
-            ui.label(format!("G load: {:.1}", g_load));
+            let adc_state: &AdcDataMessage = &state.adc_state;
+            let validity: AdcValidity = adc_state.validity;
+            let air_data_valid: bool = validity.all_valid(&[AdcParameter::Ias, AdcParameter::AltMsl]);
+
+            ui.label(param_label("ALT MSL", validity.is_valid(AdcParameter::AltMsl), format!("{} ft", adc_state.alt_msl.round())));
+            ui.label(param_label("ALT AGL", validity.is_valid(AdcParameter::AltAgl), format!("{} ft", adc_state.alt_agl.round())));
+            ui.label(param_label("IAS", validity.is_valid(AdcParameter::Ias), format!("{} kts", adc_state.ias.round())));
+            ui.label(param_label("VS", validity.is_valid(AdcParameter::Vs), format!("{} ft/min", adc_state.vs.round())));
+            ui.label(param_label("HDG", validity.is_valid(AdcParameter::Heading), format!("{}", adc_state.heading.round())));
+            ui.label(param_label("Pitch", validity.is_valid(AdcParameter::Pitch), format!("{}", adc_state.pitch_angle.round())));
+            ui.label(param_label("Roll", validity.is_valid(AdcParameter::Roll), format!("{}", adc_state.roll_angle.round())));
+            ui.label(param_label("AoA", validity.is_valid(AdcParameter::Aoa), format!("{:.1}", adc_state.aoa)));
+            ui.label(param_label("G load", validity.is_valid(AdcParameter::GLoad), format!("{:.1}", adc_state.g_load)));
+            ui.label(param_label("TAS", air_data_valid, format!("{} kts", adc_state.tas.round())));
+            ui.label(param_label("Mach", air_data_valid, format!("{:.3}", adc_state.mach)));
+            ui.label(param_label("DA", air_data_valid, format!("{} ft", adc_state.density_alt.round())));
+            ui.label(param_label("SAT", validity.is_valid(AdcParameter::AltMsl), format!("{:.1} °C", adc_state.sat)));
+            ui.label(optional_label("GS", adc_state.ground_speed.map(|gs| format!("{} kts", gs.round()))));
+            ui.label(optional_label("WIND", adc_state.wind.map(|wind| wind_label(&wind))));
+
+            ui.label(ahrs_label(&state.ahrs_state));
+
+            // Redundant ADC channels health
+            if let Some(adc_health) = state.adc_health.as_ref() {
+                for (i, channel_health) in adc_health.channels.iter().enumerate() {
+                    ui.label(channel_health_label(i + 1, *channel_health));
+                }
+            }
 
             // Primary rect (external boundaries)
             let clip_rect: Rect = Rect{
@@ -130,8 +143,78 @@ impl PrimaryFligthDisplay {
             attitude_indicator.view_update(state, ctx, ui);
             altitude_indicator.view_update(state, ctx, ui);
             heading_indicator.view_update(state, ctx, ui);
+
+            self.draw_link_state(state.link_state, ctx, &cliped_painter);
         });
     }
+
+    /// Sim link flag drawn over the attitude indicator when data is not received.
+    fn draw_link_state(&self, link_state: LinkState, ctx: &egui::Context, cliped_painter: &Painter) {
+
+        let (text_label, font_color) = match link_state {
+            LinkState::Receiving => return,
+            LinkState::Connecting => ("SIM LINK CONNECTING", Color32::YELLOW),
+            LinkState::Stale => ("SIM LINK STALE", Color32::YELLOW),
+            LinkState::Lost => ("SIM LINK LOST", Color32::RED),
+        };
+
+        let font_size: f32 = 25.0;
+        let pos: Pos2 = Pos2{x: self.x_middle_pos, y: self.box_min_y + 150.0};
+
+        gui_utils::draw_text_label(cliped_painter, ctx, text_label.to_string(), 
+                                    font_size, font_color, Stroke::NONE, 
+                                    pos, Align::Center, None);
+    }
+}
+
+/// Parameter label, failure flag instead of the value when the parameter is invalid.
+fn param_label(name: &str, valid: bool, value: String) -> RichText {
+
+    if valid {
+        RichText::new(format!("{}: {}", name, value))
+    } else {
+        RichText::new(format!("{}: FAIL", name)).color(Color32::RED)
+    }
+}
+
+/// Label of a parameter not always provided by the sensors ("---" when not available).
+fn optional_label(name: &str, value: Option<String>) -> RichText {
+
+    RichText::new(format!("{}: {}", name, value.unwrap_or_else(|| "---".into())))
+}
+
+/// Wind direction/speed, headwind (H) or tailwind (T) and crosswind from the left (L) or right (R).
+fn wind_label(wind: &WindData) -> String {
+
+    let head_tail: char = if wind.headwind >= 0.0 { 'H' } else { 'T' };
+    let left_right: char = if wind.crosswind >= 0.0 { 'R' } else { 'L' };
+
+    format!("{:03}°/{} kts {}{} {}{}",
+        (wind.direction.round() as i32).rem_euclid(360), wind.speed.round(),
+        head_tail, wind.headwind.abs().round(), left_right, wind.crosswind.abs().round())
+}
+
+/// AHRS status, drift from the sim attitude once aligned.
+fn ahrs_label(ahrs_state: &AhrsDataMessage) -> RichText {
+
+    let drift = |drift: Option<f32>| drift.map(|d| format!("{:+.1}", d)).unwrap_or_else(|| "---".into());
+
+    match ahrs_state.status {
+        AhrsStatus::NoData => RichText::new("AHRS: NO DATA").color(Color32::RED),
+        AhrsStatus::Aligning => RichText::new(format!("AHRS: ALIGN {}%", (ahrs_state.alignment * 100.0).round())).color(Color32::YELLOW),
+        AhrsStatus::Aligned => RichText::new(format!("AHRS drift P {} R {} H {}",
+                    drift(ahrs_state.pitch_drift), drift(ahrs_state.roll_drift), drift(ahrs_state.heading_drift))),
+    }
 }
 
+fn channel_health_label(channel: usize, channel_health: AdcChannelHealth) -> RichText {
+
+    let (text_label, color) = match channel_health {
+        AdcChannelHealth::Ok => ("OK", Color32::GREEN),
+        AdcChannelHealth::Miscompare => ("MISCOMPARE", Color32::YELLOW),
+        AdcChannelHealth::Isolated => ("ISOLATED", Color32::RED),
+        AdcChannelHealth::NoData => ("NO DATA", Color32::RED),
+    };
 
+    RichText::new(format!("ADC {}: {}", channel, text_label)).color(color)
+}
@@ -2,6 +2,7 @@ use std::sync::MutexGuard;
 
 use egui::epaint::{RectShape, PathShape};
 use egui::{Pos2, Ui, Rect, Painter, Rounding, Color32, Stroke, Shape, Align, TextureId};
+use crate::bus::AdcParameter;
 use crate::gui::gui::GuiState;
 use crate::gui::gui_utils;
 
@@ -75,9 +76,16 @@ impl SpeedIndicator {
         // Call painter to draw objects
         ui.painter().add(Shape::Rect(box_rect));
 
+        if !state.adc_state.validity.is_valid(AdcParameter::Ias) {
+            gui_utils::draw_failure_flag(&cliped_painter, ctx, "SPD", Pos2{x: self.x_middle_pos, y: self.y_middle_pos});
+            return;
+        }
+
         let ias: f32 = state.adc_state.ias;
+        let ias_trend: f32 = state.adc_state.ias_trend;
 
         self.draw_ias_scale(ias, ctx, &cliped_painter);
+        self.draw_ias_trend(ias_trend, &cliped_painter);
         self.draw_ias_speed(ias, ctx, &cliped_painter);
     }
 
@@ -126,6 +134,21 @@ impl SpeedIndicator {
 
     }
 
+    fn draw_ias_trend(&self, ias_trend: f32, cliped_painter: &Painter) {
+
+        const TOTAL_SPEED_VISIBLE: f32 = 120.0;
+        const MIN_IAS_TREND: f32 = 2.0;
+
+        if ias_trend.abs() < MIN_IAS_TREND {
+            return;
+        }
+
+        let trend_x_pos: f32 = self.x_middle_pos + 20.0;
+        let trend_y_pos: f32 = self.y_middle_pos - (ias_trend.clamp(-TOTAL_SPEED_VISIBLE / 2.0, TOTAL_SPEED_VISIBLE / 2.0) * self.height) / TOTAL_SPEED_VISIBLE;
+
+        gui_utils::draw_trend_arrow(cliped_painter, Pos2{x: trend_x_pos, y: self.y_middle_pos}, trend_y_pos, Color32::GREEN);
+    }
+
     fn draw_ias_speed(&self, ias: f32, ctx: &egui::Context, cliped_painter: &Painter) {
 
         // IAS Rect background
@@ -0,0 +1,47 @@
+/**
+ * Tasks Panel
+ * Display the real-time statistics of the scheduled tasks (last second) :
+ * - Frequency and cycles
+ * - Execution time (average/max, ms)
+ * - Start jitter (max, ms)
+ * - Overruns since start (red when any)
+ */
+use std::sync::MutexGuard;
+
+use egui::{Color32, RichText, Ui};
+
+use crate::bus::TaskStatsMessage;
+use crate::gui::gui::GuiState;
+
+pub struct TasksPanel {
+}
+
+impl TasksPanel {
+
+    pub fn view_update(&self, state: &mut MutexGuard<GuiState>, _ctx: &egui::Context, ui: &mut Ui) {
+
+        if state.tasks_stats.is_empty() {
+            return;
+        }
+
+        egui::CollapsingHeader::new("Tasks").show(ui, |ui| {
+
+            for stats in state.tasks_stats.iter() {
+                ui.label(task_stats_label(stats));
+            }
+        });
+    }
+}
+
+fn task_stats_label(stats: &TaskStatsMessage) -> RichText {
+
+    let text: String = format!("{} {} Hz ({}) exec {:.2}/{:.2} ms jitter {:.2} ms overruns {}",
+                stats.task, stats.frequency_hz, stats.cycles,
+                stats.exec_time_avg_ms, stats.exec_time_max_ms, stats.jitter_max_ms, stats.overruns);
+
+    if stats.overruns > 0 {
+        RichText::new(text).color(Color32::RED)
+    } else {
+        RichText::new(text)
+    }
+}
@@ -0,0 +1,131 @@
+pub mod lifecycle {
+
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// SIGINT number, the Ctrl-C signal on every platform.
+    pub const SIGINT: i32 = 2;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum ShutdownReason {
+        WindowClosed,
+        Signal(i32),
+        Failure
+    }
+
+    impl ShutdownReason {
+
+        /// Process exit status : 0 on normal close, 128 + signal number on signal (130 SIGINT, 143 SIGTERM), 1 on failure.
+        pub fn exit_code(&self) -> i32 {
+
+            match self {
+                ShutdownReason::WindowClosed => 0,
+                ShutdownReason::Signal(signal) => 128 + signal,
+                ShutdownReason::Failure => 1,
+            }
+        }
+    }
+
+    /**
+     * Lifecycle controller
+     * Shared by all threads, propagate the shutdown request (egui window closed, SIGINT/SIGTERM/SIGHUP, thread failure)
+     * so every loop can stop and the main thread can release the providers.
+     */
+    #[derive(Clone)]
+    pub struct Lifecycle {
+        running: Arc<AtomicBool>,
+        reason: Arc<Mutex<Option<ShutdownReason>>>,
+    }
+
+    impl Lifecycle {
+
+        pub fn new() -> Self {
+
+            Self {
+                running: Arc::new(AtomicBool::new(true)),
+                reason: Arc::new(Mutex::new(None)),
+            }
+        }
+
+        /// Request shutdown on SIGINT/SIGTERM/SIGHUP, the received signal is kept for the exit status.
+        #[cfg(unix)]
+        pub fn install_signal_handler(&self) {
+
+            use signal_hook::consts::{SIGHUP, SIGTERM};
+            use signal_hook::iterator::Signals;
+
+            let lifecycle: Lifecycle = self.clone();
+
+            match Signals::new([SIGINT, SIGTERM, SIGHUP]) {
+                Ok(mut signals) => {
+                    std::thread::spawn(move || {
+                        for signal in signals.forever() {
+                            println!("[LIFECYCLE] Signal received : {}", signal);
+                            lifecycle.request_shutdown(ShutdownReason::Signal(signal));
+                        }
+                    });
+                },
+                Err(e) => println!("[LIFECYCLE] Signal handler error : {:?}", e),
+            }
+        }
+
+        /// Request shutdown on Ctrl-C/termination, reported as SIGINT.
+        #[cfg(not(unix))]
+        pub fn install_signal_handler(&self) {
+
+            let lifecycle: Lifecycle = self.clone();
+
+            let result = ctrlc::set_handler(move || {
+                println!("[LIFECYCLE] Signal received");
+                lifecycle.request_shutdown(ShutdownReason::Signal(SIGINT));
+            });
+
+            if let Err(e) = result {
+                println!("[LIFECYCLE] Signal handler error : {:?}", e);
+            }
+        }
+
+        /// Request shutdown, the first reason is kept (ex: window closed after a signal).
+        pub fn request_shutdown(&self, reason: ShutdownReason) {
+
+            let mut current_reason = self.reason.lock().unwrap();
+
+            if current_reason.is_none() {
+                println!("[LIFECYCLE] Shutdown requested : {:?}", reason);
+                *current_reason = Some(reason);
+            }
+
+            self.running.store(false, Ordering::SeqCst);
+        }
+
+        pub fn is_running(&self) -> bool {
+
+            self.running.load(Ordering::SeqCst)
+        }
+
+        pub fn shutdown_reason(&self) -> Option<ShutdownReason> {
+
+            *self.reason.lock().unwrap()
+        }
+    }
+}
+
+pub use lifecycle::Lifecycle;
+pub use lifecycle::ShutdownReason;
+
+#[test]
+fn lifecycle_keeps_first_shutdown_reason() {
+
+    let lifecycle: Lifecycle = Lifecycle::new();
+    let thread_lifecycle: Lifecycle = lifecycle.clone();
+    assert!(thread_lifecycle.is_running());
+
+    lifecycle.request_shutdown(ShutdownReason::Signal(15));
+    lifecycle.request_shutdown(ShutdownReason::WindowClosed);
+
+    assert!(!thread_lifecycle.is_running());
+    assert_eq!(Some(ShutdownReason::Signal(15)), thread_lifecycle.shutdown_reason());
+    assert_eq!(143, ShutdownReason::Signal(15).exit_code());
+    assert_eq!(130, ShutdownReason::Signal(lifecycle::SIGINT).exit_code());
+    assert_eq!(0, ShutdownReason::WindowClosed.exit_code());
+}
@@ -2,16 +2,23 @@
 
 mod avionics;
 mod bus;
+mod clock;
+mod config;
 mod flight_ctrl;
 mod gui;
+mod lifecycle;
+mod mavlink;
 mod providers;
+mod scheduler;
 mod sensors;
+mod units;
 
 extern crate yaml_rust;
 
 use std::env;
-use std::sync::mpsc::{Receiver, Sender};
-use std::sync::{mpsc, Arc, Mutex};
+use std::error::Error;
+use std::process;
+use std::sync::{Arc, Mutex};
 use std::thread::{self};
 use std::fs;
 use std::time::Duration;
@@ -19,13 +26,29 @@ use egui::Vec2;
 use gui::gui::GuiState;
 use yaml_rust::{YamlLoader, Yaml};
 
-use crate::avionics::adc::adc::Adc;
+use crate::avionics::adc::adc::{Adc, AirDataComputer};
+use crate::avionics::adc::redundancy::RedundantAdc;
+use crate::avionics::ahrs::Ahrs;
+use crate::avionics::arinc429::transmitter::{Arinc429Config, Arinc429Transmitter};
 use crate::avionics::autopilot::autopilot::Autopilot;
-use crate::bus::BusMessage;
+use crate::avionics::navigation::flight_plan::load_flight_plan;
+use crate::bus::{Bus, BusMessage, OverflowPolicy, Publisher, QueueConfig, SourceId};
+use crate::bus::pubsub;
+use crate::bus::bridge::{BridgeConfig, NetworkBridge};
 use crate::sensors::SensorsProvider; 
+use crate::sensors::fault_injection::{self, FaultSwitches};
 use crate::flight_ctrl::flight_ctrls::FlightCtrlsProvider; 
-use crate::providers::providers::Provider;
+use crate::providers::providers::ProviderSet;
 use crate::gui::{Gui, GuiApp};
+use crate::lifecycle::{Lifecycle, ShutdownReason};
+use crate::mavlink::{MavlinkConfig, MavlinkEndpoint};
+use crate::scheduler::RateScheduler;
+
+const DEFAULT_ADC_FREQUENCY_HZ: f32 = 50.0;
+const DEFAULT_AP_FREQUENCY_HZ: f32 = 20.0;
+const GUI_BUS_RECEIVE_TIMEOUT: Duration = Duration::from_millis(100);
+const DEFAULT_AP_QUEUE: QueueConfig = QueueConfig { capacity: 64, overflow: OverflowPolicy::DropOldest };
+const DEFAULT_GUI_QUEUE: QueueConfig = QueueConfig { capacity: 256, overflow: OverflowPolicy::DropOldest };
 
 fn main() {
 
@@ -39,101 +62,238 @@ fn main() {
     let configs: Vec<Yaml> = YamlLoader::load_from_str(config_file_str).unwrap();
     let config: &Yaml = &configs[0];
 
-    let mut provider: Box::<dyn Provider> = providers::resolve_provider(&config);
-    provider.init();
+    let lifecycle: Lifecycle = Lifecycle::new();
+    lifecycle.install_signal_handler();
+
+    let mut providers: ProviderSet = match providers::resolve_providers(config) {
+        Ok(providers) => providers,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(ShutdownReason::Failure.exit_code());
+        }
+    };
+    providers.init();
 
-    let adc_frame_rate = config["adc"]["frame_rate"].as_i64().unwrap() as u64;
-    println!("adc_frame_rate : {}", adc_frame_rate);
+    // Tasks frequencies (Hz)
+    let adc_frequency: f32 = scheduler::task_frequency(&config["adc"], DEFAULT_ADC_FREQUENCY_HZ);
+    let ahrs_frequency: f32 = scheduler::task_frequency(&config["ahrs"], adc_frequency);
+    let ap_frequency: f32 = scheduler::task_frequency(&config["autopilot"], DEFAULT_AP_FREQUENCY_HZ);
 
-    let sensors: Arc::<dyn SensorsProvider + Send + Sync> = provider.get_sensors();
-    let flcs: Arc::<dyn FlightCtrlsProvider + Send + Sync> = provider.get_flcs();
+    // ----- Sensors faults (common to all the sensors readers : ADC channels, AHRS)
+    let fault_switches: Arc<FaultSwitches> = Arc::new(FaultSwitches::new());
+    let sensors: Arc::<dyn SensorsProvider + Send + Sync> = fault_injection::wrap(
+        exit_on_error(providers.get_sensors(), &providers),
+        &config["fault_injection"],
+        &fault_switches,
+        "");
+    let flcs: Arc::<dyn FlightCtrlsProvider + Send + Sync> = exit_on_error(providers.get_flcs(), &providers);
     
-    // MPSC channel to send data from (ADC, AP) to GUI.
-    let (tx_gui , rx_gui): (Sender<BusMessage>, Receiver<BusMessage>) = mpsc::channel();
-    let adc_tx_gui: Sender<BusMessage> = tx_gui.clone();
-    let ap_tx_gui: Sender<BusMessage> = tx_gui.clone();
+    // Publish/subscribe bus : producers publish once, each consumer subscribes to its topics.
+    let bus: Bus = Bus::new();
 
-    // MPSC channel to send data from (ADC, GUI) data to AP.
-    let (tx_ap , rx_ap): (Sender<BusMessage>, Receiver<BusMessage>) = mpsc::channel();
-    let adc_tx_ap: Sender<BusMessage> = tx_ap.clone();
-    let gui_tx_ap: Sender<BusMessage> = tx_ap.clone();
+    // ----- Building AHRS (own sensors reader)
+    let ahrs_sensors: Arc::<dyn SensorsProvider + Send + Sync> = fault_injection::wrap(
+        exit_on_error(providers.get_sensors(), &providers),
+        &config["fault_injection"],
+        &fault_switches,
+        "");
+    let mut ahrs: Ahrs = Ahrs::from(ahrs_sensors, bus.publisher(SourceId::Ahrs), &config["ahrs"]);
 
+    // ----- Building ADC (single or redundant channels), each channel reads its own sensors
+    let channels_sensors: Vec<Arc::<dyn SensorsProvider + Send + Sync>> = exit_on_error(providers.get_channels_sensors(), &providers)
+                .into_iter()
+                .map(|channel_sensors| fault_injection::wrap(channel_sensors, &config["fault_injection"], &fault_switches, ""))
+                .collect();
 
-    // ----- Building ADC
-    let adc: Adc = Adc{
-        sensors: sensors, 
-        adc_tx_gui: adc_tx_gui, 
-        adc_tx_ap: adc_tx_ap};
+    let mut adc: Box<dyn AirDataComputer + Send> = if channels_sensors.is_empty() {
+        Box::new(Adc::from(sensors, bus.publisher(SourceId::Adc), &config["adc"]))
+    } else {
+        Box::new(exit_on_error(RedundantAdc::from(channels_sensors, &bus, &config["adc"], &fault_switches), &providers))
+    };
 
     // ----- Builing AP
     let mut autopilot: Autopilot = Autopilot::from(
-        flcs, 
-        rx_ap, 
-        ap_tx_gui);
+        flcs.clone(), 
+        &bus,
+        pubsub::queue_config(&config["bus"]["ap"], DEFAULT_AP_QUEUE),
+        &config["autopilot"]);
     
     // ----- Building GUI
     let gui_state_mutex: Mutex<GuiState> = Mutex::new(GuiState::new());
     let gui_state: Arc<Mutex<GuiState>> = Arc::new(gui_state_mutex);
     let gui_state2: Arc<Mutex<GuiState>>  = gui_state.clone();
     
- //   let gui_app: GuiApp = GuiApp::from(gui_state, gui_tx_ap);
+    let mut gui: Gui = Gui::from(gui_state2, &bus, pubsub::queue_config(&config["bus"]["gui"], DEFAULT_GUI_QUEUE));
+    let gui_publisher: Publisher = bus.publisher(SourceId::Gui);
 
-    let mut gui: Gui = Gui{
-        state: gui_state2,
-        rx_gui: rx_gui,
-    };
+    // ----- Flight plan loaded at startup (optional "flight_plan" file), once the AP and GUI subscribed
+    if let Some(flight_plan_path) = config["flight_plan"].as_str() {
+        match load_flight_plan(flight_plan_path) {
+            Ok(flight_plan) => {
+                bus.publisher(SourceId::FlightPlanFile).publish(BusMessage::FlightPlan(flight_plan));
+            },
+            Err(e) => println!("[NAV] {}", e),
+        }
+    }
+
+    // Tasks statistics
+    let adc_stats_publisher: Publisher = bus.publisher(SourceId::Scheduler("ADC"));
+    let ahrs_stats_publisher: Publisher = bus.publisher(SourceId::Scheduler("AHRS"));
+    let ap_stats_publisher: Publisher = bus.publisher(SourceId::Scheduler("AP"));
 
     let mut handles = vec![];
 
     // ----- Init Thread ADC -----
-    let adc_handle = thread::spawn(move || -> ! {
+    let adc_lifecycle: Lifecycle = lifecycle.clone();
+    let adc_handle = thread::spawn(move || {
 
-        let d: Duration = Duration::from_millis(adc_frame_rate);
-        
-        loop {
-            // Read sensors and convert to ADC format
-            adc.read_sensors();
+        // Read sensors and convert to ADC format
+        RateScheduler::new("ADC", adc_frequency).run(&adc_lifecycle, &adc_stats_publisher, |_| adc.read_sensors());
 
-            thread::sleep(d);
-        }
+        println!("[ADC] Stopped");
     });
     handles.push(adc_handle);
 
+    // ----- Init Thread AHRS -----
+    let ahrs_lifecycle: Lifecycle = lifecycle.clone();
+    let ahrs_handle = thread::spawn(move || {
+
+        // Integrate the inertial sensors
+        RateScheduler::new("AHRS", ahrs_frequency).run(&ahrs_lifecycle, &ahrs_stats_publisher, |_| ahrs.read_sensors());
+
+        println!("[AHRS] Stopped");
+    });
+    handles.push(ahrs_handle);
+
     // ----- Init Thread AP -----
-    let ap_handle = thread::spawn(move || -> ! {
-        
-        loop {
-            // Read ADC Data or Handle GUI AP commands
-            autopilot.handle_bus_message();
-        }
+    let ap_lifecycle: Lifecycle = lifecycle.clone();
+    let ap_handle = thread::spawn(move || {
+
+        // Read ADC Data, handle GUI AP commands and run the control laws
+        RateScheduler::new("AP", ap_frequency).run_event_driven(&ap_lifecycle, &ap_stats_publisher, &mut autopilot);
+
+        println!("[AP] Stopped");
     });
     handles.push(ap_handle);
 
     // ----- Init Thread GUI -----
-    let gui_handle = thread::spawn(move || -> ! {
+    let gui_lifecycle: Lifecycle = lifecycle.clone();
+    let gui_handle = thread::spawn(move || {
         
-        loop {
-            // Read ADC Data or AP State
-            gui.handle_bus_message();
+        while gui_lifecycle.is_running() {
+            // Read ADC Data or AP State (blocking, lifecycle checked on timeout)
+            gui.handle_bus_message(GUI_BUS_RECEIVE_TIMEOUT);
         }
+
+        println!("[GUI] Stopped");
     });
     handles.push(gui_handle);
 
+    // ----- Init Thread Bridge (optional "bridge" section) -----
+    if !config["bridge"].is_badvalue() {
+
+        match NetworkBridge::bind(&BridgeConfig::from(&config["bridge"]), &bus) {
+            Ok(mut bridge) => {
+                let bridge_lifecycle: Lifecycle = lifecycle.clone();
+                let bridge_handle = thread::spawn(move || {
+
+                    // Stream the bus messages and publish the received AP commands
+                    bridge.run(&bridge_lifecycle);
+
+                    println!("[BRIDGE] Stopped");
+                });
+                handles.push(bridge_handle);
+            },
+            Err(e) => println!("[BRIDGE] Bind error : {:?}", e),
+        }
+    }
+
+    // ----- Init Thread ARINC 429 (optional "arinc429" section) -----
+    if !config["arinc429"].is_badvalue() {
+
+        match Arinc429Transmitter::bind(Arinc429Config::from(&config["arinc429"]), &bus) {
+            Ok(mut transmitter) => {
+                let arinc429_lifecycle: Lifecycle = lifecycle.clone();
+                let arinc429_handle = thread::spawn(move || {
+
+                    // Send the ADC and AP words to the test bench
+                    transmitter.run(&arinc429_lifecycle);
+
+                    println!("[A429] Stopped");
+                });
+                handles.push(arinc429_handle);
+            },
+            Err(e) => println!("[A429] Bind error : {:?}", e),
+        }
+    }
+
+    // ----- Init Thread MAVLink (optional "mavlink" section) -----
+    if !config["mavlink"].is_badvalue() {
+
+        match MavlinkEndpoint::bind(MavlinkConfig::from(&config["mavlink"]), &bus) {
+            Ok(mut endpoint) => {
+                let mavlink_lifecycle: Lifecycle = lifecycle.clone();
+                let mavlink_handle = thread::spawn(move || {
+
+                    // Send the telemetry to the ground stations and publish their AP commands
+                    endpoint.run(&mavlink_lifecycle);
+
+                    println!("[MAVLINK] Stopped");
+                });
+                handles.push(mavlink_handle);
+            },
+            Err(e) => println!("[MAVLINK] Bind error : {:?}", e),
+        }
+    }
+
     println!("Autopilot ready");
 
     // Init Gui APP
     let options = &mut eframe::NativeOptions::default();
-    options.initial_window_size = Some(Vec2{x: 800.0, y: 700.0});
+    options.initial_window_size = Some(Vec2{x: 1280.0, y: 700.0});
 
-    let _ = eframe::run_native(
+    let app_lifecycle: Lifecycle = lifecycle.clone();
+    let run_result = eframe::run_native(
         "Autopilot",
         options.to_owned(),
-        Box::new(|cc| Box::new(GuiApp::new(cc, gui_state, gui_tx_ap))),
+        Box::new(|cc| Box::new(GuiApp::new(cc, gui_state, gui_publisher, fault_switches, app_lifecycle))),
     );
 
+    // ----- Shutdown -----
+    match run_result {
+        Ok(_) => lifecycle.request_shutdown(ShutdownReason::WindowClosed),
+        Err(e) => {
+            println!("Gui App error : {:?}", e);
+            lifecycle.request_shutdown(ShutdownReason::Failure);
+        }
+    }
+
+    let mut exit_code: i32 = lifecycle.shutdown_reason().unwrap_or(ShutdownReason::Failure).exit_code();
+
     // join the handles in the vector
-    //for i in handles {
-    //    i.join().unwrap();
-    //}
+    for i in handles {
+        if i.join().is_err() {
+            exit_code = ShutdownReason::Failure.exit_code();
+        }
+    }
+
+    flcs.release();
+    providers.shutdown();
 
+    println!("Autopilot stopped ({})", exit_code);
+
+    process::exit(exit_code);
+}
+
+/// Config error (providers, redundant ADC) : nothing started yet, release the providers and exit on failure.
+fn exit_on_error<T, E: Error>(result: Result<T, E>, providers: &ProviderSet) -> T {
+
+    match result {
+        Ok(value) => value,
+        Err(e) => {
+            println!("{}", e);
+            providers.shutdown();
+            process::exit(ShutdownReason::Failure.exit_code());
+        }
+    }
 }
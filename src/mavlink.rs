@@ -0,0 +1,574 @@
+pub mod frame;
+pub mod messages;
+
+/**
+ * MAVLink endpoint
+ * Ground control stations on the same machine watch and command the autopilot over UDP (MAVLink v2, common dialect).
+ * Telemetry sent to the GCS peer and to every station which sent a message :
+ * - HEARTBEAT, SYS_STATUS at 1 Hz : AP engaged (armed), AP modes in the custom mode, ADC sensors health
+ * - ATTITUDE, VFR_HUD, GLOBAL_POSITION_INT at "telemetry_hz" : ADC data (lat/lon 0 without GNSS position)
+ *
+ * Commands published on the bus (APCmdPayload) :
+ * - COMMAND_LONG : COMPONENT_ARM_DISARM (AP engage), DO_SET_MODE, DO_CHANGE_ALTITUDE, acknowledged (others unsupported)
+ * - SET_MODE : armed flag (AP engage), custom mode (LNAV, VNAV)
+ * - SET_POSITION_TARGET_GLOBAL_INT : altitude (MSL frames)
+ *
+ * Custom mode bits : 1 alt hold, 2 VS, 4 heading hold, 8 auto throttle, 16 LNAV, 32 VNAV (only LNAV and VNAV can be set).
+ * Config ("mavlink" section) :
+ * - address (127.0.0.1:14551) : endpoint socket
+ * - gcs (127.0.0.1:14550) : ground station peer
+ * - system_id (1), component_id (1)
+ * - telemetry_hz (10) : attitude, HUD and position rate
+ */
+extern crate yaml_rust;
+
+use std::io::{self, ErrorKind};
+use std::net::{SocketAddr, UdpSocket};
+use std::time::Duration;
+use yaml_rust::Yaml;
+
+use crate::bus::{APCmdPayload, AdcDataMessage, AdcParameter, APStateMessage, Bus, BusMessage, OverflowPolicy, Publisher, QueueConfig, SourceId, Subscription, Topic};
+use crate::clock;
+use crate::config::get_positive_f32;
+use crate::lifecycle::Lifecycle;
+use crate::mavlink::frame::{decode_frames, MavFrame, MavlinkError};
+use crate::mavlink::messages::*;
+use crate::units::{FPM_TO_MS, FT_TO_M, KT_TO_MS};
+
+const DEFAULT_MAVLINK_ADDRESS: &str = "127.0.0.1:14551";
+const DEFAULT_GCS_ADDRESS: &str = "127.0.0.1:14550";
+const DEFAULT_TELEMETRY_HZ: f32 = 10.0;
+const MAVLINK_QUEUE: QueueConfig = QueueConfig { capacity: 64, overflow: OverflowPolicy::DropOldest };
+
+/// Bus wait of an endpoint cycle, received messages and telemetry handled in between.
+const MAVLINK_POLL_TIMEOUT: Duration = Duration::from_millis(20);
+
+const HEARTBEAT_PERIOD_MS: u64 = 1000;
+const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+// Custom mode bits
+const CUSTOM_MODE_ALT_HOLD: u32 = 1;
+const CUSTOM_MODE_VS: u32 = 2;
+const CUSTOM_MODE_HEADING: u32 = 4;
+const CUSTOM_MODE_AUTO_THROTTLE: u32 = 8;
+const CUSTOM_MODE_LNAV: u32 = 16;
+const CUSTOM_MODE_VNAV: u32 = 32;
+
+const MAV_CMD_DO_SET_MODE: u16 = 176;
+const MAV_RESULT_DENIED: u8 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MavlinkConfig {
+    pub address: String,
+    pub gcs: String,
+    pub system_id: u8,
+    pub component_id: u8,
+    pub telemetry_hz: f32,
+}
+
+impl From<&Yaml> for MavlinkConfig {
+
+    /// "mavlink" section.
+    fn from(config: &Yaml) -> Self {
+
+        let id = |key: &str| config[key].as_i64().filter(|id| (1..=255).contains(id)).unwrap_or(1) as u8;
+
+        let telemetry_hz: f32 = get_positive_f32(&config["telemetry_hz"]).unwrap_or(DEFAULT_TELEMETRY_HZ);
+
+        MavlinkConfig {
+            address: config["address"].as_str().unwrap_or(DEFAULT_MAVLINK_ADDRESS).to_string(),
+            gcs: config["gcs"].as_str().unwrap_or(DEFAULT_GCS_ADDRESS).to_string(),
+            system_id: id("system_id"),
+            component_id: id("component_id"),
+            telemetry_hz,
+        }
+    }
+}
+
+fn custom_mode(ap_state: &APStateMessage) -> u32 {
+
+    [
+        (ap_state.alt_hold_mode, CUSTOM_MODE_ALT_HOLD),
+        (ap_state.vs_mode, CUSTOM_MODE_VS),
+        (ap_state.heading_mode, CUSTOM_MODE_HEADING),
+        (ap_state.auto_throttle_mode, CUSTOM_MODE_AUTO_THROTTLE),
+        (ap_state.lnav_mode, CUSTOM_MODE_LNAV),
+        (ap_state.vnav_mode, CUSTOM_MODE_VNAV),
+    ].iter()
+        .filter(|(enabled, _)| *enabled)
+        .fold(0, |mode, (_, bit)| mode | bit)
+}
+
+pub fn heartbeat(ap_state: &APStateMessage) -> Heartbeat {
+
+    let armed: u8 = if ap_state.engaged { MAV_MODE_FLAG_SAFETY_ARMED } else { 0 };
+
+    Heartbeat {
+        custom_mode: custom_mode(ap_state),
+        mav_type: MAV_TYPE_FIXED_WING,
+        autopilot: MAV_AUTOPILOT_GENERIC,
+        base_mode: MAV_MODE_FLAG_CUSTOM_MODE_ENABLED | armed,
+        system_status: if ap_state.engaged { MAV_STATE_ACTIVE } else { MAV_STATE_STANDBY },
+    }
+}
+
+/// ADC sensors : gyro (attitude), static pressure (altitude, VS), differential pressure (IAS).
+pub fn sys_status(adc_data: Option<&AdcDataMessage>) -> SysStatus {
+
+    let sensors: [(u32, &[AdcParameter]); 3] = [
+        (MAV_SYS_STATUS_SENSOR_3D_GYRO, &[AdcParameter::Pitch, AdcParameter::Roll]),
+        (MAV_SYS_STATUS_SENSOR_ABSOLUTE_PRESSURE, &[AdcParameter::AltMsl, AdcParameter::Vs]),
+        (MAV_SYS_STATUS_SENSOR_DIFFERENTIAL_PRESSURE, &[AdcParameter::Ias]),
+    ];
+
+    let present: u32 = sensors.iter().fold(0, |present, (bit, _)| present | bit);
+    let health: u32 = match adc_data {
+        Some(adc_data) => sensors.iter()
+                    .filter(|(_, params)| adc_data.validity.all_valid(params))
+                    .fold(0, |health, (bit, _)| health | bit),
+        None => 0
+    };
+
+    SysStatus { sensors_present: present, sensors_enabled: present, sensors_health: health }
+}
+
+/// Heading (deg) to yaw (rad, -pi to pi).
+fn yaw(heading: f32) -> f32 {
+
+    let heading: f32 = heading.rem_euclid(360.0);
+    let heading: f32 = if heading > 180.0 { heading - 360.0 } else { heading };
+
+    heading.to_radians()
+}
+
+pub fn attitude(adc_data: &AdcDataMessage, time_boot_ms: u32) -> Attitude {
+
+    Attitude {
+        time_boot_ms,
+        roll: adc_data.roll_angle.to_radians(),
+        pitch: adc_data.pitch_angle.to_radians(),
+        yaw: yaw(adc_data.heading),
+        rollspeed: 0.0,
+        pitchspeed: 0.0,
+        yawspeed: 0.0,
+    }
+}
+
+pub fn vfr_hud(adc_data: &AdcDataMessage) -> VfrHud {
+
+    VfrHud {
+        airspeed: adc_data.ias * KT_TO_MS,
+        groundspeed: adc_data.ground_speed.unwrap_or(adc_data.tas) * KT_TO_MS,
+        alt: adc_data.alt_msl * FT_TO_M,
+        climb: adc_data.vs * FPM_TO_MS,
+        heading: adc_data.heading.rem_euclid(360.0).round() as i16 % 360,
+        throttle: 0,
+    }
+}
+
+pub fn global_position_int(adc_data: &AdcDataMessage, time_boot_ms: u32) -> GlobalPositionInt {
+
+    // North and east speeds from the ground speed and track, when provided.
+    let (vx, vy) = match (adc_data.ground_speed, adc_data.track) {
+        (Some(ground_speed), Some(track)) => {
+            let speed: f32 = ground_speed * KT_TO_MS * 100.0;
+            ((speed * track.to_radians().cos()) as i16, (speed * track.to_radians().sin()) as i16)
+        },
+        _ => (0, 0)
+    };
+
+    let (lat, lon) = match adc_data.position {
+        Some(position) => ((position.lat * 1e7) as i32, (position.lon * 1e7) as i32),
+        None => (0, 0)
+    };
+
+    GlobalPositionInt {
+        time_boot_ms,
+        lat,
+        lon,
+        alt: (adc_data.alt_msl * FT_TO_M * 1000.0) as i32,
+        relative_alt: (adc_data.alt_agl * FT_TO_M * 1000.0) as i32,
+        vx,
+        vy,
+        vz: (-adc_data.vs * FPM_TO_MS * 100.0) as i16,
+        hdg: ((adc_data.heading.rem_euclid(360.0) * 100.0) as u16).min(35_999),
+    }
+}
+
+/**
+ * AP engage and modes of a MAVLink mode (SET_MODE, DO_SET_MODE) and its result, `engaged` the current AP state.
+ * AP engaged or disengaged only when the armed flag changes. Only LNAV and VNAV can be selected by the AP commands :
+ * a mode with alt hold, VS, heading hold or auto throttle is unsupported, nothing changed.
+ */
+fn mode_commands(base_mode: u8, custom_mode: u32, engaged: bool) -> (Vec<APCmdPayload>, u8) {
+
+    let custom_mode_enabled: bool = base_mode & MAV_MODE_FLAG_CUSTOM_MODE_ENABLED != 0;
+
+    if custom_mode_enabled && custom_mode & (CUSTOM_MODE_ALT_HOLD | CUSTOM_MODE_VS | CUSTOM_MODE_HEADING | CUSTOM_MODE_AUTO_THROTTLE) != 0 {
+        return (Vec::new(), MAV_RESULT_UNSUPPORTED);
+    }
+
+    let mut ap_cmds: Vec<APCmdPayload> = Vec::new();
+
+    let armed: bool = base_mode & MAV_MODE_FLAG_SAFETY_ARMED != 0;
+    if armed != engaged {
+        ap_cmds.push(APCmdPayload::APEngage(armed));
+    }
+
+    if custom_mode_enabled {
+        ap_cmds.push(APCmdPayload::EnableLnavMode(custom_mode & CUSTOM_MODE_LNAV != 0));
+        ap_cmds.push(APCmdPayload::EnableVnavMode(custom_mode & CUSTOM_MODE_VNAV != 0));
+    }
+
+    (ap_cmds, MAV_RESULT_ACCEPTED)
+}
+
+/// AP commands of a COMMAND_LONG and its result.
+fn command_long(command: &CommandLong, engaged: bool) -> (Vec<APCmdPayload>, u8) {
+
+    let params: [f32; 7] = command.params;
+
+    match command.command {
+        MAV_CMD_COMPONENT_ARM_DISARM => (vec![APCmdPayload::APEngage(params[0] > 0.5)], MAV_RESULT_ACCEPTED),
+        MAV_CMD_DO_SET_MODE => mode_commands(params[0] as u8, params[1] as u32, engaged),
+        // Altitude (m) in the global frame (MSL) only.
+        MAV_CMD_DO_CHANGE_ALTITUDE if params[1] as u8 == MAV_FRAME_GLOBAL => {
+            (vec![APCmdPayload::SetAlt(params[0] / FT_TO_M)], MAV_RESULT_ACCEPTED)
+        },
+        MAV_CMD_DO_CHANGE_ALTITUDE => (Vec::new(), MAV_RESULT_DENIED),
+        // No speed target in the AP.
+        MAV_CMD_DO_CHANGE_SPEED => (Vec::new(), MAV_RESULT_UNSUPPORTED),
+        _ => (Vec::new(), MAV_RESULT_UNSUPPORTED)
+    }
+}
+
+/**
+ * AP commands of a received message for `system_id` (or broadcast), and the acknowledgement of a COMMAND_LONG.
+ * `engaged` : current AP state, the AP engage command is only sent on a change of the armed flag of a mode.
+ */
+pub fn to_ap_commands(frame: &MavFrame, system_id: u8, engaged: bool) -> (Vec<APCmdPayload>, Option<CommandAck>) {
+
+    let for_us = |target_system: u8| target_system == system_id || target_system == 0;
+
+    match frame.message_id {
+        COMMAND_LONG => {
+            let command: CommandLong = CommandLong::decode(&frame.payload);
+            if !for_us(command.target_system) {
+                return (Vec::new(), None);
+            }
+            let (ap_cmds, result) = command_long(&command, engaged);
+            (ap_cmds, Some(CommandAck { command: command.command, result }))
+        },
+        SET_MODE => {
+            let set_mode: SetMode = SetMode::decode(&frame.payload);
+            if !for_us(set_mode.target_system) {
+                return (Vec::new(), None);
+            }
+            (mode_commands(set_mode.base_mode, set_mode.custom_mode, engaged).0, None)
+        },
+        SET_POSITION_TARGET_GLOBAL_INT => {
+            let target: SetPositionTargetGlobalInt = SetPositionTargetGlobalInt::decode(&frame.payload);
+            if !for_us(target.target_system) {
+                return (Vec::new(), None);
+            }
+
+            // Altitude only (no vertical speed target in the AP).
+            let msl: bool = target.coordinate_frame == MAV_FRAME_GLOBAL || target.coordinate_frame == MAV_FRAME_GLOBAL_INT;
+            if target.type_mask & POSITION_TARGET_TYPEMASK_Z_IGNORE == 0 && msl {
+                (vec![APCmdPayload::SetAlt(target.alt / FT_TO_M)], None)
+            } else {
+                (Vec::new(), None)
+            }
+        },
+        _ => (Vec::new(), None)
+    }
+}
+
+pub struct MavlinkEndpoint {
+    config: MavlinkConfig,
+    socket: UdpSocket,
+    peers: Vec<SocketAddr>,
+    subscription: Subscription,
+    publisher: Publisher,
+    sequence: u8,
+    adc_data: Option<AdcDataMessage>,
+    ap_state: APStateMessage,
+    last_telemetry_ms: Option<u64>,
+    last_heartbeat_ms: Option<u64>,
+}
+
+impl MavlinkEndpoint {
+
+    pub fn bind(config: MavlinkConfig, bus: &Bus) -> io::Result<MavlinkEndpoint> {
+
+        let socket: UdpSocket = UdpSocket::bind(&config.address)?;
+        socket.set_nonblocking(true)?;
+        let gcs: SocketAddr = config.gcs.parse().map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
+
+        println!("[MAVLINK] Endpoint on {} (system {}), GCS {}", socket.local_addr()?, config.system_id, gcs);
+
+        Ok(MavlinkEndpoint {
+            subscription: bus.subscribe("MAVLINK", &[Topic::AdcData, Topic::ApState], MAVLINK_QUEUE),
+            publisher: bus.publisher(SourceId::Mavlink),
+            config,
+            socket,
+            peers: vec![gcs],
+            sequence: 0,
+            adc_data: None,
+            ap_state: APStateMessage::new(),
+            last_telemetry_ms: None,
+            last_heartbeat_ms: None,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn local_addr(&self) -> SocketAddr {
+
+        self.socket.local_addr().unwrap()
+    }
+
+    fn send(&mut self, message_id: u32, payload: Vec<u8>) {
+
+        let frame: MavFrame = MavFrame {
+            sequence: self.sequence,
+            system_id: self.config.system_id,
+            component_id: self.config.component_id,
+            message_id,
+            payload,
+        };
+        self.sequence = self.sequence.wrapping_add(1);
+
+        if let Ok(data) = frame.encode() {
+            for peer in self.peers.iter() {
+                // GCS not started : nothing to report.
+                let _ = self.socket.send_to(&data, peer);
+            }
+        }
+    }
+
+    /// Publish the AP commands of the received messages, acknowledge the commands.
+    fn receive(&mut self) {
+
+        let mut data: Vec<u8> = vec![0; MAX_DATAGRAM_SIZE];
+
+        loop {
+            let (size, peer) = match self.socket.recv_from(&mut data) {
+                Ok(received) => received,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                // ICMP port unreachable of a closed peer (Windows), nothing received.
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                Err(e) => {
+                    println!("[MAVLINK] Receive error : {:?}", e);
+                    break;
+                }
+            };
+
+            if !self.peers.contains(&peer) {
+                println!("[MAVLINK] GCS registered : {}", peer);
+                self.peers.push(peer);
+            }
+
+            for frame in decode_frames(&data[..size]) {
+                match frame {
+                    Ok(frame) => {
+                        let (ap_cmds, ack) = to_ap_commands(&frame, self.config.system_id, self.ap_state.engaged);
+                        for ap_cmd in ap_cmds {
+                            self.publisher.publish(BusMessage::APCmd(ap_cmd));
+                        }
+                        if let Some(ack) = ack {
+                            self.send(COMMAND_ACK, ack.payload());
+                        }
+                    },
+                    // Messages of the GCS not handled by the endpoint.
+                    Err(MavlinkError::UnknownMessage(_)) => (),
+                    Err(e) => println!("[MAVLINK] {} from {}", e, peer),
+                }
+            }
+        }
+    }
+
+    /// Heartbeat and status at 1 Hz, ADC data at the telemetry rate.
+    fn send_telemetry(&mut self, now_ms: u64) {
+
+        let due = |last_ms: Option<u64>, period_ms: u64| last_ms.is_none_or(|last_ms| now_ms >= last_ms + period_ms);
+
+        if due(self.last_heartbeat_ms, HEARTBEAT_PERIOD_MS) {
+            self.last_heartbeat_ms = Some(now_ms);
+            self.send(HEARTBEAT, heartbeat(&self.ap_state).payload());
+            self.send(SYS_STATUS, sys_status(self.adc_data.as_ref()).payload());
+        }
+
+        let telemetry_period_ms: u64 = (1000.0 / self.config.telemetry_hz) as u64;
+        if let Some(adc_data) = self.adc_data.clone() {
+            if due(self.last_telemetry_ms, telemetry_period_ms) {
+                self.last_telemetry_ms = Some(now_ms);
+                let time_boot_ms: u32 = now_ms as u32;
+                self.send(ATTITUDE, attitude(&adc_data, time_boot_ms).payload());
+                self.send(VFR_HUD, vfr_hud(&adc_data).payload());
+                self.send(GLOBAL_POSITION_INT, global_position_int(&adc_data, time_boot_ms).payload());
+            }
+        }
+    }
+
+    /// Handle the received messages, keep the bus messages received during `timeout` at most, send the telemetry due.
+    pub fn poll(&mut self, timeout: Duration) {
+
+        self.receive();
+
+        let mut next_envelope = self.subscription.recv_timeout(timeout);
+        while let Some(envelope) = next_envelope {
+            match envelope.message {
+                BusMessage::AdcData(adc_data) => self.adc_data = Some(*adc_data),
+                BusMessage::APState(ap_state) => self.ap_state = ap_state,
+                _ => ()
+            }
+            next_envelope = self.subscription.recv_timeout(Duration::ZERO);
+        }
+
+        self.send_telemetry(clock::now_ms());
+    }
+
+    pub fn run(&mut self, lifecycle: &Lifecycle) {
+
+        while lifecycle.is_running() {
+            self.poll(MAVLINK_POLL_TIMEOUT);
+        }
+    }
+}
+
+#[test]
+fn mavlink_telemetry() {
+
+    let mut ap_state: APStateMessage = APStateMessage::new();
+    ap_state.engaged = true;
+    ap_state.alt_hold_mode = true;
+    ap_state.heading_mode = true;
+    let heartbeat: Heartbeat = heartbeat(&ap_state);
+    assert_eq!(CUSTOM_MODE_ALT_HOLD | CUSTOM_MODE_HEADING, heartbeat.custom_mode);
+    assert_eq!(MAV_MODE_FLAG_CUSTOM_MODE_ENABLED | MAV_MODE_FLAG_SAFETY_ARMED, heartbeat.base_mode);
+    assert_eq!(MAV_STATE_ACTIVE, heartbeat.system_status);
+
+    let mut adc_data: AdcDataMessage = AdcDataMessage::new();
+    adc_data.ias = 250.0;
+    adc_data.alt_msl = 10_000.0;
+    adc_data.vs = -1_000.0;
+    adc_data.heading = 270.0;
+    adc_data.roll_angle = 30.0;
+    adc_data.ground_speed = Some(200.0);
+    adc_data.track = Some(90.0);
+    adc_data.position = Some(crate::bus::GeoPosition{ lat: 45.7256, lon: 5.0811 });
+
+    let attitude: Attitude = attitude(&adc_data, 0);
+    assert!((attitude.roll - std::f32::consts::FRAC_PI_6).abs() < 1e-3);
+    assert!((attitude.yaw + std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+
+    let hud: VfrHud = vfr_hud(&adc_data);
+    assert!((hud.airspeed - 128.611).abs() < 1e-2);
+    assert!((hud.alt - 3048.0).abs() < 1e-2);
+    assert!((hud.climb + 5.08).abs() < 1e-4);
+    assert_eq!(270, hud.heading);
+
+    let position: GlobalPositionInt = global_position_int(&adc_data, 0);
+    assert_eq!((457_256_000, 50_811_000), (position.lat, position.lon));
+    assert_eq!(3_048_000, position.alt);
+    assert_eq!(508, position.vz);
+    assert_eq!((0, 10288), (position.vx, position.vy));
+    assert_eq!(27_000, position.hdg);
+
+    // No ADC data : sensors present, none healthy.
+    let status: SysStatus = sys_status(None);
+    assert_eq!(0x19, status.sensors_present);
+    assert_eq!(0, status.sensors_health);
+}
+
+#[test]
+fn mavlink_commands() {
+
+    let frame = |message_id: u32, payload: Vec<u8>| MavFrame { sequence: 0, system_id: 255, component_id: 190, message_id, payload };
+    let command = |command: u16, target_system: u8, params: [f32; 7]| {
+        frame(COMMAND_LONG, CommandLong { params, command, target_system, target_component: 1, confirmation: 0 }.payload())
+    };
+
+    let (ap_cmds, ack) = to_ap_commands(&command(MAV_CMD_COMPONENT_ARM_DISARM, 1, [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]), 1, false);
+    assert_eq!(vec![APCmdPayload::APEngage(true)], ap_cmds);
+    assert_eq!(Some(CommandAck { command: MAV_CMD_COMPONENT_ARM_DISARM, result: MAV_RESULT_ACCEPTED }), ack);
+
+    let (ap_cmds, _) = to_ap_commands(&command(MAV_CMD_DO_CHANGE_ALTITUDE, 0, [3048.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]), 1, false);
+    assert!(matches!(ap_cmds[..], [APCmdPayload::SetAlt(alt)] if (alt - 10_000.0).abs() < 0.1));
+
+    // No speed target in the AP, altitude in a relative frame, unknown command, other system.
+    let (ap_cmds, ack) = to_ap_commands(&command(MAV_CMD_DO_CHANGE_SPEED, 1, [0.0, 128.611, -1.0, 0.0, 0.0, 0.0, 0.0]), 1, false);
+    assert!(ap_cmds.is_empty());
+    assert_eq!(MAV_RESULT_UNSUPPORTED, ack.unwrap().result);
+    assert_eq!(MAV_RESULT_DENIED, to_ap_commands(&command(MAV_CMD_DO_CHANGE_ALTITUDE, 1, [3048.0, 3.0, 0.0, 0.0, 0.0, 0.0, 0.0]), 1, false).1.unwrap().result);
+    assert_eq!(MAV_RESULT_UNSUPPORTED, to_ap_commands(&command(22, 1, [0.0; 7]), 1, false).1.unwrap().result);
+    assert_eq!((Vec::new(), None), to_ap_commands(&command(MAV_CMD_COMPONENT_ARM_DISARM, 2, [1.0; 7]), 1, false));
+
+    // Modes : AP engage only on a change of the armed flag, VS mode unsupported.
+    let set_mode = |base_mode: u8, custom_mode: u32| frame(SET_MODE, SetMode { custom_mode, target_system: 1, base_mode }.payload());
+    let lnav_mode: MavFrame = set_mode(MAV_MODE_FLAG_CUSTOM_MODE_ENABLED | MAV_MODE_FLAG_SAFETY_ARMED, CUSTOM_MODE_LNAV);
+    assert_eq!(vec![
+        APCmdPayload::APEngage(true),
+        APCmdPayload::EnableLnavMode(true),
+        APCmdPayload::EnableVnavMode(false),
+    ], to_ap_commands(&lnav_mode, 1, false).0);
+    assert_eq!(vec![
+        APCmdPayload::EnableLnavMode(true),
+        APCmdPayload::EnableVnavMode(false),
+    ], to_ap_commands(&lnav_mode, 1, true).0);
+    assert_eq!(vec![APCmdPayload::APEngage(false)], to_ap_commands(&set_mode(0, 0), 1, true).0);
+    assert!(to_ap_commands(&set_mode(MAV_MODE_FLAG_CUSTOM_MODE_ENABLED, CUSTOM_MODE_VS), 1, true).0.is_empty());
+
+    let do_set_mode: MavFrame = command(MAV_CMD_DO_SET_MODE, 1, [MAV_MODE_FLAG_CUSTOM_MODE_ENABLED as f32, CUSTOM_MODE_HEADING as f32, 0.0, 0.0, 0.0, 0.0, 0.0]);
+    assert_eq!((Vec::new(), Some(CommandAck { command: MAV_CMD_DO_SET_MODE, result: MAV_RESULT_UNSUPPORTED })), to_ap_commands(&do_set_mode, 1, false));
+
+    // Vertical speed ignored, altitude in the global frame.
+    let target = |alt: f32, type_mask: u16| frame(SET_POSITION_TARGET_GLOBAL_INT, SetPositionTargetGlobalInt {
+        alt, vz: -2.54, type_mask, target_system: 1, coordinate_frame: MAV_FRAME_GLOBAL_INT }.payload());
+    assert!(to_ap_commands(&target(0.0, 0x0FDF | POSITION_TARGET_TYPEMASK_Z_IGNORE), 1, false).0.is_empty());
+    assert!(matches!(to_ap_commands(&target(3048.0, 0x0FDB), 1, false).0[..], [APCmdPayload::SetAlt(alt)] if (alt - 10_000.0).abs() < 0.1));
+}
+
+#[test]
+fn mavlink_udp_loopback() {
+
+    let bus: Bus = Bus::new();
+    let adc: Publisher = bus.publisher(SourceId::Adc);
+    let ap_cmds: Subscription = bus.subscribe("AP", &[Topic::ApCmd], QueueConfig { capacity: 8, overflow: OverflowPolicy::DropOldest });
+
+    let gcs: UdpSocket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    gcs.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let config: MavlinkConfig = MavlinkConfig { address: "127.0.0.1:0".to_string(), gcs: gcs.local_addr().unwrap().to_string(), ..MavlinkConfig::from(&Yaml::BadValue) };
+    let mut endpoint: MavlinkEndpoint = MavlinkEndpoint::bind(config, &bus).unwrap();
+
+    adc.publish(BusMessage::AdcData(Box::new(AdcDataMessage::new())));
+    endpoint.poll(Duration::from_millis(10));
+
+    let mut data: [u8; 2048] = [0; 2048];
+    let mut message_ids: Vec<u32> = Vec::new();
+    while message_ids.len() < 5 {
+        let size: usize = gcs.recv(&mut data).unwrap();
+        message_ids.extend(decode_frames(&data[..size]).into_iter().map(|frame| frame.unwrap().message_id));
+    }
+    assert_eq!(vec![HEARTBEAT, SYS_STATUS, ATTITUDE, VFR_HUD, GLOBAL_POSITION_INT], message_ids);
+
+    // Command published on the bus and acknowledged.
+    let command: CommandLong = CommandLong { params: [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0], command: MAV_CMD_COMPONENT_ARM_DISARM, target_system: 1, target_component: 1, confirmation: 0 };
+    let frame: MavFrame = MavFrame { sequence: 0, system_id: 255, component_id: 190, message_id: COMMAND_LONG, payload: command.payload() };
+    gcs.send_to(&frame.encode().unwrap(), endpoint.local_addr()).unwrap();
+
+    let envelope = (0..50).find_map(|_| {
+        endpoint.poll(Duration::from_millis(5));
+        ap_cmds.recv_timeout(Duration::ZERO)
+    }).unwrap();
+    assert_eq!(SourceId::Mavlink, envelope.source);
+    assert_eq!(BusMessage::APCmd(APCmdPayload::APEngage(true)), envelope.message);
+
+    let ack: CommandAck = loop {
+        let size: usize = gcs.recv(&mut data).unwrap();
+        if let Some(Ok(frame)) = decode_frames(&data[..size]).into_iter().find(|frame| matches!(frame, Ok(frame) if frame.message_id == COMMAND_ACK)) {
+            break CommandAck::decode(&frame.payload);
+        }
+    };
+    assert_eq!(CommandAck { command: MAV_CMD_COMPONENT_ARM_DISARM, result: MAV_RESULT_ACCEPTED }, ack);
+}
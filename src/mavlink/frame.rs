@@ -0,0 +1,223 @@
+/**
+ * MAVLink framing
+ * v2 frame : 0xFD, payload length, incompat flags, compat flags, sequence, system id, component id, message id (24 bits),
+ * payload (trailing zeros truncated), CRC-16/MCRF4XX of the frame after the magic byte and of the message CRC extra.
+ * v1 frames (0xFE, message id on 8 bits, no flags) are also accepted from the ground stations.
+ * Signed frames are not supported (signature ignored, frame accepted).
+ */
+use std::error::Error;
+use std::fmt;
+
+use crate::mavlink::messages;
+
+pub const MAGIC_V2: u8 = 0xFD;
+pub const MAGIC_V1: u8 = 0xFE;
+
+const HEADER_LEN_V2: usize = 10;
+const HEADER_LEN_V1: usize = 6;
+const CRC_LEN: usize = 2;
+const SIGNATURE_LEN: usize = 13;
+const INCOMPAT_FLAG_SIGNED: u8 = 0x01;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MavlinkError {
+    Truncated,
+    Crc { message_id: u32 },
+    UnknownMessage(u32),
+}
+
+impl fmt::Display for MavlinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MavlinkError::Truncated => write!(f, "MAVLink error: truncated frame"),
+            MavlinkError::Crc { message_id } => write!(f, "MAVLink error: CRC of message {}", message_id),
+            MavlinkError::UnknownMessage(message_id) => write!(f, "MAVLink error: unknown message {}", message_id),
+        }
+    }
+}
+
+impl Error for MavlinkError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MavFrame {
+    pub sequence: u8,
+    pub system_id: u8,
+    pub component_id: u8,
+    pub message_id: u32,
+    pub payload: Vec<u8>,
+}
+
+/// CRC-16/MCRF4XX (X.25) accumulation of `data`.
+pub fn crc_accumulate(crc: u16, data: &[u8]) -> u16 {
+
+    data.iter().fold(crc, |crc, byte| {
+        let mut tmp: u8 = byte ^ (crc & 0xFF) as u8;
+        tmp ^= tmp << 4;
+        let tmp: u16 = tmp as u16;
+        (crc >> 8) ^ (tmp << 8) ^ (tmp << 3) ^ (tmp >> 4)
+    })
+}
+
+impl MavFrame {
+
+    /// v2 frame bytes.
+    pub fn encode(&self) -> Result<Vec<u8>, MavlinkError> {
+
+        let crc_extra: u8 = messages::crc_extra(self.message_id).ok_or(MavlinkError::UnknownMessage(self.message_id))?;
+
+        // Trailing zeros of the payload not sent (at least one byte).
+        let payload_len: usize = self.payload.iter().rposition(|b| *b != 0).map(|i| i + 1).unwrap_or(1).min(self.payload.len());
+        let payload: &[u8] = &self.payload[..payload_len];
+
+        let mut frame: Vec<u8> = Vec::with_capacity(HEADER_LEN_V2 + payload_len + CRC_LEN);
+        frame.push(MAGIC_V2);
+        frame.push(payload_len as u8);
+        frame.push(0);
+        frame.push(0);
+        frame.push(self.sequence);
+        frame.push(self.system_id);
+        frame.push(self.component_id);
+        frame.extend_from_slice(&self.message_id.to_le_bytes()[..3]);
+        frame.extend_from_slice(payload);
+
+        let crc: u16 = crc_accumulate(crc_accumulate(0xFFFF, &frame[1..]), &[crc_extra]);
+        frame.extend_from_slice(&crc.to_le_bytes());
+
+        Ok(frame)
+    }
+
+    /// Header length, payload length, frame length and message id of the frame at the start of `data`.
+    fn header(data: &[u8]) -> Result<(usize, usize, usize, u32), MavlinkError> {
+
+        match data.first() {
+            Some(&MAGIC_V2) if data.len() >= HEADER_LEN_V2 => {
+                let payload_len: usize = data[1] as usize;
+                let signature_len: usize = if data[2] & INCOMPAT_FLAG_SIGNED != 0 { SIGNATURE_LEN } else { 0 };
+                let message_id: u32 = u32::from_le_bytes([data[7], data[8], data[9], 0]);
+                Ok((HEADER_LEN_V2, payload_len, HEADER_LEN_V2 + payload_len + CRC_LEN + signature_len, message_id))
+            },
+            Some(&MAGIC_V1) if data.len() >= HEADER_LEN_V1 => {
+                let payload_len: usize = data[1] as usize;
+                Ok((HEADER_LEN_V1, payload_len, HEADER_LEN_V1 + payload_len + CRC_LEN, data[5] as u32))
+            },
+            _ => Err(MavlinkError::Truncated)
+        }
+    }
+
+    /// Frame at the start of `data` and its length.
+    fn decode(data: &[u8]) -> Result<(MavFrame, usize), MavlinkError> {
+
+        let (header_len, payload_len, frame_len, message_id) = MavFrame::header(data)?;
+
+        if data.len() < frame_len {
+            return Err(MavlinkError::Truncated);
+        }
+
+        let crc_extra: u8 = messages::crc_extra(message_id).ok_or(MavlinkError::UnknownMessage(message_id))?;
+        let crc_offset: usize = header_len + payload_len;
+        let crc: u16 = crc_accumulate(crc_accumulate(0xFFFF, &data[1..crc_offset]), &[crc_extra]);
+
+        if crc.to_le_bytes() != [data[crc_offset], data[crc_offset + 1]] {
+            return Err(MavlinkError::Crc { message_id });
+        }
+
+        let (sequence, system_id, component_id) = match header_len {
+            HEADER_LEN_V2 => (data[4], data[5], data[6]),
+            _ => (data[2], data[3], data[4]),
+        };
+
+        let frame: MavFrame = MavFrame {
+            sequence,
+            system_id,
+            component_id,
+            message_id,
+            payload: data[header_len..crc_offset].to_vec(),
+        };
+
+        Ok((frame, frame_len))
+    }
+}
+
+/// Frames of a datagram, the whole frame skipped on an unknown message (CRC not checkable), bytes skipped up to
+/// the next magic byte on an invalid frame.
+pub fn decode_frames(data: &[u8]) -> Vec<Result<MavFrame, MavlinkError>> {
+
+    let mut frames: Vec<Result<MavFrame, MavlinkError>> = Vec::new();
+    let mut offset: usize = 0;
+
+    while offset < data.len() {
+
+        if data[offset] != MAGIC_V2 && data[offset] != MAGIC_V1 {
+            offset += 1;
+            continue;
+        }
+
+        match MavFrame::decode(&data[offset..]) {
+            Ok((frame, frame_len)) => {
+                frames.push(Ok(frame));
+                offset += frame_len;
+            },
+            Err(MavlinkError::Truncated) => {
+                frames.push(Err(MavlinkError::Truncated));
+                break;
+            },
+            Err(MavlinkError::UnknownMessage(message_id)) => {
+                frames.push(Err(MavlinkError::UnknownMessage(message_id)));
+                offset += MavFrame::header(&data[offset..]).map(|(_, _, frame_len, _)| frame_len).unwrap_or(1);
+            },
+            Err(e) => {
+                frames.push(Err(e));
+                offset += 1;
+            }
+        }
+    }
+
+    frames
+}
+
+#[test]
+fn mavlink_crc() {
+
+    // CRC-16/MCRF4XX check value.
+    assert_eq!(0x6F91, crc_accumulate(0xFFFF, b"123456789"));
+}
+
+#[test]
+fn mavlink_frames() {
+
+    // Heartbeat : 9 bytes payload, trailing zeros truncated.
+    let frame: MavFrame = MavFrame { sequence: 7, system_id: 1, component_id: 1, message_id: 0, payload: vec![0, 0, 0, 0, 1, 8, 0, 0, 0] };
+    let data: Vec<u8> = frame.encode().unwrap();
+    assert_eq!(vec![MAGIC_V2, 6, 0, 0, 7, 1, 1, 0, 0, 0], data[..10].to_vec());
+    assert_eq!(10 + 6 + 2, data.len());
+
+    // Two frames in a datagram, garbage skipped.
+    let mut datagram: Vec<u8> = vec![0x55, 0x00];
+    datagram.extend_from_slice(&data);
+    datagram.extend_from_slice(&MavFrame { sequence: 8, ..frame.clone() }.encode().unwrap());
+    let frames: Vec<Result<MavFrame, MavlinkError>> = decode_frames(&datagram);
+    assert_eq!(2, frames.len());
+    assert_eq!(vec![0, 0, 0, 0, 1, 8], frames[0].as_ref().unwrap().payload);
+    assert_eq!(8, frames[1].as_ref().unwrap().sequence);
+
+    // Unknown message skipped as a whole, magic bytes in its payload not decoded.
+    let mut datagram: Vec<u8> = vec![MAGIC_V2, 4, 0, 0, 1, 1, 1, 0x0F, 0x27, 0, MAGIC_V2, MAGIC_V1, 0, 0, 0xAB, 0xCD];
+    datagram.extend_from_slice(&data);
+    let frames: Vec<Result<MavFrame, MavlinkError>> = decode_frames(&datagram);
+    assert_eq!(2, frames.len());
+    assert_eq!(Err(MavlinkError::UnknownMessage(9999)), frames[0]);
+    assert_eq!(7, frames[1].as_ref().unwrap().sequence);
+
+    // Corrupted payload.
+    let mut corrupted: Vec<u8> = data.clone();
+    corrupted[12] ^= 0x01;
+    assert_eq!(vec![Err(MavlinkError::Crc { message_id: 0 })], decode_frames(&corrupted));
+    assert_eq!(vec![Err(MavlinkError::Truncated)], decode_frames(&data[..12]));
+
+    // v1 frame.
+    let mut v1: Vec<u8> = vec![MAGIC_V1, 9, 3, 255, 190, 0, 0, 0, 0, 0, 6, 8, 0, 0, 3];
+    let crc: u16 = crc_accumulate(crc_accumulate(0xFFFF, &v1[1..]), &[messages::crc_extra(0).unwrap()]);
+    v1.extend_from_slice(&crc.to_le_bytes());
+    let frame: MavFrame = decode_frames(&v1).remove(0).unwrap();
+    assert_eq!((3, 255, 190, 0), (frame.sequence, frame.system_id, frame.component_id, frame.message_id));
+}
@@ -0,0 +1,475 @@
+/*!
+ * MAVLink messages (common dialect) of the endpoint
+ * Payload fields in the wire order (sorted by type size), little endian, extension fields not used.
+ * Received payloads are zero extended to the message length (v2 trailing zeros truncation).
+ */
+
+pub const HEARTBEAT: u32 = 0;
+pub const SYS_STATUS: u32 = 1;
+pub const SET_MODE: u32 = 11;
+pub const ATTITUDE: u32 = 30;
+pub const GLOBAL_POSITION_INT: u32 = 33;
+pub const VFR_HUD: u32 = 74;
+pub const COMMAND_LONG: u32 = 76;
+pub const COMMAND_ACK: u32 = 77;
+pub const SET_POSITION_TARGET_GLOBAL_INT: u32 = 86;
+
+/// CRC extra of the known messages.
+pub fn crc_extra(message_id: u32) -> Option<u8> {
+
+    match message_id {
+        HEARTBEAT => Some(50),
+        SYS_STATUS => Some(124),
+        SET_MODE => Some(89),
+        ATTITUDE => Some(39),
+        GLOBAL_POSITION_INT => Some(104),
+        VFR_HUD => Some(20),
+        COMMAND_LONG => Some(152),
+        COMMAND_ACK => Some(143),
+        SET_POSITION_TARGET_GLOBAL_INT => Some(5),
+        _ => None
+    }
+}
+
+// MAV_TYPE, MAV_AUTOPILOT, MAV_STATE
+pub const MAV_TYPE_FIXED_WING: u8 = 1;
+pub const MAV_AUTOPILOT_GENERIC: u8 = 0;
+pub const MAV_STATE_STANDBY: u8 = 3;
+pub const MAV_STATE_ACTIVE: u8 = 4;
+
+// MAV_MODE_FLAG
+pub const MAV_MODE_FLAG_CUSTOM_MODE_ENABLED: u8 = 1;
+pub const MAV_MODE_FLAG_SAFETY_ARMED: u8 = 128;
+
+// MAV_CMD
+pub const MAV_CMD_DO_CHANGE_SPEED: u16 = 178;
+pub const MAV_CMD_DO_CHANGE_ALTITUDE: u16 = 186;
+pub const MAV_CMD_COMPONENT_ARM_DISARM: u16 = 400;
+
+// MAV_RESULT
+pub const MAV_RESULT_ACCEPTED: u8 = 0;
+pub const MAV_RESULT_UNSUPPORTED: u8 = 3;
+
+// MAV_FRAME (altitude above mean sea level)
+pub const MAV_FRAME_GLOBAL: u8 = 0;
+pub const MAV_FRAME_GLOBAL_INT: u8 = 5;
+
+// MAV_SYS_STATUS_SENSOR
+pub const MAV_SYS_STATUS_SENSOR_3D_GYRO: u32 = 0x01;
+pub const MAV_SYS_STATUS_SENSOR_ABSOLUTE_PRESSURE: u32 = 0x08;
+pub const MAV_SYS_STATUS_SENSOR_DIFFERENTIAL_PRESSURE: u32 = 0x10;
+
+// POSITION_TARGET_TYPEMASK
+pub const POSITION_TARGET_TYPEMASK_Z_IGNORE: u16 = 0x04;
+
+struct PayloadWriter {
+    data: Vec<u8>,
+}
+
+impl PayloadWriter {
+
+    fn new() -> PayloadWriter {
+
+        PayloadWriter { data: Vec::new() }
+    }
+
+    fn u8(mut self, value: u8) -> Self {
+
+        self.data.push(value);
+        self
+    }
+
+    fn i8(self, value: i8) -> Self {
+
+        self.u8(value as u8)
+    }
+
+    fn u16(mut self, value: u16) -> Self {
+
+        self.data.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn i16(self, value: i16) -> Self {
+
+        self.u16(value as u16)
+    }
+
+    fn u32(mut self, value: u32) -> Self {
+
+        self.data.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn i32(self, value: i32) -> Self {
+
+        self.u32(value as u32)
+    }
+
+    fn f32(self, value: f32) -> Self {
+
+        self.u32(value.to_bits())
+    }
+}
+
+struct PayloadReader {
+    data: Vec<u8>,
+    offset: usize,
+}
+
+impl PayloadReader {
+
+    /// Payload zero extended to `len`.
+    fn new(payload: &[u8], len: usize) -> PayloadReader {
+
+        let mut data: Vec<u8> = payload.to_vec();
+        data.resize(data.len().max(len), 0);
+
+        PayloadReader { data, offset: 0 }
+    }
+
+    fn bytes<const N: usize>(&mut self) -> [u8; N] {
+
+        let mut bytes: [u8; N] = [0; N];
+        bytes.copy_from_slice(&self.data[self.offset..self.offset + N]);
+        self.offset += N;
+        bytes
+    }
+
+    fn u8(&mut self) -> u8 {
+
+        self.bytes::<1>()[0]
+    }
+
+    fn u16(&mut self) -> u16 {
+
+        u16::from_le_bytes(self.bytes())
+    }
+
+    fn u32(&mut self) -> u32 {
+
+        u32::from_le_bytes(self.bytes())
+    }
+
+    fn i32(&mut self) -> i32 {
+
+        i32::from_le_bytes(self.bytes())
+    }
+
+    fn f32(&mut self) -> f32 {
+
+        f32::from_le_bytes(self.bytes())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Heartbeat {
+    pub custom_mode: u32,
+    pub mav_type: u8,
+    pub autopilot: u8,
+    pub base_mode: u8,
+    pub system_status: u8,
+}
+
+impl Heartbeat {
+
+    pub fn payload(&self) -> Vec<u8> {
+
+        PayloadWriter::new()
+            .u32(self.custom_mode)
+            .u8(self.mav_type)
+            .u8(self.autopilot)
+            .u8(self.base_mode)
+            .u8(self.system_status)
+            .u8(3)
+            .data
+    }
+}
+
+/// Sensors present, enabled and healthy (MAV_SYS_STATUS_SENSOR bits), no battery.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SysStatus {
+    pub sensors_present: u32,
+    pub sensors_enabled: u32,
+    pub sensors_health: u32,
+}
+
+impl SysStatus {
+
+    pub fn payload(&self) -> Vec<u8> {
+
+        PayloadWriter::new()
+            .u32(self.sensors_present)
+            .u32(self.sensors_enabled)
+            .u32(self.sensors_health)
+            .u16(0)
+            .u16(u16::MAX)
+            .i16(-1)
+            .u16(0)
+            .u16(0)
+            .u16(0)
+            .u16(0)
+            .u16(0)
+            .u16(0)
+            .i8(-1)
+            .data
+    }
+}
+
+/// Attitude (rad, yaw in the -pi to pi range) and angular speeds (rad/s).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attitude {
+    pub time_boot_ms: u32,
+    pub roll: f32,
+    pub pitch: f32,
+    pub yaw: f32,
+    pub rollspeed: f32,
+    pub pitchspeed: f32,
+    pub yawspeed: f32,
+}
+
+impl Attitude {
+
+    pub fn payload(&self) -> Vec<u8> {
+
+        PayloadWriter::new()
+            .u32(self.time_boot_ms)
+            .f32(self.roll)
+            .f32(self.pitch)
+            .f32(self.yaw)
+            .f32(self.rollspeed)
+            .f32(self.pitchspeed)
+            .f32(self.yawspeed)
+            .data
+    }
+}
+
+/// Position (degE7, mm) and NED speeds (cm/s), heading (cdeg).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlobalPositionInt {
+    pub time_boot_ms: u32,
+    pub lat: i32,
+    pub lon: i32,
+    pub alt: i32,
+    pub relative_alt: i32,
+    pub vx: i16,
+    pub vy: i16,
+    pub vz: i16,
+    pub hdg: u16,
+}
+
+impl GlobalPositionInt {
+
+    pub fn payload(&self) -> Vec<u8> {
+
+        PayloadWriter::new()
+            .u32(self.time_boot_ms)
+            .i32(self.lat)
+            .i32(self.lon)
+            .i32(self.alt)
+            .i32(self.relative_alt)
+            .i16(self.vx)
+            .i16(self.vy)
+            .i16(self.vz)
+            .u16(self.hdg)
+            .data
+    }
+}
+
+/// HUD values : speeds (m/s), altitude MSL (m), climb rate (m/s), heading (deg), throttle (%).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VfrHud {
+    pub airspeed: f32,
+    pub groundspeed: f32,
+    pub alt: f32,
+    pub climb: f32,
+    pub heading: i16,
+    pub throttle: u16,
+}
+
+impl VfrHud {
+
+    pub fn payload(&self) -> Vec<u8> {
+
+        PayloadWriter::new()
+            .f32(self.airspeed)
+            .f32(self.groundspeed)
+            .f32(self.alt)
+            .f32(self.climb)
+            .i16(self.heading)
+            .u16(self.throttle)
+            .data
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CommandLong {
+    pub params: [f32; 7],
+    pub command: u16,
+    pub target_system: u8,
+    pub target_component: u8,
+    pub confirmation: u8,
+}
+
+impl CommandLong {
+
+    #[cfg(test)]
+    pub fn payload(&self) -> Vec<u8> {
+
+        let writer: PayloadWriter = self.params.iter().fold(PayloadWriter::new(), |writer, param| writer.f32(*param));
+
+        writer
+            .u16(self.command)
+            .u8(self.target_system)
+            .u8(self.target_component)
+            .u8(self.confirmation)
+            .data
+    }
+
+    pub fn decode(payload: &[u8]) -> CommandLong {
+
+        let mut reader: PayloadReader = PayloadReader::new(payload, 33);
+        let mut params: [f32; 7] = [0.0; 7];
+        for param in params.iter_mut() {
+            *param = reader.f32();
+        }
+
+        CommandLong {
+            params,
+            command: reader.u16(),
+            target_system: reader.u8(),
+            target_component: reader.u8(),
+            confirmation: reader.u8(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CommandAck {
+    pub command: u16,
+    pub result: u8,
+}
+
+impl CommandAck {
+
+    pub fn payload(&self) -> Vec<u8> {
+
+        PayloadWriter::new()
+            .u16(self.command)
+            .u8(self.result)
+            .data
+    }
+
+    #[cfg(test)]
+    pub fn decode(payload: &[u8]) -> CommandAck {
+
+        let mut reader: PayloadReader = PayloadReader::new(payload, 3);
+
+        CommandAck { command: reader.u16(), result: reader.u8() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SetMode {
+    pub custom_mode: u32,
+    pub target_system: u8,
+    pub base_mode: u8,
+}
+
+impl SetMode {
+
+    #[cfg(test)]
+    pub fn payload(&self) -> Vec<u8> {
+
+        PayloadWriter::new()
+            .u32(self.custom_mode)
+            .u8(self.target_system)
+            .u8(self.base_mode)
+            .data
+    }
+
+    pub fn decode(payload: &[u8]) -> SetMode {
+
+        let mut reader: PayloadReader = PayloadReader::new(payload, 6);
+
+        SetMode { custom_mode: reader.u32(), target_system: reader.u8(), base_mode: reader.u8() }
+    }
+}
+
+/// Position target, only the altitude (m) and vertical speed (m/s, NED) are used.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SetPositionTargetGlobalInt {
+    pub alt: f32,
+    pub vz: f32,
+    pub type_mask: u16,
+    pub target_system: u8,
+    pub coordinate_frame: u8,
+}
+
+impl SetPositionTargetGlobalInt {
+
+    #[cfg(test)]
+    pub fn payload(&self) -> Vec<u8> {
+
+        PayloadWriter::new()
+            .u32(0)
+            .i32(0)
+            .i32(0)
+            .f32(self.alt)
+            .f32(0.0)
+            .f32(0.0)
+            .f32(self.vz)
+            .f32(0.0)
+            .f32(0.0)
+            .f32(0.0)
+            .f32(0.0)
+            .f32(0.0)
+            .u16(self.type_mask)
+            .u8(self.target_system)
+            .u8(0)
+            .u8(self.coordinate_frame)
+            .data
+    }
+
+    pub fn decode(payload: &[u8]) -> SetPositionTargetGlobalInt {
+
+        let mut reader: PayloadReader = PayloadReader::new(payload, 53);
+        let _time_boot_ms: u32 = reader.u32();
+        let _lat_int: i32 = reader.i32();
+        let _lon_int: i32 = reader.i32();
+        let alt: f32 = reader.f32();
+        let _vx: f32 = reader.f32();
+        let _vy: f32 = reader.f32();
+        let vz: f32 = reader.f32();
+        for _ in 0..5 {
+            reader.f32();
+        }
+        let type_mask: u16 = reader.u16();
+        let target_system: u8 = reader.u8();
+        let _target_component: u8 = reader.u8();
+
+        SetPositionTargetGlobalInt { alt, vz, type_mask, target_system, coordinate_frame: reader.u8() }
+    }
+}
+
+#[test]
+fn mavlink_payloads_length() {
+
+    assert_eq!(9, Heartbeat { custom_mode: 0, mav_type: 1, autopilot: 0, base_mode: 0, system_status: 0 }.payload().len());
+    assert_eq!(31, SysStatus { sensors_present: 0, sensors_enabled: 0, sensors_health: 0 }.payload().len());
+    assert_eq!(28, Attitude { time_boot_ms: 0, roll: 0.0, pitch: 0.0, yaw: 0.0, rollspeed: 0.0, pitchspeed: 0.0, yawspeed: 0.0 }.payload().len());
+    assert_eq!(28, GlobalPositionInt { time_boot_ms: 0, lat: 0, lon: 0, alt: 0, relative_alt: 0, vx: 0, vy: 0, vz: 0, hdg: 0 }.payload().len());
+    assert_eq!(20, VfrHud { airspeed: 0.0, groundspeed: 0.0, alt: 0.0, climb: 0.0, heading: 0, throttle: 0 }.payload().len());
+
+    let command: CommandLong = CommandLong { params: [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 2.5], command: 400, target_system: 1, target_component: 1, confirmation: 0 };
+    assert_eq!(33, command.payload().len());
+    assert_eq!(command, CommandLong::decode(&command.payload()));
+
+    // Truncated payload (v2) zero extended.
+    let set_mode: SetMode = SetMode { custom_mode: 5, target_system: 1, base_mode: 0 };
+    assert_eq!(set_mode, SetMode::decode(&set_mode.payload()[..5]));
+
+    let target: SetPositionTargetGlobalInt = SetPositionTargetGlobalInt { alt: 3000.0, vz: -2.5, type_mask: 0x0FF8, target_system: 1, coordinate_frame: 5 };
+    assert_eq!(53, target.payload().len());
+    assert_eq!(target, SetPositionTargetGlobalInt::decode(&target.payload()));
+}
@@ -1,63 +1,221 @@
+pub(crate) mod link_monitor;
 pub(crate) mod xpln11_provider;
 pub(crate) mod sim_mock_provider;
+pub(crate) mod replay_provider;
+pub(crate) mod dry_run_provider;
+pub(crate) mod logging_provider;
 
 pub mod providers {
 
     extern crate yaml_rust;
     use core::fmt;
+    use std::error::Error;
     use std::sync::Arc;
     use yaml_rust::Yaml;
     use crate::flight_ctrl::FlightCtrlsProvider;
     use crate::sensors::SensorsProvider;
     use crate::providers::xpln11_provider;
     use crate::providers::sim_mock_provider;
+    use crate::providers::replay_provider;
+    use crate::providers::dry_run_provider;
+    use crate::providers::logging_provider;
 
+    /**
+     * Provider of sensors (source) and/or flight controls (sink).
+     * A provider returns None for the side it doesn't handle (ex: replay has no flight controls).
+     */
     pub trait Provider {
 
         fn name(&self) -> &str;
         fn init(&mut self);
         fn shutdown(&self);
-        fn get_sensors(&self) -> Arc::<dyn SensorsProvider + Send + Sync>;
-        fn get_flcs(&self) -> Arc::<dyn FlightCtrlsProvider + Send + Sync>;
+        /// New sensors reader on each call (ex: replay cursor of its own), the provider link is shared.
+        fn get_sensors(&self) -> Option<Arc::<dyn SensorsProvider + Send + Sync>>;
+        fn get_flcs(&self) -> Option<Arc::<dyn FlightCtrlsProvider + Send + Sync>>;
     }
 
-    enum SensorsProviderEnum {
+    /// Provider config error (ex: unknown provider, replay log not readable, flight controls only provider selected as sensors source).
+    #[derive(Debug)]
+    pub struct ProviderError(pub String);
+
+    impl fmt::Display for ProviderError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "Provider error: {}", self.0)
+        }
+    }
+
+    impl Error for ProviderError {}
+
+    fn sensors_of(provider: &dyn Provider) -> Result<Arc::<dyn SensorsProvider + Send + Sync>, ProviderError> {
+
+        provider.get_sensors()
+                    .ok_or_else(|| ProviderError(format!("{} can't be used as sensors source", provider.name())))
+    }
+
+    enum ProviderEnum {
         XPLN11,
-        SIMMOCK,
+        SimMock,
+        Replay,
+        DryRun,
+        Logging,
     }
 
-    impl fmt::Display for SensorsProviderEnum {
+    impl fmt::Display for ProviderEnum {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             match self {
-                SensorsProviderEnum::XPLN11 => write!(f, "xpln11"),
-                SensorsProviderEnum::SIMMOCK => write!(f, "simmock"),
+                ProviderEnum::XPLN11 => write!(f, "xpln11"),
+                ProviderEnum::SimMock => write!(f, "simmock"),
+                ProviderEnum::Replay => write!(f, "replay"),
+                ProviderEnum::DryRun => write!(f, "dryrun"),
+                ProviderEnum::Logging => write!(f, "logging"),
+            }
+        }
+    }
+
+    /**
+     * Sensors source and flight controls sink selected independently :
+     * - "sensors" section : sensors source provider (default : "provider" section)
+     * - "flcs" section : flight controls sink provider (default : same provider instance as the sensors)
+     * - "adc.redundancy.channels[i].sensors" sections : sensors source of the redundant ADC channels (default : "sensors")
+     */
+    pub struct ProviderSet {
+        sensors_provider: Box<dyn Provider>,
+        flcs_provider: Option<Box<dyn Provider>>,
+        channels_sensors_providers: Vec<Option<Box<dyn Provider>>>,
+    }
+
+    impl ProviderSet {
+
+        pub fn init(&mut self) {
+
+            self.sensors_provider.init();
+
+            if let Some(flcs_provider) = self.flcs_provider.as_mut() {
+                flcs_provider.init();
+            }
+
+            for channel_provider in self.channels_sensors_providers.iter_mut().flatten() {
+                channel_provider.init();
+            }
+        }
+
+        pub fn shutdown(&self) {
+
+            self.sensors_provider.shutdown();
+
+            if let Some(flcs_provider) = self.flcs_provider.as_ref() {
+                flcs_provider.shutdown();
+            }
+
+            for channel_provider in self.channels_sensors_providers.iter().flatten() {
+                channel_provider.shutdown();
             }
         }
+
+        pub fn get_sensors(&self) -> Result<Arc::<dyn SensorsProvider + Send + Sync>, ProviderError> {
+
+            sensors_of(self.sensors_provider.as_ref())
+        }
+
+        /// Sensors of each redundant ADC channel (empty without redundancy) : own provider or a new sensors reader of the main provider.
+        pub fn get_channels_sensors(&self) -> Result<Vec<Arc::<dyn SensorsProvider + Send + Sync>>, ProviderError> {
+
+            self.channels_sensors_providers.iter()
+                        .map(|channel_provider| match channel_provider {
+                            Some(provider) => sensors_of(provider.as_ref()),
+                            None => self.get_sensors()
+                        })
+                        .collect()
+        }
+
+        pub fn get_flcs(&self) -> Result<Arc::<dyn FlightCtrlsProvider + Send + Sync>, ProviderError> {
+
+            let provider: &dyn Provider = self.flcs_provider.as_deref().unwrap_or(self.sensors_provider.as_ref());
+
+            provider.get_flcs()
+                        .ok_or_else(|| ProviderError(format!("{} can't be used as flight controls sink", provider.name())))
+        }
     }
 
-    pub fn resolve_provider(config: &Yaml) -> Box<dyn Provider> {
+    pub fn resolve_providers(config: &Yaml) -> Result<ProviderSet, ProviderError> {
+
+        let sensors_config: &Yaml = match &config["sensors"] {
+            Yaml::BadValue => &config["provider"],
+            sensors_config => sensors_config
+        };
+
+        let sensors_provider: Box<dyn Provider> = resolve_provider(sensors_config)?;
+
+        let flcs_provider: Option<Box<dyn Provider>> = match &config["flcs"] {
+            Yaml::BadValue => None,
+            flcs_config if flcs_config == sensors_config => None,
+            flcs_config => Some(resolve_provider(flcs_config)?)
+        };
+
+        let channels_sensors_providers: Vec<Option<Box<dyn Provider>>> = match config["adc"]["redundancy"]["channels"].as_vec() {
+            Some(channels) => channels.iter()
+                        .map(|channel| match &channel["sensors"] {
+                            Yaml::BadValue => Ok(None),
+                            channel_sensors_config => resolve_provider(channel_sensors_config).map(Some)
+                        })
+                        .collect::<Result<Vec<Option<Box<dyn Provider>>>, ProviderError>>()?,
+            None => Vec::new()
+        };
+
+        Ok(ProviderSet { sensors_provider, flcs_provider, channels_sensors_providers })
+    }
+
+    pub fn resolve_provider(config: &Yaml) -> Result<Box<dyn Provider>, ProviderError> {
 
         println!("Resolving provider");
 
         let provider: Box<dyn Provider>;
 
-        let provider_name = config["provider"]["name"].as_str().unwrap();
+        let provider_name: &str = config["name"].as_str()
+                    .ok_or_else(|| ProviderError("missing provider name".to_string()))?;
 
-        if SensorsProviderEnum::XPLN11.to_string().eq(provider_name) {
-            println!("Loading X-Plane 11 sensors provider");
-            provider = xpln11_provider::xpl11_provider_init(&config) as Box<dyn Provider>;
-        } else if SensorsProviderEnum::SIMMOCK.to_string().eq(provider_name) {
-            println!("Loading SimMock sensors provider");
-            provider = sim_mock_provider::simmock_provider_init(&config) as Box<dyn Provider>;
+        if ProviderEnum::XPLN11.to_string().eq(provider_name) {
+            println!("Loading X-Plane 11 provider");
+            provider = xpln11_provider::xpl11_provider_init(config) as Box<dyn Provider>;
+        } else if ProviderEnum::SimMock.to_string().eq(provider_name) {
+            println!("Loading SimMock provider");
+            provider = sim_mock_provider::simmock_provider_init(config) as Box<dyn Provider>;
+        } else if ProviderEnum::Replay.to_string().eq(provider_name) {
+            println!("Loading Replay sensors provider");
+            provider = replay_provider::replay_provider_init(config)?;
+        } else if ProviderEnum::DryRun.to_string().eq(provider_name) {
+            println!("Loading Dry run flight controls provider");
+            provider = dry_run_provider::dry_run_provider_init(config) as Box<dyn Provider>;
+        } else if ProviderEnum::Logging.to_string().eq(provider_name) {
+            println!("Loading Logging flight controls provider");
+            provider = logging_provider::logging_provider_init(config)?;
         } else {
-            panic!("Unknown provider: {provider_name}");
+            return Err(ProviderError(format!("unknown provider {}", provider_name)));
         }
 
         println!("End init module");
 
-        return provider;
+        Ok(provider)
     }
 
 }
 
-pub use providers::resolve_provider;
+pub use providers::resolve_providers;
+
+#[test]
+fn providers_config_errors() {
+
+    let config: Vec<yaml_rust::Yaml> = yaml_rust::YamlLoader::load_from_str("provider:\n  name: dryrun\n").unwrap();
+    let providers: providers::ProviderSet = resolve_providers(&config[0]).unwrap();
+
+    assert_eq!("Provider error: DRYRUN can't be used as sensors source", providers.get_sensors().err().unwrap().to_string());
+    assert!(providers.get_flcs().is_ok());
+
+    let message = |yaml: &str| resolve_providers(&yaml_rust::YamlLoader::load_from_str(yaml).unwrap()[0]).err().unwrap().to_string();
+
+    assert_eq!("Provider error: unknown provider xplane12", message("provider:\n  name: xplane12\n"));
+    assert_eq!("Provider error: missing provider name", message("sensors:\n  path: ./log.csv\nflcs:\n  name: dryrun\n"));
+    assert!(message("provider:\n  name: replay\n").ends_with("replay log path missing"));
+    assert!(message("provider:\n  name: replay\n  path: ./conf/missing.csv\n").starts_with("Provider error: replay log ./conf/missing.csv :"));
+    assert!(message("sensors:\n  name: simmock\nflcs:\n  name: logging\n  path: ./conf/missing/flcs.csv\n").starts_with("Provider error: logging file ./conf/missing/flcs.csv :"));
+}
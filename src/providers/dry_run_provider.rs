@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use yaml_rust::Yaml;
+use crate::sensors::SensorsProvider;
+use crate::flight_ctrl::{FlightCtrlsProvider, FlightCtrlsCommand};
+use super::providers::Provider;
+
+const NAME: &str = "DRYRUN";
+const DEFAULT_HISTORY_SIZE: usize = 1_000;
+
+/**
+ * Dry run flight controls provider
+ * Only record the AP commands (last "history_size" commands kept), nothing is sent to the aircraft.
+ * Used to run the AP open loop against real sensors data.
+ */
+pub struct DryRunProvider {
+
+    flcs: Arc<DryRunFlightCtrlsProvider>,
+}
+
+impl Provider for DryRunProvider {
+
+    fn name(&self) -> &str {
+
+        NAME
+    }
+
+    fn init(&mut self) {
+        println!("DryRun Provider config : history_size: {}", self.flcs.history_size);
+    }
+
+    fn shutdown(&self) {
+
+        let commands = self.flcs.commands.lock().unwrap();
+
+        println!("DryRun Provider shutdown : {} commands recorded, last : {:?}", 
+            self.flcs.count.load(Ordering::SeqCst), commands.back());
+    }
+
+    fn get_sensors(&self) -> Option<Arc::<dyn SensorsProvider + Send + Sync>> {
+
+        None
+    }
+
+    fn get_flcs(&self) -> Option<Arc::<dyn FlightCtrlsProvider + Send + Sync>> {
+
+        Some(self.flcs.clone())
+    }
+}
+
+struct DryRunFlightCtrlsProvider {
+
+    history_size: usize,
+    count: AtomicUsize,
+    commands: Mutex<VecDeque<FlightCtrlsCommand>>,
+}
+
+impl FlightCtrlsProvider for DryRunFlightCtrlsProvider {
+
+    fn send(&self, cmd: &FlightCtrlsCommand) {
+
+        let mut commands = self.commands.lock().unwrap();
+
+        if commands.len() >= self.history_size {
+            commands.pop_front();
+        }
+
+        commands.push_back(cmd.clone());
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn release(&self) {
+        println!("DryRun Provider release");
+    }
+}
+
+pub fn dry_run_provider_init(config: &Yaml) -> Box<dyn Provider> {
+
+    println!("Start init dry_run_provider module");
+
+    let history_size: usize = config["history_size"].as_i64().map(|v| v as usize).unwrap_or(DEFAULT_HISTORY_SIZE);
+
+    let flcs: DryRunFlightCtrlsProvider = DryRunFlightCtrlsProvider {
+        history_size,
+        count: AtomicUsize::new(0),
+        commands: Mutex::new(VecDeque::with_capacity(history_size)),
+    };
+
+    let provider = Box::new(DryRunProvider{ flcs: Arc::new(flcs) });
+
+    println!("End init dry_run_provider module");
+
+    provider
+}
@@ -0,0 +1,210 @@
+/**
+ * Provider link monitor
+ * Connection state machine shared by the network providers :
+ * - Connecting : socket bound, waiting for the first data message
+ * - Receiving : data messages received within the stale timeout
+ * - Stale : no data message since the stale timeout
+ * - Lost : no data message since the lost timeout (or socket error), reconnect with backoff
+ */
+use std::time::{Duration, Instant};
+
+use yaml_rust::Yaml;
+
+use crate::bus::LinkState;
+
+const DEFAULT_STALE_TIMEOUT_MS: u64 = 500;
+const DEFAULT_LOST_TIMEOUT_MS: u64 = 3_000;
+const DEFAULT_RECONNECT_BACKOFF_MS: u64 = 500;
+const DEFAULT_RECONNECT_BACKOFF_MAX_MS: u64 = 8_000;
+
+#[derive(Debug, Clone)]
+pub struct LinkConfig {
+
+    pub stale_timeout: Duration,
+    pub lost_timeout: Duration,
+    pub reconnect_backoff: Duration,
+    pub reconnect_backoff_max: Duration,
+}
+
+impl LinkConfig {
+
+    /// Read the optional `link` section of a provider configuration, missing values use defaults.
+    pub fn from(config: &Yaml) -> LinkConfig {
+
+        let read_ms = |key: &str, default: u64| -> Duration {
+            let value: u64 = config[key].as_i64().map(|v| v as u64).unwrap_or(default);
+            Duration::from_millis(value)
+        };
+
+        LinkConfig {
+            stale_timeout: read_ms("stale_timeout_ms", DEFAULT_STALE_TIMEOUT_MS),
+            lost_timeout: read_ms("lost_timeout_ms", DEFAULT_LOST_TIMEOUT_MS),
+            reconnect_backoff: read_ms("reconnect_backoff_ms", DEFAULT_RECONNECT_BACKOFF_MS),
+            reconnect_backoff_max: read_ms("reconnect_backoff_max_ms", DEFAULT_RECONNECT_BACKOFF_MAX_MS),
+        }
+    }
+}
+
+pub struct LinkMonitor {
+
+    config: LinkConfig,
+    state: LinkState,
+    connected_at: Option<Instant>,
+    last_rx: Option<Instant>,
+    backoff: Duration,
+    next_reconnect: Option<Instant>,
+}
+
+impl LinkMonitor {
+
+    pub fn new(config: LinkConfig) -> LinkMonitor {
+
+        let backoff: Duration = config.reconnect_backoff;
+
+        LinkMonitor {
+            config,
+            state: LinkState::Connecting,
+            connected_at: None,
+            last_rx: None,
+            backoff,
+            next_reconnect: None,
+        }
+    }
+
+    pub fn state(&self) -> LinkState {
+
+        self.state
+    }
+
+    /// Socket (re)bound, wait for the first data message.
+    pub fn on_connected(&mut self, now: Instant) {
+
+        self.set_state(LinkState::Connecting);
+        self.connected_at = Some(now);
+        self.last_rx = None;
+        self.next_reconnect = None;
+    }
+
+    /// Bind or socket error, the link is lost and a reconnect is scheduled after the current backoff.
+    pub fn on_link_error(&mut self, now: Instant) {
+
+        self.set_state(LinkState::Lost);
+        self.connected_at = None;
+        self.next_reconnect = Some(now + self.backoff);
+        self.backoff = (self.backoff * 2).min(self.config.reconnect_backoff_max);
+    }
+
+    pub fn on_receive(&mut self, now: Instant) {
+
+        self.set_state(LinkState::Receiving);
+        self.last_rx = Some(now);
+        self.backoff = self.config.reconnect_backoff;
+    }
+
+    /// Evaluate timeouts when no data message has been received.
+    pub fn update(&mut self, now: Instant) {
+
+        if self.state == LinkState::Lost {
+            return;
+        }
+
+        let reference: Option<Instant> = self.last_rx.or(self.connected_at);
+
+        if let Some(reference) = reference {
+
+            let silence: Duration = now.saturating_duration_since(reference);
+
+            if silence >= self.config.lost_timeout {
+                self.on_link_error(now);
+            } else if silence >= self.config.stale_timeout && self.state == LinkState::Receiving {
+                self.set_state(LinkState::Stale);
+            }
+        }
+    }
+
+    pub fn reconnect_due(&self, now: Instant) -> bool {
+
+        match self.next_reconnect {
+            Some(next) => self.state == LinkState::Lost && now >= next,
+            None => false
+        }
+    }
+
+    fn set_state(&mut self, state: LinkState) {
+
+        if self.state != state {
+            println!("[LINK] {:?} -> {:?}", self.state, state);
+            self.state = state;
+        }
+    }
+}
+
+#[test]
+fn link_monitor_timeouts() {
+
+    let config: LinkConfig = LinkConfig {
+        stale_timeout: Duration::from_millis(100),
+        lost_timeout: Duration::from_millis(1_000),
+        reconnect_backoff: Duration::from_millis(200),
+        reconnect_backoff_max: Duration::from_millis(500),
+    };
+
+    let t0: Instant = Instant::now();
+    let mut monitor: LinkMonitor = LinkMonitor::new(config);
+    monitor.on_connected(t0);
+    assert_eq!(LinkState::Connecting, monitor.state());
+
+    // Still connecting while the sim has not sent anything, stale only applies once receiving.
+    monitor.update(t0 + Duration::from_millis(500));
+    assert_eq!(LinkState::Connecting, monitor.state());
+
+    monitor.on_receive(t0 + Duration::from_millis(600));
+    assert_eq!(LinkState::Receiving, monitor.state());
+
+    monitor.update(t0 + Duration::from_millis(650));
+    assert_eq!(LinkState::Receiving, monitor.state());
+
+    monitor.update(t0 + Duration::from_millis(750));
+    assert_eq!(LinkState::Stale, monitor.state());
+
+    monitor.on_receive(t0 + Duration::from_millis(800));
+    assert_eq!(LinkState::Receiving, monitor.state());
+
+    monitor.update(t0 + Duration::from_millis(1_800));
+    assert_eq!(LinkState::Lost, monitor.state());
+}
+
+#[test]
+fn link_monitor_reconnect_backoff() {
+
+    let config: LinkConfig = LinkConfig {
+        stale_timeout: Duration::from_millis(100),
+        lost_timeout: Duration::from_millis(1_000),
+        reconnect_backoff: Duration::from_millis(200),
+        reconnect_backoff_max: Duration::from_millis(500),
+    };
+
+    let t0: Instant = Instant::now();
+    let mut monitor: LinkMonitor = LinkMonitor::new(config);
+
+    monitor.on_link_error(t0);
+    assert_eq!(LinkState::Lost, monitor.state());
+    assert!(!monitor.reconnect_due(t0 + Duration::from_millis(199)));
+    assert!(monitor.reconnect_due(t0 + Duration::from_millis(200)));
+
+    // Backoff doubles up to the configured maximum.
+    let t1: Instant = t0 + Duration::from_millis(200);
+    monitor.on_link_error(t1);
+    assert!(!monitor.reconnect_due(t1 + Duration::from_millis(399)));
+    assert!(monitor.reconnect_due(t1 + Duration::from_millis(400)));
+
+    let t2: Instant = t1 + Duration::from_millis(400);
+    monitor.on_link_error(t2);
+    assert!(monitor.reconnect_due(t2 + Duration::from_millis(500)));
+
+    // Receiving resets the backoff.
+    monitor.on_connected(t2);
+    monitor.on_receive(t2);
+    monitor.on_link_error(t2);
+    assert!(monitor.reconnect_due(t2 + Duration::from_millis(200)));
+}
@@ -0,0 +1,119 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use yaml_rust::Yaml;
+use crate::sensors::SensorsProvider;
+use crate::flight_ctrl::{FlightCtrlsProvider, FlightCtrlsCommand};
+use super::providers::{Provider, ProviderError};
+
+const NAME: &str = "LOGGING";
+
+/**
+ * Logging flight controls provider
+ * Write every AP command as a CSV line (time_ms,pitch,roll,yaw,throttle, empty when not driven)
+ * to the "path" file or to stdout, nothing is sent to the aircraft.
+ */
+pub struct LoggingProvider {
+
+    path: Option<String>,
+    flcs: Arc<LoggingFlightCtrlsProvider>,
+}
+
+impl Provider for LoggingProvider {
+
+    fn name(&self) -> &str {
+
+        NAME
+    }
+
+    fn init(&mut self) {
+        println!("Logging Provider config : path: {:?}", self.path);
+
+        self.flcs.write_line(String::from("time_ms,pitch,roll,yaw,throttle"));
+    }
+
+    fn shutdown(&self) {
+        println!("Logging Provider shutdown");
+
+        self.flcs.flush();
+    }
+
+    fn get_sensors(&self) -> Option<Arc::<dyn SensorsProvider + Send + Sync>> {
+
+        None
+    }
+
+    fn get_flcs(&self) -> Option<Arc::<dyn FlightCtrlsProvider + Send + Sync>> {
+
+        Some(self.flcs.clone())
+    }
+}
+
+struct LoggingFlightCtrlsProvider {
+
+    start: Instant,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl LoggingFlightCtrlsProvider {
+
+    fn write_line(&self, line: String) {
+
+        if let Err(e) = writeln!(self.writer.lock().unwrap(), "{}", line) {
+            println!("Logging Provider write error : {:?}", e);
+        }
+    }
+
+    fn flush(&self) {
+
+        if let Err(e) = self.writer.lock().unwrap().flush() {
+            println!("Logging Provider flush error : {:?}", e);
+        }
+    }
+}
+
+impl FlightCtrlsProvider for LoggingFlightCtrlsProvider {
+
+    fn send(&self, cmd: &FlightCtrlsCommand) {
+
+        let field = |value: Option<f32>| -> String {
+            value.map(|v| v.to_string()).unwrap_or_default()
+        };
+
+        self.write_line(format!("{},{},{},{},{}", 
+            self.start.elapsed().as_millis(), field(cmd.pitch), field(cmd.roll), field(cmd.yaw), field(cmd.throttle)));
+    }
+
+    fn release(&self) {
+
+        self.flush();
+    }
+}
+
+pub fn logging_provider_init(config: &Yaml) -> Result<Box<dyn Provider>, ProviderError> {
+
+    println!("Start init logging_provider module");
+
+    let path: Option<String> = config["path"].as_str().map(|p| p.to_string());
+
+    let writer: Box<dyn Write + Send> = match &path {
+        Some(p) => {
+            let file: File = File::create(p).map_err(|e| ProviderError(format!("logging file {} : {}", p, e)))?;
+            Box::new(BufWriter::new(file))
+        },
+        None => Box::new(io::stdout())
+    };
+
+    let flcs: LoggingFlightCtrlsProvider = LoggingFlightCtrlsProvider {
+        start: Instant::now(),
+        writer: Mutex::new(writer),
+    };
+
+    let provider = Box::new(LoggingProvider{ path, flcs: Arc::new(flcs) });
+
+    println!("End init logging_provider module");
+
+    Ok(provider)
+}
@@ -0,0 +1,222 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use yaml_rust::Yaml;
+use crate::bus::LinkState;
+use crate::sensors::SensorsProvider;
+use crate::flight_ctrl::FlightCtrlsProvider;
+use crate::sensors::sensors::{SensorsValues, SensorsSample};
+use super::providers::{Provider, ProviderError};
+
+const NAME: &str = "REPLAY";
+const REPLAY_COLUMNS: [&str; 11] = ["ias", "alt_msl", "alt_agl", "heading", "vs", "aoa", "mach", "g_load", "pitch", "roll", "yaw"];
+
+#[derive(Debug)]
+struct ReplayError(String);
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Replay error: {}", self.0)
+    }
+}
+
+impl Error for ReplayError {}
+
+/**
+ * Replay sensors provider
+ * Replay a CSV sensors log (one row per ADC frame), header with the SensorsValues field names :
+ * ias,alt_msl,alt_agl,heading,vs,aoa,mach,g_load,pitch,roll,yaw
+ * Optional columns (empty value when not available) : static_pressure (hPa), oat (°C), ground_speed (knots), track (deg true)
+ */
+pub struct ReplayProvider {
+
+    path: String,
+    looped: bool,
+    samples: Arc<Vec<SensorsValues>>,
+}
+
+impl Provider for ReplayProvider {
+
+    fn name(&self) -> &str {
+
+        NAME
+    }
+
+    fn init(&mut self) {
+        println!("Replay Provider config : path: {}, loop: {}, samples: {}", self.path, self.looped, self.samples.len());
+    }
+
+    fn shutdown(&self) {
+        println!("Replay Provider shutdown");
+    }
+
+    fn get_sensors(&self) -> Option<Arc::<dyn SensorsProvider + Send + Sync>> {
+
+        Some(Arc::new(ReplaySensorsProvider{ 
+            samples: self.samples.clone(), 
+            looped: self.looped, 
+            next_sample: AtomicUsize::new(0) 
+        }))
+    }
+
+    fn get_flcs(&self) -> Option<Arc::<dyn FlightCtrlsProvider + Send + Sync>> {
+
+        None
+    }
+}
+
+struct ReplaySensorsProvider {
+
+    samples: Arc<Vec<SensorsValues>>,
+    looped: bool,
+    next_sample: AtomicUsize,
+}
+
+impl ReplaySensorsProvider {
+
+    fn ended(&self) -> bool {
+
+        !self.looped && self.next_sample.load(Ordering::SeqCst) >= self.samples.len()
+    }
+}
+
+impl SensorsProvider for ReplaySensorsProvider {
+
+    fn acquire(&self) -> Result<SensorsSample, Box<dyn std::error::Error>> {
+
+        let sample_index: usize = self.next_sample.fetch_add(1, Ordering::SeqCst);
+
+        if self.looped {
+            return Ok(SensorsSample::fresh(self.samples[sample_index % self.samples.len()].clone()));
+        }
+
+        match self.samples.get(sample_index) {
+            Some(values) => Ok(SensorsSample::fresh(values.clone())),
+            None => Err(Box::new(ReplayError("End of replay log".into())))
+        }
+    }
+
+    fn link_state(&self) -> LinkState {
+
+        if self.ended() { LinkState::Lost } else { LinkState::Receiving }
+    }
+}
+
+pub fn replay_provider_init(config: &Yaml) -> Result<Box<dyn Provider>, ProviderError> {
+
+    println!("Start init replay_provider module");
+
+    let path: String = config["path"].as_str()
+                .ok_or_else(|| ProviderError("replay log path missing".to_string()))?
+                .to_string();
+    let looped: bool = config["loop"].as_bool().unwrap_or(true);
+
+    let samples: Vec<SensorsValues> = std::fs::read_to_string(&path)
+                .map_err(|e| e.into())
+                .and_then(|content| load_replay_log(&content))
+                .map_err(|e| ProviderError(format!("replay log {} : {}", path, e)))?;
+
+    let provider = Box::new(ReplayProvider{ path, looped, samples: Arc::new(samples) });
+
+    println!("End init replay_provider module");
+
+    Ok(provider)
+}
+
+fn load_replay_log(content: &str) -> Result<Vec<SensorsValues>, Box<dyn Error>> {
+
+    let mut lines = content.lines()
+                .enumerate()
+                .filter(|(_, l)| !l.trim().is_empty() && !l.trim().starts_with('#'));
+
+    let header: Vec<&str> = match lines.next() {
+        Some((_, l)) => l.split(',').map(|c| c.trim()).collect(),
+        None => return Err(Box::new(ReplayError("Empty replay log".into())))
+    };
+
+    // Column index in the file for each SensorsValues field.
+    let mut columns: [usize; 11] = [0; 11];
+
+    for (i, name) in REPLAY_COLUMNS.iter().enumerate() {
+        columns[i] = match header.iter().position(|c| c == name) {
+            Some(column) => column,
+            None => return Err(Box::new(ReplayError(format!("Missing column {}", name))))
+        };
+    }
+
+    let static_pressure_column: Option<usize> = header.iter().position(|c| *c == "static_pressure");
+    let oat_column: Option<usize> = header.iter().position(|c| *c == "oat");
+    let ground_speed_column: Option<usize> = header.iter().position(|c| *c == "ground_speed");
+    let track_column: Option<usize> = header.iter().position(|c| *c == "track");
+
+    let mut samples: Vec<SensorsValues> = Vec::new();
+
+    for (line_number, line) in lines {
+
+        let fields: Vec<&str> = line.split(',').map(|c| c.trim()).collect();
+        let mut v: [f32; 11] = [0f32; 11];
+
+        for (i, column) in columns.iter().enumerate() {
+
+            let field: &str = fields.get(*column).copied().unwrap_or("");
+
+            v[i] = field.parse::<f32>().map_err(|_| 
+                ReplayError(format!("line {}: invalid {} value '{}'", line_number + 1, REPLAY_COLUMNS[i], field)))?;
+        }
+
+        let mut values: SensorsValues = SensorsValues::from(v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7], v[8], v[9], v[10]);
+        values.static_pressure = parse_optional_field(&fields, static_pressure_column);
+        values.oat = parse_optional_field(&fields, oat_column);
+        values.ground_speed = parse_optional_field(&fields, ground_speed_column);
+        values.track = parse_optional_field(&fields, track_column);
+
+        samples.push(values);
+    }
+
+    if samples.is_empty() {
+        return Err(Box::new(ReplayError("No sample in replay log".into())));
+    }
+
+    Ok(samples)
+}
+
+fn parse_optional_field(fields: &[&str], column: Option<usize>) -> Option<f32> {
+
+    column.and_then(|c| fields.get(c)).and_then(|f| f.parse::<f32>().ok())
+}
+
+#[test]
+fn load_replay_log_test() {
+
+    let content: &str = "# recorded sample\n\
+        yaw,roll,pitch,g_load,mach,aoa,vs,heading,alt_agl,alt_msl,ias\n\
+        0,0,2.5,1,0.4,3,0,180,9000,10000,250\n\
+        \n\
+        0,5,2.5,1,0.4,3,100,181,9010,10010,251\n";
+
+    let samples: Vec<SensorsValues> = load_replay_log(content).unwrap();
+
+    assert_eq!(2, samples.len());
+    assert_eq!(250.0, samples[0].ias);
+    assert_eq!(10_000.0, samples[0].alt_msl);
+    assert_eq!(5.0, samples[1].roll);
+    assert_eq!(100.0, samples[1].vs);
+
+    assert_eq!(None, samples[0].static_pressure);
+
+    let header: &str = "ias,alt_msl,alt_agl,heading,vs,aoa,mach,g_load,pitch,roll,yaw,static_pressure,oat,ground_speed,track\n";
+    let samples: Vec<SensorsValues> = load_replay_log(&format!("{}250,0,0,0,0,0,0,0,0,0,0,696.8,,310,92.5\n", header)).unwrap();
+    assert_eq!(Some(696.8), samples[0].static_pressure);
+    assert_eq!(None, samples[0].oat);
+    assert_eq!(Some(310.0), samples[0].ground_speed);
+    assert_eq!(Some(92.5), samples[0].track);
+
+    let error: String = load_replay_log("ias,alt_msl\n250,10000\n").unwrap_err().to_string();
+    assert_eq!("Replay error: Missing column alt_agl", error);
+
+    let header: &str = "ias,alt_msl,alt_agl,heading,vs,aoa,mach,g_load,pitch,roll,yaw\n";
+    let error: String = load_replay_log(&format!("{}250,x,0,0,0,0,0,0,0,0,0\n", header)).unwrap_err().to_string();
+    assert_eq!("Replay error: line 2: invalid alt_msl value 'x'", error);
+}
@@ -3,8 +3,8 @@ use std::sync::Arc;
 
 use yaml_rust::Yaml;
 use crate::sensors::SensorsProvider;
-use crate::flight_ctrl::FlightCtrlsProvider;
-use crate::sensors::sensors::SensorsValues;
+use crate::flight_ctrl::{FlightCtrlsProvider, FlightCtrlsCommand};
+use crate::sensors::sensors::{SensorsValues, SensorsSample};
 use super::providers::Provider;
 
 const NAME: &str = "SIMMOCK";
@@ -29,14 +29,14 @@ impl Provider for SimMockProvider {
         println!("SimMock Provider shutdown");
     }
 
-    fn get_sensors(&self) -> Arc::<dyn SensorsProvider + Send + Sync> {
+    fn get_sensors(&self) -> Option<Arc::<dyn SensorsProvider + Send + Sync>> {
 
-        Arc::new(SimMockSensorsProvider{})
+        Some(Arc::new(SimMockSensorsProvider{}))
     }
 
-    fn get_flcs(&self) -> Arc::<dyn FlightCtrlsProvider + Send + Sync> {
+    fn get_flcs(&self) -> Option<Arc::<dyn FlightCtrlsProvider + Send + Sync>> {
 
-        Arc::new(SimMockFlightCtrlsProvider{})
+        Some(Arc::new(SimMockFlightCtrlsProvider{}))
     }
 }
 
@@ -46,7 +46,7 @@ struct SimMockSensorsProvider {
 
 impl SensorsProvider for SimMockSensorsProvider {
 
-    fn acquire(&self) -> Result<SensorsValues, Box<dyn std::error::Error>> {
+    fn acquire(&self) -> Result<SensorsSample, Box<dyn std::error::Error>> {
         //println!("SimMock Provider acquire");
         let result = SensorsValues::from(
             250f32,
@@ -61,7 +61,7 @@ impl SensorsProvider for SimMockSensorsProvider {
             30f32,
             0f32);
         
-        Ok(result)
+        Ok(SensorsSample::fresh(result))
     }
 }
 
@@ -71,8 +71,12 @@ struct  SimMockFlightCtrlsProvider {
 
 impl FlightCtrlsProvider for SimMockFlightCtrlsProvider {
 
-    fn send(&self) {
-        println!("SimMock Provider send");
+    fn send(&self, cmd: &FlightCtrlsCommand) {
+        println!("SimMock Provider send : {:?}", cmd);
+    }
+
+    fn release(&self) {
+        println!("SimMock Provider release");
     }
 }
 
@@ -1,19 +1,27 @@
 
 mod constants;
+#[cfg(test)]
+mod xpln11_stub;
 
 use num_traits::FromPrimitive;
 use yaml_rust::Yaml;
 use std::error::Error;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
 use std::fmt;
 use std::collections::HashMap;
-use std::time::Duration;
+use std::io::ErrorKind;
+use std::time::{Duration, Instant};
 use std::net::UdpSocket;
 use crate::{sensors::SensorsProvider, providers::xpln11_provider::constants::GnssEnum};
-use crate::flight_ctrl::FlightCtrlsProvider;
-use crate::sensors::sensors::SensorsValues;
+use crate::bus::{GeoPosition, LinkState};
+use crate::flight_ctrl::{FlightCtrlsProvider, FlightCtrlsCommand};
+use crate::sensors::sensors::{BodyAccels, BodyRates, SensorsValues, SensorsSample, SensorField};
+use crate::clock;
+use super::link_monitor::{LinkConfig, LinkMonitor};
 use super::providers::Provider;
-use crate::providers::xpln11_provider::constants::{XPLN11DataReadEnum, SpeedsEnum, MachVVIGloadEnum, AoAEnum, PitchRollHeadingsEnum, ClimbStatsEnum, MagCompassEnum};
+use crate::providers::xpln11_provider::constants::{XPLN11DataReadEnum, SpeedsEnum, MachVVIGloadEnum, AngularVelocitiesEnum, AoAEnum, PitchRollHeadingsEnum, ClimbStatsEnum, MagCompassEnum, AtmosphereAircraftEnum};
 
 const NAME: &str = "XPLN11";
 const DATA_MESSAGE_BUFFER_SIZE_VALUE:usize = 1024;
@@ -21,6 +29,16 @@ const DATA_MESSAGE_MIN_BYTES_SIZE_VALUE:usize = 9;
 const DATA_MESSAGE_NO_DATA: f32 = -999f32;
 const DATA_MESSAGE_READ_TIMEOUT_VALUE:Duration = Duration::from_millis(100);
 const DATA_MESSAGE_WRITE_TIMEOUT_VALUE:Duration = Duration::from_millis(100);
+const DATA_INHG_TO_HPA: f32 = 33.863_89;
+const SUBSCRIBED_DATA_INDEXES: [i32; 8] = [
+    XPLN11DataReadEnum::Speeds as i32,
+    XPLN11DataReadEnum::MachVviGLoad as i32,
+    XPLN11DataReadEnum::AtmosphereAircraft as i32,
+    XPLN11DataReadEnum::AngularVelocities as i32,
+    XPLN11DataReadEnum::PitchRollHeadings as i32,
+    XPLN11DataReadEnum::AoA as i32,
+    XPLN11DataReadEnum::Gnss as i32,
+    XPLN11DataReadEnum::ClimbStats as i32];
 
 
 #[derive(Debug)]
@@ -75,185 +93,348 @@ impl XPLN11UDPDataFragment {
 
 pub struct XPLN11Provider {
 
+    link: Arc<XPLN11Link>,
+}
+
+/// UDP link shared by the sensors and flight controls providers, rebound on link loss.
+struct XPLN11Link {
+
     host: String,
     read_port: i64,
     write_port: i64,
-    socket: Option<UdpSocket>,
+    socket: Mutex<Option<Arc<UdpSocket>>>,
+    monitor: Mutex<LinkMonitor>,
+    cache: Mutex<XPLN11SampleCache>,
+    running: AtomicBool,
+    receiver: Mutex<Option<JoinHandle<()>>>,
 }
 
-impl XPLN11Provider {
+/// Latest sample received from X-Plane, DATA messages merged in receive order.
+struct XPLN11SampleCache {
 
-    fn connect_read_socket(&mut self) -> std::io::Result<()> {
+    values: SensorsValues,
+    updated_at: Option<Instant>,
+}
+
+impl XPLN11Link {
+
+    fn connect_read_socket(&self) -> std::io::Result<()> {
 
         println!("Connecting to xplane read socket");
 
+        // Release the previous socket before binding the same port again.
+        *self.socket.lock().unwrap() = None;
+
         let url: String = format!("{}:{}", self.host, self.read_port);
 
-        let bind_result: Result<UdpSocket, std::io::Error> = UdpSocket::bind(url);
-        
-        if bind_result.is_ok() {
+        match UdpSocket::bind(url) {
+            Ok(s) => {
+
+                println!("UDP read socket binded");
+
+                s.set_read_timeout(Some(DATA_MESSAGE_READ_TIMEOUT_VALUE))?;
+                s.set_write_timeout(Some(DATA_MESSAGE_WRITE_TIMEOUT_VALUE))?;
 
-            println!("UDP read socket binded");
-            let s: UdpSocket =  bind_result.unwrap();
-            
-            s.set_read_timeout(Some(DATA_MESSAGE_READ_TIMEOUT_VALUE)).expect("set_read_timeout call failed");
-            s.set_write_timeout(Some(DATA_MESSAGE_WRITE_TIMEOUT_VALUE)).expect("set_write_timeout call failed");
+                self.subscribe(&s);
 
-            self.socket = Some(s);
+                *self.socket.lock().unwrap() = Some(Arc::new(s));
+                self.monitor.lock().unwrap().on_connected(Instant::now());
 
-            return Ok(());
-        } else {
-            let err = bind_result.unwrap_err();
-            println!("Error during connection to xplane read socket : {:?}", err);
-            return Err(err);
+                Ok(())
+            },
+            Err(err) => {
+                println!("Error during connection to xplane read socket : {:?}", err);
+                self.monitor.lock().unwrap().on_link_error(Instant::now());
+
+                Err(err)
+            }
         }
     }
 
-}
+    /// Ask X-Plane to (re)start the UDP output of the data indexes read by the provider (DSEL message).
+    fn subscribe(&self, socket: &UdpSocket) {
 
-impl Provider for XPLN11Provider {
+        let mut vec: Vec<u8> = Vec::new();
+        vec.extend_from_slice("DSEL".as_bytes());
+        vec.push(0);
 
-    fn name(&self) -> &str {
+        for index in SUBSCRIBED_DATA_INDEXES {
+            vec.extend_from_slice(&index.to_le_bytes());
+        }
 
-        return NAME;
+        if let Err(e) = socket.send_to(&vec, self.write_url()) {
+            println!("Error during xplane data subscription : {:?}", e);
+        }
     }
 
-    fn init(&mut self) {
-        println!("XPLN11 Provider config : host: {}, read_port: {}, write_port: {}", self.host, self.read_port, self.write_port);
-        
-        self.connect_read_socket().expect("Connection to read socket error");
+    fn write_url(&self) -> String {
+
+        format!("{}:{}", self.host, self.write_port)
     }
 
-    fn shutdown(&self) {
-        println!("XPLN11 Provider shutdown");
+    fn socket(&self) -> Option<Arc<UdpSocket>> {
+
+        self.socket.lock().unwrap().clone()
     }
 
-    fn get_sensors(&self) -> Arc::<dyn SensorsProvider + Send + Sync> {
+    fn poll_reconnect(&self) {
 
-        let s: &UdpSocket = self.socket.as_ref().unwrap();
+        let reconnect_due: bool = self.monitor.lock().unwrap().reconnect_due(Instant::now());
 
-        let socket = s.try_clone().unwrap();
-        Arc::new(XMPL11SensorsProvider{ socket: socket })
+        if reconnect_due {
+            let _ = self.connect_read_socket();
+        }
     }
 
-    fn get_flcs(&self) -> Arc::<dyn FlightCtrlsProvider + Send + Sync> {
+    /// Receive thread : decode DATA messages into the latest sample cache until shutdown.
+    fn receive_loop(&self) {
 
-        let s: &UdpSocket = self.socket.as_ref().unwrap();
+        while self.running.load(Ordering::SeqCst) {
 
-        let socket = s.try_clone().unwrap();
-        Arc::new(XPLN11FlightCtrlsProvider{host: self.host.clone(), write_port: self.write_port, socket: socket})
-    }
-}
+            self.poll_reconnect();
 
-struct XMPL11SensorsProvider {
+            if let Ok(message_data) = self.receive() {
 
-    socket: UdpSocket,
-}
+                let mut cache: MutexGuard<XPLN11SampleCache> = self.cache.lock().unwrap();
+                apply_data_message(&mut cache.values, message_data, clock::now_ms());
+                cache.updated_at = Some(Instant::now());
+            }
+        }
+    }
 
-impl XMPL11SensorsProvider {
+    fn receive(&self) -> Result<XPLN11UDPDataMessage, Box<dyn std::error::Error>> {
 
-    fn get_data(&self) -> Result<XPLN11UDPDataMessage, Box<dyn std::error::Error>> {
+        let socket: Arc<UdpSocket> = match self.socket() {
+            Some(s) => s,
+            None => {
+                // Waiting for reconnect.
+                thread::sleep(DATA_MESSAGE_READ_TIMEOUT_VALUE);
+                return Err(Box::new(XPLN11Error("Link lost, waiting for reconnect".into())));
+            }
+        };
 
         let mut buf:[u8; DATA_MESSAGE_BUFFER_SIZE_VALUE] = [0; DATA_MESSAGE_BUFFER_SIZE_VALUE];
-        let socket: &UdpSocket = &self.socket;
         
         //let (number_of_bytes, _src) = socket.recv_from(&mut buf).unwrap();
         match socket.recv(&mut buf)  {
             Ok(number_of_bytes) => {
 
+                self.monitor.lock().unwrap().on_receive(Instant::now());
+
                 match decode_data(&number_of_bytes, buf) {
                     Ok(message) => {
                         return Ok(message)
                     },
-                    Err(e) => return Err(e)
+                    Err(e) => {
+                        println!("XPLN11 decode error : {:?}", e);
+                        return Err(e)
+                    }
+                }
+            },
+            Err(e) => {
+
+                let mut monitor: MutexGuard<LinkMonitor> = self.monitor.lock().unwrap();
+
+                match e.kind() {
+                    ErrorKind::WouldBlock | ErrorKind::TimedOut => monitor.update(Instant::now()),
+                    _ => monitor.on_link_error(Instant::now())
                 }
+
+                Err(Box::new(XPLN11Error(e.to_string())))
             },
-            Err(e) => Err(Box::new(XPLN11Error(e.to_string()))),
         }
     }
 }
 
+impl Provider for XPLN11Provider {
+
+    fn name(&self) -> &str {
+
+        return NAME;
+    }
+
+    fn init(&mut self) {
+        println!("XPLN11 Provider config : host: {}, read_port: {}, write_port: {}", self.link.host, self.link.read_port, self.link.write_port);
+        
+        if let Err(e) = self.link.connect_read_socket() {
+            println!("XPLN11 read socket not available, retrying in background : {:?}", e);
+        }
+
+        let link: Arc<XPLN11Link> = self.link.clone();
+        let receiver: JoinHandle<()> = thread::spawn(move || link.receive_loop());
+
+        *self.link.receiver.lock().unwrap() = Some(receiver);
+    }
+
+    fn shutdown(&self) {
+        println!("XPLN11 Provider shutdown");
+
+        self.link.running.store(false, Ordering::SeqCst);
+
+        if let Some(receiver) = self.link.receiver.lock().unwrap().take() {
+            let _ = receiver.join();
+        }
+    }
+
+    fn get_sensors(&self) -> Option<Arc::<dyn SensorsProvider + Send + Sync>> {
+
+        Some(Arc::new(XMPL11SensorsProvider{ link: self.link.clone() }))
+    }
+
+    fn get_flcs(&self) -> Option<Arc::<dyn FlightCtrlsProvider + Send + Sync>> {
+
+        Some(Arc::new(XPLN11FlightCtrlsProvider{ link: self.link.clone() }))
+    }
+}
+
+struct XMPL11SensorsProvider {
+
+    link: Arc<XPLN11Link>,
+}
+
 impl SensorsProvider for XMPL11SensorsProvider {
 
-    // TODO refact to return Result<SensorsValues, Error>
-    fn acquire(&self) -> Result<SensorsValues,Box<dyn std::error::Error>> {
+    /// Freshest sample from the receive thread cache, never blocks on the socket.
+    fn acquire(&self) -> Result<SensorsSample,Box<dyn std::error::Error>> {
         //println!("XPLN11 Provider acquire");
-        
-        let raw_data = self.get_data();
-
-        let mut result: SensorsValues = SensorsValues::new();
-
-        match raw_data {
-            Ok(message_data) => {
-
-                //println!("ENTRY DATA : {:?}", message_data);
-
-                for (key, value) in message_data.data {
-
-                    match FromPrimitive::from_i32(key) {
-                        Some(XPLN11DataReadEnum::Frame) => (),
-                        Some(XPLN11DataReadEnum::Speeds) => {
-                            result.ias = value.get_data_field(SpeedsEnum::Kias as isize);
-                        },
-                        Some(XPLN11DataReadEnum::MachVviGLoad) => {
-                            result.mach = value.get_data_field(MachVVIGloadEnum::Mach as isize);
-                            result.g_load = value.get_data_field(MachVVIGloadEnum::GloadNorm as isize);
-                        },
-                        Some(XPLN11DataReadEnum::JoystickYoke) => (),
-                        Some(XPLN11DataReadEnum::PitchRollHeadings) => {
-                            result.pitch = value.get_data_field(PitchRollHeadingsEnum::Pitch as isize);
-                            result.roll = value.get_data_field(PitchRollHeadingsEnum::Roll as isize);
-                            result.yaw = value.get_data_field(PitchRollHeadingsEnum::Heading as isize);
-                            result.heading = value.get_data_field(PitchRollHeadingsEnum::HeadingMag as isize);
-                        },
-                        Some(XPLN11DataReadEnum::AoA) => {
-                            result.aoa = value.get_data_field(AoAEnum::Alpha as isize);
-                        },
-                        Some(XPLN11DataReadEnum::MagCompass) => (),
-                        Some(XPLN11DataReadEnum::Gnss) => {
-                            result.alt_msl = value.get_data_field(GnssEnum::AltitudeFtMSL as isize);
-                            result.alt_agl = value.get_data_field(GnssEnum::AltitudeFtAGL as isize);
-                        },
-                        Some(XPLN11DataReadEnum::ThrottleCmd) => (),
-                        Some(XPLN11DataReadEnum::ThrottleActual) => (),
-                        Some(XPLN11DataReadEnum::N1) => (),
-                        Some(XPLN11DataReadEnum::N2) => (),
-                        Some(XPLN11DataReadEnum::ClimbStats) => {
-                            result.vs = value.get_data_field(ClimbStatsEnum::VSpd as isize);
-                        }
-                        _ => println!("XPLN11 ACQUIRE DATA NOT MATCH ENUM : {}", key)
-                    }
-                }
 
-                return Ok(result);
+        let cache: MutexGuard<XPLN11SampleCache> = self.link.cache.lock().unwrap();
 
-            },
-            Err(e) => return Err(e)
+        match cache.updated_at {
+            Some(updated_at) => Ok(SensorsSample{ values: cache.values.clone(), age: updated_at.elapsed() }),
+            None => Err(Box::new(XPLN11Error("No data received".into())))
         }
+    }
 
-        //println!("MAPPED DATA : {:?}", result);
+    fn link_state(&self) -> LinkState {
 
+        self.link.monitor.lock().unwrap().state()
+    }
+}
+
+/// Data field value, None when X-Plane sends no data for the field.
+fn get_optional_data_field(fragment: &XPLN11UDPDataFragment, index: isize) -> Option<f32> {
+
+    let value: f32 = fragment.get_data_field(index);
+
+    if value == DATA_MESSAGE_NO_DATA { None } else { Some(value) }
+}
+
+/// Data field value received at `timestamp_ms`, the field reception time is cleared when X-Plane sends no data.
+fn receive_data_field(result: &mut SensorsValues, field: SensorField, fragment: &XPLN11UDPDataFragment, index: isize, timestamp_ms: u64) -> Option<f32> {
+
+    let value: Option<f32> = get_optional_data_field(fragment, index);
+
+    result.mark_received(field, value.map(|_| timestamp_ms));
+
+    value
+}
+
+/// Merge the received data indexes into the sample, fields of not received indexes are kept.
+/// Fields without data keep their last value, their reception time tells the ADC they are not valid.
+fn apply_data_message(result: &mut SensorsValues, message_data: XPLN11UDPDataMessage, timestamp_ms: u64) {
+
+    //println!("ENTRY DATA : {:?}", message_data);
+
+    for (key, value) in message_data.data {
+
+        match FromPrimitive::from_i32(key) {
+            Some(XPLN11DataReadEnum::Frame) => (),
+            Some(XPLN11DataReadEnum::Speeds) => {
+                if let Some(ias) = receive_data_field(result, SensorField::Ias, &value, SpeedsEnum::Kias as isize, timestamp_ms) {
+                    result.ias = ias;
+                }
+                result.ground_speed = get_optional_data_field(&value, SpeedsEnum::Ktgs as isize);
+            },
+            Some(XPLN11DataReadEnum::MachVviGLoad) => {
+                if let Some(mach) = receive_data_field(result, SensorField::Mach, &value, MachVVIGloadEnum::Mach as isize, timestamp_ms) {
+                    result.mach = mach;
+                }
+                if let Some(g_load) = receive_data_field(result, SensorField::GLoad, &value, MachVVIGloadEnum::GloadNorm as isize, timestamp_ms) {
+                    result.g_load = g_load;
+                }
+                // X-Plane axial load is positive aft.
+                result.body_accels = match (get_optional_data_field(&value, MachVVIGloadEnum::GloadAxial as isize),
+                                            get_optional_data_field(&value, MachVVIGloadEnum::GloadSide as isize),
+                                            get_optional_data_field(&value, MachVVIGloadEnum::GloadNorm as isize)) {
+                    (Some(axial), Some(side), Some(normal)) => Some(BodyAccels{ axial: -axial, side, normal }),
+                    _ => None
+                };
+            },
+            Some(XPLN11DataReadEnum::AtmosphereAircraft) => {
+                result.static_pressure = get_optional_data_field(&value, AtmosphereAircraftEnum::AmbientPressureInHg as isize)
+                            .map(|p| p * DATA_INHG_TO_HPA);
+                result.oat = get_optional_data_field(&value, AtmosphereAircraftEnum::AmbientTemperatureC as isize);
+            },
+            Some(XPLN11DataReadEnum::JoystickYoke) => (),
+            Some(XPLN11DataReadEnum::AngularVelocities) => {
+                result.body_rates = match (get_optional_data_field(&value, AngularVelocitiesEnum::PRadS as isize),
+                                           get_optional_data_field(&value, AngularVelocitiesEnum::QRadS as isize),
+                                           get_optional_data_field(&value, AngularVelocitiesEnum::RRadS as isize)) {
+                    (Some(p), Some(q), Some(r)) => Some(BodyRates{ p: p.to_degrees(), q: q.to_degrees(), r: r.to_degrees() }),
+                    _ => None
+                };
+            },
+            Some(XPLN11DataReadEnum::PitchRollHeadings) => {
+                if let Some(pitch) = receive_data_field(result, SensorField::Pitch, &value, PitchRollHeadingsEnum::Pitch as isize, timestamp_ms) {
+                    result.pitch = pitch;
+                }
+                if let Some(roll) = receive_data_field(result, SensorField::Roll, &value, PitchRollHeadingsEnum::Roll as isize, timestamp_ms) {
+                    result.roll = roll;
+                }
+                if let Some(yaw) = receive_data_field(result, SensorField::Yaw, &value, PitchRollHeadingsEnum::Heading as isize, timestamp_ms) {
+                    result.yaw = yaw;
+                }
+                if let Some(heading) = receive_data_field(result, SensorField::Heading, &value, PitchRollHeadingsEnum::HeadingMag as isize, timestamp_ms) {
+                    result.heading = heading;
+                }
+            },
+            Some(XPLN11DataReadEnum::AoA) => {
+                if let Some(aoa) = receive_data_field(result, SensorField::Aoa, &value, AoAEnum::Alpha as isize, timestamp_ms) {
+                    result.aoa = aoa;
+                }
+                result.track = get_optional_data_field(&value, AoAEnum::Hpath as isize);
+            },
+            Some(XPLN11DataReadEnum::MagCompass) => (),
+            Some(XPLN11DataReadEnum::Gnss) => {
+                if let Some(alt_msl) = receive_data_field(result, SensorField::AltMsl, &value, GnssEnum::AltitudeFtMSL as isize, timestamp_ms) {
+                    result.alt_msl = alt_msl;
+                }
+                if let Some(alt_agl) = receive_data_field(result, SensorField::AltAgl, &value, GnssEnum::AltitudeFtAGL as isize, timestamp_ms) {
+                    result.alt_agl = alt_agl;
+                }
+                result.position = match (get_optional_data_field(&value, GnssEnum::LatitudeDeg as isize),
+                                         get_optional_data_field(&value, GnssEnum::LongitudeDeg as isize)) {
+                    (Some(lat), Some(lon)) => Some(GeoPosition{ lat: lat as f64, lon: lon as f64 }),
+                    _ => None
+                };
+            },
+            Some(XPLN11DataReadEnum::ThrottleCmd) => (),
+            Some(XPLN11DataReadEnum::ThrottleActual) => (),
+            Some(XPLN11DataReadEnum::N1) => (),
+            Some(XPLN11DataReadEnum::N2) => (),
+            Some(XPLN11DataReadEnum::ClimbStats) => {
+                if let Some(vs) = receive_data_field(result, SensorField::Vs, &value, ClimbStatsEnum::VSpd as isize, timestamp_ms) {
+                    result.vs = vs;
+                }
+            }
+            _ => println!("XPLN11 ACQUIRE DATA NOT MATCH ENUM : {}", key)
+        }
     }
+
+    //println!("MAPPED DATA : {:?}", result);
 }
 
 struct  XPLN11FlightCtrlsProvider {
 
-    host: String,
-    write_port: i64,
-    socket: UdpSocket
+    link: Arc<XPLN11Link>,
 }
 
 
 impl FlightCtrlsProvider for XPLN11FlightCtrlsProvider {
 
-    fn send(&self) {
+    fn send(&self, cmd: &FlightCtrlsCommand) {
 
-        let url = format!("{}:{}", self.host, self.write_port);
-        let socket: &UdpSocket = &self.socket;
-        //socket.connect(url).expect("Error connection to write socket");
-
-        //while (true) {
-            
         /* 
         let prologue: [u8; 4] = "DATA".as_bytes().try_into().unwrap();
         let space: [u8; 1] = 0u8.to_le_bytes(); //.as_bytes().try_into().unwrap();
@@ -282,9 +463,48 @@ impl FlightCtrlsProvider for XPLN11FlightCtrlsProvider {
         vec.extend_from_slice(&data8);
         */
 
+        if let Some(pitch) = cmd.pitch {
+            self.send_dref("sim/joystick/yoke_pitch_ratio", pitch);
+        }
+
+        if let Some(roll) = cmd.roll {
+            self.send_dref("sim/joystick/yoke_roll_ratio", roll);
+        }
+
+        if let Some(yaw) = cmd.yaw {
+            self.send_dref("sim/joystick/yoke_heading_ratio", yaw);
+        }
+
+        if let Some(throttle) = cmd.throttle {
+            self.send_dref("sim/cockpit2/engine/actuators/throttle_ratio_all", throttle);
+        }
+    }
+
+    fn release(&self) {
+
+        self.send_dref("sim/operation/override/override_joystick", 0f32);
+        self.send_dref("sim/joystick/yoke_pitch_ratio", 0f32);
+        self.send_dref("sim/joystick/yoke_roll_ratio", 0f32);
+        self.send_dref("sim/joystick/yoke_heading_ratio", 0f32);
+    }
+}
+
+impl XPLN11FlightCtrlsProvider {
+
+    fn send_dref(&self, dref_name: &str, dref_value: f32) {
+
+        let url = self.link.write_url();
+        let socket: Arc<UdpSocket> = match self.link.socket() {
+            Some(s) => s,
+            None => {
+                println!("XPLN11 link lost, flight controls not sent");
+                return;
+            }
+        };
+
         let prologue: &[u8] = "DREF0".as_bytes();
-        let value: [u8; 4] = 1f32.to_le_bytes();
-        let dref: &[u8] = "sim/joystick/yoke_pitch_ratio".as_bytes();
+        let value: [u8; 4] = dref_value.to_le_bytes();
+        let dref: &[u8] = dref_name.as_bytes();
 
         let mut vec: Vec<u8> = Vec::with_capacity(509);
         vec.extend_from_slice(&prologue);
@@ -305,15 +525,9 @@ impl FlightCtrlsProvider for XPLN11FlightCtrlsProvider {
 
         let message: &[u8] = &(vec)[..];
 
-        println!("message len : {}", message.len());
-
-        //while(true) {
-        socket.send_to(&message, url).expect("Message send error");
-
-
-        println!("Message sent !{:?}", message);
-        //    thread::sleep(Duration::from_millis(100));
-        //}
+        if let Err(e) = socket.send_to(&message, url) {
+            println!("Message send error : {:?}", e);
+        }
     }
 }
 
@@ -321,16 +535,23 @@ pub fn xpl11_provider_init(config: &Yaml) -> Box<dyn Provider> {
 
     println!("Start init xpl11_provider module");
 
-    let host = config["provider"]["host"].as_str().unwrap().to_string();
-    let read_port = config["provider"]["read_port"].as_i64().unwrap();
-    let write_port = config["provider"]["write_port"].as_i64().unwrap();
+    let host = config["host"].as_str().unwrap().to_string();
+    let read_port = config["read_port"].as_i64().unwrap();
+    let write_port = config["write_port"].as_i64().unwrap();
+    let link_config: LinkConfig = LinkConfig::from(&config["link"]);
 
-    let provider = Box::new(XPLN11Provider{ 
+    let link: XPLN11Link = XPLN11Link{ 
         host, 
         read_port, 
         write_port,
-        socket: None,
-    });
+        socket: Mutex::new(None),
+        monitor: Mutex::new(LinkMonitor::new(link_config)),
+        cache: Mutex::new(XPLN11SampleCache{ values: SensorsValues::new(), updated_at: None }),
+        running: AtomicBool::new(true),
+        receiver: Mutex::new(None),
+    };
+
+    let provider = Box::new(XPLN11Provider{ link: Arc::new(link) });
 
     println!("End init xpl11_provider module");
 
@@ -474,3 +695,243 @@ fn load_test_data() {
     assert_eq!(1f32, decoded_frag.data8);
 
 }
+
+#[cfg(test)]
+fn build_test_provider(write_port: u16, link_config: LinkConfig) -> XPLN11Provider {
+
+    let link: XPLN11Link = XPLN11Link{ 
+        host: String::from("127.0.0.1"), 
+        read_port: 0, 
+        write_port: write_port as i64,
+        socket: Mutex::new(None),
+        monitor: Mutex::new(LinkMonitor::new(link_config)),
+        cache: Mutex::new(XPLN11SampleCache{ values: SensorsValues::new(), updated_at: None }),
+        running: AtomicBool::new(true),
+        receiver: Mutex::new(None),
+    };
+
+    XPLN11Provider{ link: Arc::new(link) }
+}
+
+#[cfg(test)]
+fn test_link_config(reconnect_backoff_ms: u64) -> LinkConfig {
+
+    LinkConfig {
+        stale_timeout: Duration::from_millis(150),
+        lost_timeout: Duration::from_millis(400),
+        reconnect_backoff: Duration::from_millis(reconnect_backoff_ms),
+        reconnect_backoff_max: Duration::from_millis(reconnect_backoff_ms),
+    }
+}
+
+#[cfg(test)]
+fn acquire_until<P>(sensors: &Arc<dyn SensorsProvider + Send + Sync>, predicate: P) -> Option<SensorsValues>
+    where P: Fn(&SensorsValues) -> bool {
+
+    let deadline: Instant = Instant::now() + Duration::from_secs(3);
+
+    while Instant::now() < deadline {
+
+        if let Ok(sample) = sensors.acquire() {
+            if predicate(&sample.values) {
+                return Some(sample.values);
+            }
+        }
+
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    None
+}
+
+#[cfg(test)]
+fn wait_for_link_state(sensors: &Arc<dyn SensorsProvider + Send + Sync>, link_state: LinkState) -> bool {
+
+    let deadline: Instant = Instant::now() + Duration::from_secs(3);
+
+    while Instant::now() < deadline {
+
+        if sensors.link_state() == link_state {
+            return true;
+        }
+
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    false
+}
+
+#[test]
+fn xpln11_stub_acquire_end_to_end() {
+
+    use xpln11_stub::{XPLN11Stub, XPLN11StubWrite};
+
+    let stub: XPLN11Stub = XPLN11Stub::start("127.0.0.1:9".parse().unwrap(), 50.0);
+    let mut provider: XPLN11Provider = build_test_provider(stub.port(), test_link_config(10_000));
+    provider.init();
+
+    // Read socket bound and data indexes subscribed.
+    let provider_addr = provider.link.socket().unwrap().local_addr().unwrap();
+    let dsel = stub.wait_for_write(|w| matches!(w, XPLN11StubWrite::Dsel(_)), Duration::from_secs(1));
+    assert_eq!(Some(XPLN11StubWrite::Dsel(SUBSCRIBED_DATA_INDEXES.to_vec())), dsel);
+
+    let sensors: Arc<dyn SensorsProvider + Send + Sync> = provider.get_sensors().unwrap();
+    assert_eq!(LinkState::Connecting, sensors.link_state());
+
+    stub.set_data(XPLN11DataReadEnum::Speeds as i32, [250.0, 245.0, 280.0, 300.0, -999.0, 287.0, 322.0, 345.0]);
+    stub.set_data(XPLN11DataReadEnum::MachVviGLoad as i32, [0.45, -999.0, 500.0, -999.0, 1.1, 0.0, 0.0, -999.0]);
+    stub.set_data(XPLN11DataReadEnum::PitchRollHeadings as i32, [5.0, -10.0, 182.0, 180.0, -999.0, -999.0, -999.0, -999.0]);
+    stub.set_data(XPLN11DataReadEnum::AngularVelocities as i32, [0.5, -0.25, 0.0, -999.0, -999.0, -999.0, -999.0, -999.0]);
+    stub.set_data(XPLN11DataReadEnum::AoA as i32, [3.5, 0.1, 181.0, 1.0, -999.0, -999.0, -999.0, 0.2]);
+    stub.set_data(XPLN11DataReadEnum::Gnss as i32, [45.0, 5.0, 10_000.0, 9_500.0, 0.0, 10_010.0, 45.0, 5.0]);
+    stub.set_data(XPLN11DataReadEnum::ClimbStats as i32, [300.0, 500.0, -999.0, 1.0, -999.0, -999.0, -999.0, -999.0]);
+    stub.set_target(provider_addr);
+
+    let values: SensorsValues = acquire_until(&sensors, |v| v.ias == 250.0 && v.vs == 500.0).unwrap();
+
+    assert_eq!(LinkState::Receiving, sensors.link_state());
+    assert_eq!(0.45, values.mach);
+    assert_eq!(1.1, values.g_load);
+    assert_eq!(5.0, values.pitch);
+    assert_eq!(-10.0, values.roll);
+    assert_eq!(180.0, values.heading);
+    assert_eq!(3.5, values.aoa);
+    assert_eq!(10_000.0, values.alt_msl);
+    assert_eq!(9_500.0, values.alt_agl);
+    assert_eq!(Some(300.0), values.ground_speed);
+    assert_eq!(Some(181.0), values.track);
+    assert_eq!(Some(GeoPosition{ lat: 45.0, lon: 5.0 }), values.position);
+    assert_eq!(Some(BodyRates{ p: -0.25_f32.to_degrees(), q: 0.5_f32.to_degrees(), r: 0.0 }), values.body_rates);
+    assert_eq!(Some(BodyAccels{ axial: -0.0, side: 0.0, normal: 1.1 }), values.body_accels);
+    assert!(values.received_at(SensorField::Ias).is_some());
+
+    // No data (-999) for the AoA : last value kept, field not received.
+    stub.set_data(XPLN11DataReadEnum::AoA as i32, [-999.0, 0.1, 181.0, 1.0, -999.0, -999.0, -999.0, 0.2]);
+
+    let values: SensorsValues = acquire_until(&sensors, |v| v.received_at(SensorField::Aoa).is_none()).unwrap();
+
+    assert_eq!(3.5, values.aoa);
+    assert!(values.received_at(SensorField::Ias).is_some());
+
+    provider.shutdown();
+}
+
+#[test]
+fn xpln11_stub_flight_controls_writes() {
+
+    use xpln11_stub::{XPLN11Stub, XPLN11StubWrite};
+
+    let stub: XPLN11Stub = XPLN11Stub::start("127.0.0.1:9".parse().unwrap(), 50.0);
+    let mut provider: XPLN11Provider = build_test_provider(stub.port(), test_link_config(10_000));
+    provider.init();
+
+    let flcs: Arc<dyn FlightCtrlsProvider + Send + Sync> = provider.get_flcs().unwrap();
+
+    let mut cmd: FlightCtrlsCommand = FlightCtrlsCommand::new();
+    cmd.pitch = Some(0.25);
+    flcs.send(&cmd);
+    let pitch = stub.wait_for_write(|w| matches!(w, XPLN11StubWrite::Dref{ .. }), Duration::from_secs(1));
+    assert_eq!(Some(XPLN11StubWrite::Dref{ name: String::from("sim/joystick/yoke_pitch_ratio"), value: 0.25 }), pitch);
+
+    flcs.release();
+    let release = stub.wait_for_write(
+        |w| matches!(w, XPLN11StubWrite::Dref{ name, .. } if name == "sim/operation/override/override_joystick"), 
+        Duration::from_secs(1));
+    assert_eq!(Some(XPLN11StubWrite::Dref{ name: String::from("sim/operation/override/override_joystick"), value: 0.0 }), release);
+
+    provider.shutdown();
+}
+
+#[test]
+fn xpln11_stub_link_timeouts() {
+
+    use xpln11_stub::XPLN11Stub;
+
+    let stub: XPLN11Stub = XPLN11Stub::start("127.0.0.1:9".parse().unwrap(), 50.0);
+    let mut provider: XPLN11Provider = build_test_provider(stub.port(), test_link_config(10_000));
+    provider.init();
+
+    stub.set_data(XPLN11DataReadEnum::Speeds as i32, [250.0, 245.0, 280.0, 300.0, -999.0, 287.0, 322.0, 345.0]);
+    stub.set_target(provider.link.socket().unwrap().local_addr().unwrap());
+
+    let sensors: Arc<dyn SensorsProvider + Send + Sync> = provider.get_sensors().unwrap();
+    assert!(wait_for_link_state(&sensors, LinkState::Receiving));
+
+    // Sim stops sending : stale then lost.
+    stub.set_sending(false);
+    let t0: Instant = Instant::now();
+    assert!(wait_for_link_state(&sensors, LinkState::Stale));
+    assert!(wait_for_link_state(&sensors, LinkState::Lost));
+    assert!(t0.elapsed() >= Duration::from_millis(300));
+
+    // Sim sends again before the reconnect backoff : receiving on the same socket.
+    stub.set_sending(true);
+    assert!(wait_for_link_state(&sensors, LinkState::Receiving));
+
+    provider.shutdown();
+}
+
+#[test]
+fn xpln11_stub_link_reconnect() {
+
+    use xpln11_stub::{XPLN11Stub, XPLN11StubWrite};
+
+    let stub: XPLN11Stub = XPLN11Stub::start("127.0.0.1:9".parse().unwrap(), 50.0);
+    let mut provider: XPLN11Provider = build_test_provider(stub.port(), test_link_config(50));
+    provider.init();
+
+    stub.set_data(XPLN11DataReadEnum::Speeds as i32, [250.0, 245.0, 280.0, 300.0, -999.0, 287.0, 322.0, 345.0]);
+    stub.set_target(provider.link.socket().unwrap().local_addr().unwrap());
+
+    let sensors: Arc<dyn SensorsProvider + Send + Sync> = provider.get_sensors().unwrap();
+    assert!(wait_for_link_state(&sensors, LinkState::Receiving));
+
+    stub.set_sending(false);
+    assert!(wait_for_link_state(&sensors, LinkState::Lost));
+
+    // Socket rebound and data indexes subscribed again.
+    assert!(wait_for_link_state(&sensors, LinkState::Connecting));
+    let dsel_count = || stub.writes().iter().filter(|w| matches!(w, XPLN11StubWrite::Dsel(_))).count();
+    let deadline: Instant = Instant::now() + Duration::from_secs(3);
+    while dsel_count() < 2 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(2, dsel_count());
+
+    stub.set_target(provider.link.socket().unwrap().local_addr().unwrap());
+    stub.set_sending(true);
+    assert!(wait_for_link_state(&sensors, LinkState::Receiving));
+
+    provider.shutdown();
+}
+
+#[test]
+fn xpln11_stub_latest_sample_cache() {
+
+    use xpln11_stub::XPLN11Stub;
+
+    let stub: XPLN11Stub = XPLN11Stub::start("127.0.0.1:9".parse().unwrap(), 50.0);
+    let mut provider: XPLN11Provider = build_test_provider(stub.port(), test_link_config(10_000));
+    provider.init();
+
+    let sensors: Arc<dyn SensorsProvider + Send + Sync> = provider.get_sensors().unwrap();
+    assert!(sensors.acquire().is_err());
+
+    stub.set_data(XPLN11DataReadEnum::Speeds as i32, [250.0, 245.0, 280.0, 300.0, -999.0, 287.0, 322.0, 345.0]);
+    stub.set_target(provider.link.socket().unwrap().local_addr().unwrap());
+    assert!(acquire_until(&sensors, |v| v.ias == 250.0).is_some());
+
+    // Freshest sample returned immediately, the age grows when the sim stops sending.
+    stub.set_data(XPLN11DataReadEnum::Speeds as i32, [260.0, 245.0, 280.0, 300.0, -999.0, 287.0, 322.0, 345.0]);
+    assert!(acquire_until(&sensors, |v| v.ias == 260.0).is_some());
+    stub.set_sending(false);
+    thread::sleep(Duration::from_millis(100));
+
+    let t0: Instant = Instant::now();
+    let sample: SensorsSample = sensors.acquire().unwrap();
+    assert!(t0.elapsed() < Duration::from_millis(5));
+    assert_eq!(260.0, sample.values.ias);
+    assert!(sample.age >= Duration::from_millis(60));
+
+    provider.shutdown();
+}
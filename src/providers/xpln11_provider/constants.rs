@@ -5,7 +5,9 @@ pub enum XPLN11DataReadEnum {
     Frame = 0,
     Speeds = 3,
     MachVviGLoad = 4,
+    AtmosphereAircraft = 6,
     JoystickYoke = 8,
+    AngularVelocities = 16,
     PitchRollHeadings = 17,
     AoA = 18,
     MagCompass = 19,
@@ -47,6 +49,17 @@ pub enum MachVVIGloadEnum {
     GloadSide = 6,
 }
 
+#[derive(FromPrimitive)]
+pub enum AtmosphereAircraftEnum {
+    AmbientPressureInHg = 0,
+    AmbientTemperatureC = 1,
+    LeadingEdgeTemperatureC = 2,
+    DensityRatio = 3,
+    SpeedOfSoundKt = 4,
+    DynamicPressurePsf = 5,
+    Gravity = 7
+}
+
 #[derive(FromPrimitive)]
 pub enum JoystickYokeEnum {
     Elevator = 0,
@@ -54,6 +67,13 @@ pub enum JoystickYokeEnum {
     Rudder = 2
 }
 
+#[derive(FromPrimitive)]
+pub enum AngularVelocitiesEnum {
+    QRadS = 0,
+    PRadS = 1,
+    RRadS = 2
+}
+
 #[derive(FromPrimitive)]
 pub enum PitchRollHeadingsEnum {
     Pitch = 0,
@@ -0,0 +1,213 @@
+/**
+ * X-Plane 11 stand-in for integration tests
+ * Imitate X-Plane 11 over loopback UDP :
+ * - emit DATA messages with configurable values at a configurable rate to the provider read socket
+ * - accept DREF and DSEL messages on its own socket (the provider write port) and record them for assertions
+ */
+use std::collections::HashMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const STUB_READ_TIMEOUT_VALUE: Duration = Duration::from_millis(20);
+const STUB_BUFFER_SIZE_VALUE: usize = 1024;
+
+/// Message written by the provider to the sim.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XPLN11StubWrite {
+    Dref{ name: String, value: f32 },
+    Dsel(Vec<i32>),
+    Unknown(Vec<u8>)
+}
+
+pub struct XPLN11Stub {
+
+    socket: Arc<UdpSocket>,
+    target: Arc<Mutex<SocketAddr>>,
+    data: Arc<Mutex<HashMap<i32, [f32; 8]>>>,
+    writes: Arc<Mutex<Vec<XPLN11StubWrite>>>,
+    sending: Arc<AtomicBool>,
+    running: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl XPLN11Stub {
+
+    /// Bind the stub on a loopback ephemeral port and start sending DATA messages to `target` at `rate_hz`.
+    pub fn start(target: SocketAddr, rate_hz: f32) -> XPLN11Stub {
+
+        let socket: UdpSocket = UdpSocket::bind("127.0.0.1:0").expect("Stub bind error");
+        socket.set_read_timeout(Some(STUB_READ_TIMEOUT_VALUE)).expect("set_read_timeout call failed");
+
+        let mut stub: XPLN11Stub = XPLN11Stub {
+            socket: Arc::new(socket),
+            target: Arc::new(Mutex::new(target)),
+            data: Arc::new(Mutex::new(HashMap::new())),
+            writes: Arc::new(Mutex::new(Vec::new())),
+            sending: Arc::new(AtomicBool::new(true)),
+            running: Arc::new(AtomicBool::new(true)),
+            handles: Vec::new(),
+        };
+
+        stub.spawn_sender(Duration::from_secs_f32(1.0 / rate_hz));
+        stub.spawn_receiver();
+
+        stub
+    }
+
+    /// Port to configure as the provider `write_port`.
+    pub fn port(&self) -> u16 {
+
+        self.socket.local_addr().unwrap().port()
+    }
+
+    pub fn set_target(&self, target: SocketAddr) {
+
+        *self.target.lock().unwrap() = target;
+    }
+
+    /// Set the 8 data fields sent for a data index.
+    pub fn set_data(&self, index: i32, values: [f32; 8]) {
+
+        self.data.lock().unwrap().insert(index, values);
+    }
+
+    /// Pause or resume the DATA messages (sim paused, network cut, etc...).
+    pub fn set_sending(&self, sending: bool) {
+
+        self.sending.store(sending, Ordering::SeqCst);
+    }
+
+    pub fn writes(&self) -> Vec<XPLN11StubWrite> {
+
+        self.writes.lock().unwrap().clone()
+    }
+
+    /// Wait until a recorded write matches the predicate.
+    pub fn wait_for_write<P>(&self, predicate: P, timeout: Duration) -> Option<XPLN11StubWrite>
+        where P: Fn(&XPLN11StubWrite) -> bool {
+
+        let deadline: Instant = Instant::now() + timeout;
+
+        while Instant::now() < deadline {
+
+            if let Some(write) = self.writes().into_iter().find(|w| predicate(w)) {
+                return Some(write);
+            }
+
+            thread::sleep(STUB_READ_TIMEOUT_VALUE);
+        }
+
+        None
+    }
+
+    pub fn stop(&mut self) {
+
+        self.running.store(false, Ordering::SeqCst);
+
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+
+    fn spawn_sender(&mut self, period: Duration) {
+
+        let socket: Arc<UdpSocket> = self.socket.clone();
+        let target: Arc<Mutex<SocketAddr>> = self.target.clone();
+        let data: Arc<Mutex<HashMap<i32, [f32; 8]>>> = self.data.clone();
+        let sending: Arc<AtomicBool> = self.sending.clone();
+        let running: Arc<AtomicBool> = self.running.clone();
+
+        self.handles.push(thread::spawn(move || {
+
+            while running.load(Ordering::SeqCst) {
+
+                if sending.load(Ordering::SeqCst) {
+
+                    let message: Vec<u8> = encode_data(&data.lock().unwrap());
+                    let addr: SocketAddr = *target.lock().unwrap();
+                    let _ = socket.send_to(&message, addr);
+                }
+
+                thread::sleep(period);
+            }
+        }));
+    }
+
+    fn spawn_receiver(&mut self) {
+
+        let socket: Arc<UdpSocket> = self.socket.clone();
+        let writes: Arc<Mutex<Vec<XPLN11StubWrite>>> = self.writes.clone();
+        let running: Arc<AtomicBool> = self.running.clone();
+
+        self.handles.push(thread::spawn(move || {
+
+            let mut buf: [u8; STUB_BUFFER_SIZE_VALUE] = [0; STUB_BUFFER_SIZE_VALUE];
+
+            while running.load(Ordering::SeqCst) {
+
+                if let Ok(number_of_bytes) = socket.recv(&mut buf) {
+                    writes.lock().unwrap().push(decode_write(&buf[..number_of_bytes]));
+                }
+            }
+        }));
+    }
+}
+
+impl Drop for XPLN11Stub {
+
+    fn drop(&mut self) {
+
+        self.stop();
+    }
+}
+
+/// Encode a DATA message : "DATA" + 1 byte + (index i32 + 8 x f32) per data index, little endian.
+pub fn encode_data(data: &HashMap<i32, [f32; 8]>) -> Vec<u8> {
+
+    let mut vec: Vec<u8> = Vec::new();
+    vec.extend_from_slice("DATA".as_bytes());
+    vec.push(b'*');
+
+    let mut indexes: Vec<&i32> = data.keys().collect();
+    indexes.sort();
+
+    for index in indexes {
+
+        vec.extend_from_slice(&index.to_le_bytes());
+
+        for value in data[index].iter() {
+            vec.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    vec
+}
+
+/// Decode a DREF ("DREF" + 1 byte + f32 value + null padded dataref name) or DSEL ("DSEL" + 1 byte + i32 indexes) message.
+pub fn decode_write(message: &[u8]) -> XPLN11StubWrite {
+
+    if message.len() >= 9 && message.starts_with("DREF".as_bytes()) {
+
+        let value: f32 = f32::from_le_bytes([message[5], message[6], message[7], message[8]]);
+        let name: String = message[9..].iter()
+                    .take_while(|b| **b != 0)
+                    .map(|b| *b as char)
+                    .collect();
+
+        return XPLN11StubWrite::Dref{ name, value };
+    }
+
+    if message.len() >= 5 && message.starts_with("DSEL".as_bytes()) {
+
+        let indexes: Vec<i32> = message[5..].chunks_exact(4)
+                    .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect();
+
+        return XPLN11StubWrite::Dsel(indexes);
+    }
+
+    XPLN11StubWrite::Unknown(message.to_vec())
+}
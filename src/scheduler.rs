@@ -0,0 +1,422 @@
+pub mod scheduler {
+
+    extern crate yaml_rust;
+
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use yaml_rust::Yaml;
+
+    use crate::bus::{BusMessage, Publisher, TaskStatsMessage};
+    use crate::config::get_positive_f32;
+    use crate::lifecycle::Lifecycle;
+
+    /// Statistics published every period.
+    const STATS_PERIOD: Duration = Duration::from_secs(1);
+
+    /**
+     * Task handling events (bus messages) as soon as they are received between its fixed rate cycles.
+     */
+    pub trait EventDrivenTask {
+
+        /// Block on the events until the deadline, each event is handled when received.
+        fn handle_events_until(&mut self, deadline: Instant);
+
+        /// Fixed rate cycle, `dt` is the time (s) since the previous cycle.
+        fn step(&mut self, dt: f32);
+    }
+
+    /**
+     * Fixed rate task scheduler
+     * Run a task cycle at a fixed frequency against absolute deadlines (start + n x period, no drift),
+     * measure for each cycle :
+     * - jitter : delay between the scheduled start and the actual start
+     * - execution time
+     * - overrun : cycle ending after the next deadline, the missed deadlines are skipped
+     *
+     * The statistics are published on the bus (TaskStats) every second.
+     */
+    pub struct RateScheduler {
+        name: &'static str,
+        frequency_hz: f32,
+        period: Duration,
+        next_deadline: Option<Instant>,
+        cycle_start: Option<Instant>,
+        last_cycle_start: Option<Instant>,
+        overruns: u64,
+        window: StatsWindow,
+    }
+
+    /// Statistics of the current publication period.
+    struct StatsWindow {
+        start: Option<Instant>,
+        cycles: u32,
+        exec_time_total: Duration,
+        exec_time_max: Duration,
+        jitter_total: Duration,
+        jitter_max: Duration,
+        dt_min: f32,
+        dt_max: f32,
+    }
+
+    impl StatsWindow {
+
+        const fn new() -> StatsWindow {
+
+            StatsWindow {
+                start: None,
+                cycles: 0,
+                exec_time_total: Duration::ZERO,
+                exec_time_max: Duration::ZERO,
+                jitter_total: Duration::ZERO,
+                jitter_max: Duration::ZERO,
+                dt_min: f32::MAX,
+                dt_max: 0.0,
+            }
+        }
+    }
+
+    impl RateScheduler {
+
+        pub fn new(name: &'static str, frequency_hz: f32) -> RateScheduler {
+
+            if frequency_hz.is_nan() || frequency_hz <= 0.0 {
+                panic!("Task {} : invalid frequency {} Hz", name, frequency_hz);
+            }
+
+            println!("Task {} : {} Hz", name, frequency_hz);
+
+            RateScheduler {
+                name,
+                frequency_hz,
+                period: Duration::from_secs_f64(1.0 / frequency_hz as f64),
+                next_deadline: None,
+                cycle_start: None,
+                last_cycle_start: None,
+                overruns: 0,
+                window: StatsWindow::new(),
+            }
+        }
+
+        /// Scheduled start of the next cycle (now before the first cycle).
+        pub fn next_deadline(&self) -> Instant {
+
+            self.next_deadline.unwrap_or_else(Instant::now)
+        }
+
+        /**
+         * Wait for the next deadline and start a cycle.
+         * Return the time (s) elapsed since the previous cycle start (0 on the first cycle).
+         */
+        pub fn wait_next_cycle(&mut self) -> f32 {
+
+            self.wait_next_cycle_with(|deadline| {
+                let now: Instant = Instant::now();
+
+                if deadline > now {
+                    thread::sleep(deadline - now);
+                }
+
+                Instant::now()
+            })
+        }
+
+        /// Wait for the next deadline with `wait` (blocking until the deadline, returning the wake up time) and start a cycle.
+        pub(crate) fn wait_next_cycle_with<W: FnMut(Instant) -> Instant>(&mut self, mut wait: W) -> f32 {
+
+            let deadline: Instant = self.next_deadline();
+
+            let now: Instant = wait(deadline);
+
+            self.start_cycle(deadline, now)
+        }
+
+        /// Start a cycle scheduled at `deadline`, actually started at `now`.
+        pub(crate) fn start_cycle(&mut self, deadline: Instant, now: Instant) -> f32 {
+
+            let jitter: Duration = now.saturating_duration_since(deadline);
+
+            let dt: f32 = match self.last_cycle_start {
+                Some(last_cycle_start) => now.duration_since(last_cycle_start).as_secs_f32(),
+                None => 0.0
+            };
+
+            self.window.start.get_or_insert(now);
+            self.window.jitter_total += jitter;
+            self.window.jitter_max = self.window.jitter_max.max(jitter);
+            if self.last_cycle_start.is_some() {
+                self.window.dt_min = self.window.dt_min.min(dt);
+                self.window.dt_max = self.window.dt_max.max(dt);
+            }
+
+            self.next_deadline = Some(deadline + self.period);
+            self.cycle_start = Some(now);
+            self.last_cycle_start = Some(now);
+
+            dt
+        }
+
+        /// End the current cycle, return the statistics at the end of each publication period.
+        pub fn end_cycle(&mut self) -> Option<TaskStatsMessage> {
+
+            self.end_cycle_at(Instant::now())
+        }
+
+        pub(crate) fn end_cycle_at(&mut self, now: Instant) -> Option<TaskStatsMessage> {
+
+            let cycle_start: Instant = self.cycle_start.take()?;
+            let exec_time: Duration = now.saturating_duration_since(cycle_start);
+
+            self.window.cycles += 1;
+            self.window.exec_time_total += exec_time;
+            self.window.exec_time_max = self.window.exec_time_max.max(exec_time);
+
+            // Overrun : next deadline missed, restart from the first deadline still ahead.
+            if let Some(mut next_deadline) = self.next_deadline {
+                if now > next_deadline {
+                    self.overruns += 1;
+                    while next_deadline <= now {
+                        next_deadline += self.period;
+                    }
+                    self.next_deadline = Some(next_deadline);
+                }
+            }
+
+            match self.window.start {
+                Some(window_start) if now.duration_since(window_start) >= STATS_PERIOD => Some(self.take_stats()),
+                _ => None
+            }
+        }
+
+        /// Statistics of the current publication period, a new period is started.
+        fn take_stats(&mut self) -> TaskStatsMessage {
+
+            let window: StatsWindow = std::mem::replace(&mut self.window, StatsWindow::new());
+            let cycles: u32 = window.cycles.max(1);
+
+            TaskStatsMessage {
+                task: self.name.to_string(),
+                frequency_hz: self.frequency_hz,
+                cycles: window.cycles,
+                exec_time_avg_ms: to_ms(window.exec_time_total / cycles),
+                exec_time_max_ms: to_ms(window.exec_time_max),
+                jitter_avg_ms: to_ms(window.jitter_total / cycles),
+                jitter_max_ms: to_ms(window.jitter_max),
+                dt_min_ms: if window.dt_min <= window.dt_max { window.dt_min * 1000.0 } else { 0.0 },
+                dt_max_ms: window.dt_max * 1000.0,
+                overruns: self.overruns,
+            }
+        }
+
+        /**
+         * Run `cycle` at the task frequency until shutdown, the cycle receives the time (s) since the previous cycle.
+         * Statistics published with `stats_publisher`.
+         */
+        pub fn run<F: FnMut(f32)>(&mut self, lifecycle: &Lifecycle, stats_publisher: &Publisher, mut cycle: F) {
+
+            while lifecycle.is_running() {
+
+                let dt: f32 = self.wait_next_cycle();
+
+                cycle(dt);
+
+                if let Some(stats) = self.end_cycle() {
+                    stats_publisher.publish(BusMessage::TaskStats(stats));
+                }
+            }
+        }
+    }
+
+    impl RateScheduler {
+
+        /**
+         * Run an event driven task until shutdown : events handled as soon as received while waiting for the deadlines,
+         * cycles at the task frequency. Statistics published with `stats_publisher`.
+         */
+        pub fn run_event_driven(&mut self, lifecycle: &Lifecycle, stats_publisher: &Publisher, task: &mut dyn EventDrivenTask) {
+
+            while lifecycle.is_running() {
+
+                let dt: f32 = self.wait_next_cycle_with(|deadline| {
+                    task.handle_events_until(deadline);
+                    Instant::now()
+                });
+
+                task.step(dt);
+
+                if let Some(stats) = self.end_cycle() {
+                    stats_publisher.publish(BusMessage::TaskStats(stats));
+                }
+            }
+        }
+    }
+
+    fn to_ms(duration: Duration) -> f32 {
+
+        duration.as_secs_f32() * 1000.0
+    }
+
+    /// Task frequency of a config section : "frequency_hz", or legacy "frame_rate" (period in ms), both > 0.
+    pub fn task_frequency(config: &Yaml, default_hz: f32) -> f32 {
+
+        if let Some(frequency_hz) = get_positive_f32(&config["frequency_hz"]) {
+            return frequency_hz;
+        }
+
+        match config["frame_rate"].as_i64() {
+            Some(period_ms) if period_ms > 0 => 1000.0 / period_ms as f32,
+            _ => default_hz
+        }
+    }
+}
+
+pub use scheduler::{task_frequency, EventDrivenTask, RateScheduler};
+
+#[test]
+fn scheduler_absolute_deadlines() {
+
+    use std::time::{Duration, Instant};
+
+    let mut scheduler: RateScheduler = RateScheduler::new("TEST", 100.0);
+
+    // Simulated clock : first cycle on time, then every cycle wakes up 3 ms after its deadline.
+    let mut start: Option<Instant> = None;
+    assert_eq!(0.0, scheduler.wait_next_cycle_with(|deadline| *start.insert(deadline)));
+    let start: Instant = start.unwrap();
+    scheduler.end_cycle_at(start);
+
+    let wake_up = |deadline: Instant| deadline + Duration::from_millis(3);
+
+    let mut dt_total: f32 = 0.0;
+    for _ in 0..20 {
+        dt_total += scheduler.wait_next_cycle_with(wake_up);
+        scheduler.end_cycle_at(scheduler.next_deadline() - Duration::from_millis(5));
+    }
+
+    // 20 periods of 10 ms from the first cycle, the wake up delays are not accumulated.
+    assert_eq!(start + Duration::from_millis(210), scheduler.next_deadline());
+    assert!((dt_total - 0.203).abs() < 1e-4, "dt total {}", dt_total);
+
+    // Real clock : the cycles wait for their deadlines.
+    scheduler.wait_next_cycle();
+    assert!(Instant::now() >= start + Duration::from_millis(210));
+}
+
+#[test]
+fn scheduler_overruns_and_stats() {
+
+    use std::time::{Duration, Instant};
+    use crate::bus::TaskStatsMessage;
+
+    let mut scheduler: RateScheduler = RateScheduler::new("TEST", 10.0);
+    let t0: Instant = Instant::now();
+
+    // Cycle on time, 20 ms execution.
+    assert_eq!(0.0, scheduler.start_cycle(t0, t0));
+    assert_eq!(None, scheduler.end_cycle_at(t0 + Duration::from_millis(20)));
+    assert_eq!(t0 + Duration::from_millis(100), scheduler.next_deadline());
+
+    // Cycle started 5 ms late, running 250 ms : overrun, deadlines 200 and 300 skipped.
+    let dt: f32 = scheduler.start_cycle(t0 + Duration::from_millis(100), t0 + Duration::from_millis(105));
+    assert!((dt - 0.105).abs() < 1e-4);
+    scheduler.end_cycle_at(t0 + Duration::from_millis(355));
+    assert_eq!(t0 + Duration::from_millis(400), scheduler.next_deadline());
+
+    // Statistics published after 1 s.
+    scheduler.start_cycle(t0 + Duration::from_millis(1000), t0 + Duration::from_millis(1000));
+    let stats: TaskStatsMessage = scheduler.end_cycle_at(t0 + Duration::from_millis(1010)).unwrap();
+
+    assert_eq!("TEST", stats.task);
+    assert_eq!(3, stats.cycles);
+    assert_eq!(1, stats.overruns);
+    assert!((stats.exec_time_max_ms - 250.0).abs() < 0.01);
+    assert!((stats.exec_time_avg_ms - 280.0 / 3.0).abs() < 0.01);
+    assert!((stats.jitter_max_ms - 5.0).abs() < 0.01);
+    assert!((stats.dt_max_ms - 895.0).abs() < 0.1);
+}
+
+#[test]
+fn scheduler_task_frequency() {
+
+    let config: yaml_rust::Yaml = yaml_rust::YamlLoader::load_from_str("
+adc:
+    frame_rate: 20
+ahrs:
+    frequency_hz: 100
+ap:
+    frequency_hz: 12.5
+mavlink:
+    frequency_hz: 0
+bridge:
+    frequency_hz: -5.0
+    frame_rate: 0
+").unwrap().remove(0);
+
+    assert_eq!(50.0, task_frequency(&config["adc"], 10.0));
+    assert_eq!(100.0, task_frequency(&config["ahrs"], 10.0));
+    assert_eq!(12.5, task_frequency(&config["ap"], 10.0));
+    assert_eq!(10.0, task_frequency(&config["gui"], 10.0));
+
+    // Non positive frequency or period : default frequency.
+    assert_eq!(10.0, task_frequency(&config["mavlink"], 10.0));
+    assert_eq!(10.0, task_frequency(&config["bridge"], 10.0));
+}
+
+#[cfg(test)]
+struct TestEventTask {
+    rx: std::sync::mpsc::Receiver<std::time::Instant>,
+    latencies: Vec<std::time::Duration>,
+    steps: u32,
+    lifecycle: crate::lifecycle::Lifecycle,
+}
+
+#[cfg(test)]
+impl EventDrivenTask for TestEventTask {
+
+    fn handle_events_until(&mut self, deadline: std::time::Instant) {
+
+        while let Ok(sent_at) = self.rx.recv_timeout(deadline.saturating_duration_since(std::time::Instant::now())) {
+            self.latencies.push(sent_at.elapsed());
+        }
+
+        // Sender disconnected : wait for the deadline.
+        std::thread::sleep(deadline.saturating_duration_since(std::time::Instant::now()));
+    }
+
+    fn step(&mut self, _dt: f32) {
+
+        self.steps += 1;
+        if self.steps == 3 {
+            self.lifecycle.request_shutdown(crate::lifecycle::ShutdownReason::WindowClosed);
+        }
+    }
+}
+
+#[test]
+fn scheduler_event_driven_task() {
+
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+    use crate::bus::{Bus, Publisher, SourceId};
+    use crate::lifecycle::Lifecycle;
+
+    let lifecycle: Lifecycle = Lifecycle::new();
+    let (tx, rx) = mpsc::channel();
+    let stats_publisher: Publisher = Bus::new().publisher(SourceId::Scheduler("TEST"));
+
+    let sender = std::thread::spawn(move || {
+        for _ in 0..3 {
+            std::thread::sleep(Duration::from_millis(30));
+            let _ = tx.send(Instant::now());
+        }
+    });
+
+    // 5 Hz cycles : the events are handled as soon as received, not at the next cycle.
+    let mut task: TestEventTask = TestEventTask{ rx, latencies: Vec::new(), steps: 0, lifecycle: lifecycle.clone() };
+    RateScheduler::new("TEST", 5.0).run_event_driven(&lifecycle, &stats_publisher, &mut task);
+    sender.join().unwrap();
+
+    assert_eq!(3, task.steps);
+    assert_eq!(3, task.latencies.len());
+    // Well before the next 200 ms cycle, margin for loaded test machines.
+    assert!(task.latencies.iter().all(|latency| *latency < Duration::from_millis(100)), "latencies {:?}", task.latencies);
+}
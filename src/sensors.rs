@@ -1,8 +1,74 @@
+pub mod fault_injection;
+
 pub mod sensors {
 
+    use std::time::Duration;
+
+    use crate::bus::{GeoPosition, LinkState};
+    use crate::clock;
+
     pub trait  SensorsProvider {
 
-        fn acquire(&self) -> Result<SensorsValues,Box<dyn std::error::Error>> ;
+        /// Return immediately the freshest sample available.
+        fn acquire(&self) -> Result<SensorsSample,Box<dyn std::error::Error>> ;
+
+        /// Connection state of the underlying provider link (always receiving for local providers).
+        fn link_state(&self) -> LinkState {
+            LinkState::Receiving
+        }
+    }
+
+    /// Sensors values and time elapsed since they were received from the provider.
+    #[derive(Debug, Clone)]
+    pub struct SensorsSample {
+
+        pub values: SensorsValues,
+        pub age: Duration
+    }
+
+    impl SensorsSample {
+
+        /// Sample produced at acquisition time (local providers), all the fields are received now.
+        pub fn fresh(mut values: SensorsValues) -> SensorsSample {
+
+            values.mark_all_received(clock::now_ms());
+
+            SensorsSample { values, age: Duration::ZERO }
+        }
+    }
+
+    /// Sensors fields, index of the reception timestamps.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum SensorField {
+        Ias,
+        AltMsl,
+        AltAgl,
+        Heading,
+        Vs,
+        Aoa,
+        Mach,
+        GLoad,
+        Pitch,
+        Roll,
+        Yaw
+    }
+
+    pub const SENSOR_FIELD_COUNT: usize = 11;
+
+    /// Body angular rates (deg/s) : roll rate (P), pitch rate (Q), yaw rate (R).
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct BodyRates {
+        pub p: f32,
+        pub q: f32,
+        pub r: f32,
+    }
+
+    /// Body accelerations (g) : axial (forward), side (right), normal (up, 1 g in level flight).
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct BodyAccels {
+        pub axial: f32,
+        pub side: f32,
+        pub normal: f32,
     }
 
     #[derive(Debug, Clone)]
@@ -18,7 +84,25 @@ pub mod sensors {
         pub g_load: f32,
         pub pitch: f32,
         pub roll: f32,
-        pub yaw: f32
+        pub yaw: f32,
+
+        // Static pressure (hPa) and outside air temperature (°C), when provided by the sim.
+        pub static_pressure: Option<f32>,
+        pub oat: Option<f32>,
+
+        // Ground speed (knots) and ground track (deg true), when provided by the sim (GNSS).
+        pub ground_speed: Option<f32>,
+        pub track: Option<f32>,
+
+        // Aircraft position, when provided by the sim (GNSS).
+        pub position: Option<GeoPosition>,
+
+        // Inertial sensors (AHRS inputs), when provided by the sim.
+        pub body_rates: Option<BodyRates>,
+        pub body_accels: Option<BodyAccels>,
+
+        // Reception time (clock ms) of each field, None when never received or no data sent by the provider.
+        pub received_at: [Option<u64>; SENSOR_FIELD_COUNT]
     }
     
     impl SensorsValues {
@@ -36,7 +120,15 @@ pub mod sensors {
                 g_load: 0f32,
                 pitch: 0f32,
                 roll: 0f32,
-                yaw: 0f32
+                yaw: 0f32,
+                static_pressure: None,
+                oat: None,
+                ground_speed: None,
+                track: None,
+                position: None,
+                body_rates: None,
+                body_accels: None,
+                received_at: [None; SENSOR_FIELD_COUNT]
             };
         }
 
@@ -64,9 +156,68 @@ pub mod sensors {
                 g_load: g_load,
                 pitch: pitch,
                 roll: roll,
-                yaw: yaw
+                yaw: yaw,
+                static_pressure: None,
+                oat: None,
+                ground_speed: None,
+                track: None,
+                position: None,
+                body_rates: None,
+                body_accels: None,
+                received_at: [None; SENSOR_FIELD_COUNT]
             };
         }
+
+        /// Value of a field.
+        pub fn get(&self, field: SensorField) -> f32 {
+
+            match field {
+                SensorField::Ias => self.ias,
+                SensorField::AltMsl => self.alt_msl,
+                SensorField::AltAgl => self.alt_agl,
+                SensorField::Heading => self.heading,
+                SensorField::Vs => self.vs,
+                SensorField::Aoa => self.aoa,
+                SensorField::Mach => self.mach,
+                SensorField::GLoad => self.g_load,
+                SensorField::Pitch => self.pitch,
+                SensorField::Roll => self.roll,
+                SensorField::Yaw => self.yaw,
+            }
+        }
+
+        pub fn set(&mut self, field: SensorField, value: f32) {
+
+            match field {
+                SensorField::Ias => self.ias = value,
+                SensorField::AltMsl => self.alt_msl = value,
+                SensorField::AltAgl => self.alt_agl = value,
+                SensorField::Heading => self.heading = value,
+                SensorField::Vs => self.vs = value,
+                SensorField::Aoa => self.aoa = value,
+                SensorField::Mach => self.mach = value,
+                SensorField::GLoad => self.g_load = value,
+                SensorField::Pitch => self.pitch = value,
+                SensorField::Roll => self.roll = value,
+                SensorField::Yaw => self.yaw = value,
+            }
+        }
+
+        pub fn received_at(&self, field: SensorField) -> Option<u64> {
+
+            self.received_at[field as usize]
+        }
+
+        /// Set the reception time of a field, None when the provider sent no data for it.
+        pub fn mark_received(&mut self, field: SensorField, timestamp_ms: Option<u64>) {
+
+            self.received_at[field as usize] = timestamp_ms;
+        }
+
+        pub fn mark_all_received(&mut self, timestamp_ms: u64) {
+
+            self.received_at = [Some(timestamp_ms); SENSOR_FIELD_COUNT];
+        }
     }
 }
 
@@ -0,0 +1,581 @@
+/**
+ * Sensors fault injection
+ * Wrapper around any sensors provider altering the samples with the faults of a YAML scenario ("fault_injection" section) :
+ * - freeze : parameter held at its value when the fault starts
+ * - bias : constant offset ("value")
+ * - drift : offset growing at "value" per second
+ * - noise : uniform noise of amplitude "value"
+ * - spike : one sample offset by "value" every "period_s" seconds
+ * - drop : whole sample dropped with the probability "value" (default 1.0)
+ *
+ * Triggers ("trigger" section, none : active from start) : "time_s" after start, "altitude_ft" crossed ("below": true
+ * to trigger under the altitude) or "manual" from the GUI fault panel. The fault stops after "duration_s" when set.
+ */
+extern crate yaml_rust;
+
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use yaml_rust::Yaml;
+
+use crate::bus::LinkState;
+use crate::config::get_f32;
+use crate::sensors::SensorsProvider;
+use crate::sensors::sensors::{SensorField, SensorsSample, SensorsValues};
+
+#[derive(Debug)]
+struct FaultInjectionError(String);
+
+impl fmt::Display for FaultInjectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Fault injection: {}", self.0)
+    }
+}
+
+impl Error for FaultInjectionError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FaultKind {
+    Freeze,
+    Bias(f32),
+    Drift(f32),
+    Noise(f32),
+    Spike{ amplitude: f32, period_s: f32 },
+    Drop(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FaultTrigger {
+    Always,
+    Time{ after_s: f32 },
+    Altitude{ altitude_ft: f32, below: bool },
+    Manual,
+}
+
+/**
+ * Fault state shared with the GUI fault panel.
+ * Manual faults are started/stopped by the panel (requested), `active` reports the current state of all faults.
+ */
+pub struct FaultSwitch {
+    pub name: String,
+    pub manual: bool,
+    requested: AtomicBool,
+    active: AtomicBool,
+}
+
+impl FaultSwitch {
+
+    pub fn is_requested(&self) -> bool {
+
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    pub fn set_requested(&self, requested: bool) {
+
+        self.requested.store(requested, Ordering::SeqCst);
+    }
+
+    pub fn is_active(&self) -> bool {
+
+        self.active.load(Ordering::SeqCst)
+    }
+}
+
+/// Switches of all the injected faults (main sensors and redundant ADC channels), displayed by the GUI.
+pub struct FaultSwitches {
+    switches: Mutex<Vec<Arc<FaultSwitch>>>,
+}
+
+impl FaultSwitches {
+
+    pub fn new() -> FaultSwitches {
+
+        FaultSwitches { switches: Mutex::new(Vec::new()) }
+    }
+
+    pub fn list(&self) -> Vec<Arc<FaultSwitch>> {
+
+        self.switches.lock().unwrap().clone()
+    }
+
+    /// Switch of a fault, shared by the faults registered with the same name (same fault on several sensors copies).
+    fn register(&self, name: String, manual: bool) -> Arc<FaultSwitch> {
+
+        let mut switches: MutexGuard<Vec<Arc<FaultSwitch>>> = self.switches.lock().unwrap();
+
+        if let Some(switch) = switches.iter().find(|switch| switch.name == name && switch.manual == manual) {
+            return switch.clone();
+        }
+
+        let switch: Arc<FaultSwitch> = Arc::new(FaultSwitch {
+            name,
+            manual,
+            requested: AtomicBool::new(false),
+            active: AtomicBool::new(false),
+        });
+
+        switches.push(switch.clone());
+
+        switch
+    }
+}
+
+pub struct Fault {
+    field: Option<SensorField>,
+    kind: FaultKind,
+    trigger: FaultTrigger,
+    duration_s: Option<f32>,
+    switch: Arc<FaultSwitch>,
+    started_at: Option<f32>,
+    finished: bool,
+    frozen_value: Option<f32>,
+    last_spike: Option<i64>,
+    rng: u32,
+}
+
+impl Fault {
+
+    /// Update the fault state at `time_s` since start, return true while active.
+    fn update(&mut self, time_s: f32, s_values: &SensorsValues) -> bool {
+
+        if self.trigger == FaultTrigger::Manual {
+
+            let requested: bool = self.switch.is_requested();
+
+            if requested && self.started_at.is_none() {
+                self.start(time_s);
+            } else if !requested && self.started_at.is_some() {
+                self.stop();
+            }
+        } else if !self.finished && self.started_at.is_none() {
+
+            let triggered: bool = match self.trigger {
+                FaultTrigger::Always => true,
+                FaultTrigger::Time{ after_s } => time_s >= after_s,
+                FaultTrigger::Altitude{ altitude_ft, below } => {
+                    if below { s_values.alt_msl <= altitude_ft } else { s_values.alt_msl >= altitude_ft }
+                },
+                FaultTrigger::Manual => false,
+            };
+
+            if triggered {
+                self.start(time_s);
+            }
+        }
+
+        if let (Some(started_at), Some(duration_s)) = (self.started_at, self.duration_s) {
+            if time_s - started_at >= duration_s {
+                self.stop();
+                self.finished = self.trigger != FaultTrigger::Manual;
+            }
+        }
+
+        let active: bool = self.started_at.is_some();
+        self.switch.active.store(active, Ordering::SeqCst);
+
+        active
+    }
+
+    fn start(&mut self, time_s: f32) {
+
+        println!("[FAULT] {} started", self.switch.name);
+        self.started_at = Some(time_s);
+    }
+
+    fn stop(&mut self) {
+
+        println!("[FAULT] {} stopped", self.switch.name);
+        self.started_at = None;
+        self.frozen_value = None;
+        self.last_spike = None;
+
+        if self.trigger == FaultTrigger::Manual {
+            self.switch.set_requested(false);
+        }
+    }
+
+    /// Alter the sample, return false when the sample is dropped.
+    fn apply(&mut self, time_s: f32, s_values: &mut SensorsValues) -> bool {
+
+        let elapsed_s: f32 = time_s - self.started_at.unwrap_or(time_s);
+
+        if let FaultKind::Drop(probability) = self.kind {
+            return self.random() >= probability;
+        }
+
+        let field: SensorField = match self.field {
+            Some(field) => field,
+            None => return true
+        };
+
+        let value: f32 = s_values.get(field);
+
+        let altered_value: f32 = match self.kind {
+            FaultKind::Freeze => *self.frozen_value.get_or_insert(value),
+            FaultKind::Bias(bias) => value + bias,
+            FaultKind::Drift(rate) => value + rate * elapsed_s,
+            FaultKind::Noise(amplitude) => value + amplitude * (2.0 * self.random() - 1.0),
+            FaultKind::Spike{ amplitude, period_s } => {
+                let spike: i64 = (elapsed_s / period_s.max(0.001)) as i64;
+                if self.last_spike != Some(spike) {
+                    self.last_spike = Some(spike);
+                    value + amplitude
+                } else {
+                    value
+                }
+            },
+            FaultKind::Drop(_) => value,
+        };
+
+        let altered_value: f32 = match field {
+            SensorField::Heading | SensorField::Yaw => altered_value.rem_euclid(360.0),
+            _ => altered_value
+        };
+
+        s_values.set(field, altered_value);
+
+        true
+    }
+
+    /// Uniform random value from 0 to 1 (xorshift).
+    fn random(&mut self) -> f32 {
+
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+
+        (self.rng as f32) / (u32::MAX as f32)
+    }
+}
+
+/// Sensors provider altered by the faults of a scenario.
+pub struct FaultInjectionSensorsProvider {
+    sensors: Arc::<dyn SensorsProvider + Send + Sync>,
+    faults: Mutex<Vec<Fault>>,
+    start: Instant,
+}
+
+impl FaultInjectionSensorsProvider {
+
+    /// Sensors altered by the faults of the "fault_injection" config section ("faults" list), None without faults.
+    fn from(
+        sensors: Arc::<dyn SensorsProvider + Send + Sync>,
+        config: &Yaml,
+        switches: &FaultSwitches,
+        label: &str) -> Option<FaultInjectionSensorsProvider> {
+
+        let faults_config: &Vec<Yaml> = match config["faults"].as_vec() {
+            Some(faults_config) if !faults_config.is_empty() => faults_config,
+            _ => return None
+        };
+
+        let faults: Vec<Fault> = faults_config.iter()
+                    .enumerate()
+                    .map(|(i, fault_config)| parse_fault(fault_config, i, switches, label))
+                    .collect();
+
+        println!("Fault injection{} : {} faults", label_suffix(label), faults.len());
+
+        Some(FaultInjectionSensorsProvider {
+            sensors,
+            faults: Mutex::new(faults),
+            start: Instant::now(),
+        })
+    }
+
+    fn acquire_at(&self, time_s: f32) -> Result<SensorsSample, Box<dyn Error>> {
+
+        let mut sample: SensorsSample = self.sensors.acquire()?;
+        let mut faults: MutexGuard<Vec<Fault>> = self.faults.lock().unwrap();
+
+        // Triggers checked on the sensors values before any fault.
+        let raw_values: SensorsValues = sample.values.clone();
+        let mut dropped: bool = false;
+
+        for fault in faults.iter_mut() {
+
+            if fault.update(time_s, &raw_values) && !fault.apply(time_s, &mut sample.values) {
+                dropped = true;
+            }
+        }
+
+        if dropped {
+            return Err(Box::new(FaultInjectionError("Sample dropped".into())));
+        }
+
+        Ok(sample)
+    }
+}
+
+impl SensorsProvider for FaultInjectionSensorsProvider {
+
+    fn acquire(&self) -> Result<SensorsSample, Box<dyn Error>> {
+
+        self.acquire_at(self.start.elapsed().as_secs_f32())
+    }
+
+    fn link_state(&self) -> LinkState {
+
+        self.sensors.link_state()
+    }
+}
+
+/**
+ * Wrap the sensors with the faults of the "fault_injection" config section ("faults" list),
+ * the sensors are returned unchanged without faults. Faults names are prefixed by `label` in the GUI.
+ * Each sensors copy wrapped with the same section gets the same faults (common mode), sharing their GUI switches.
+ */
+pub fn wrap(
+    sensors: Arc::<dyn SensorsProvider + Send + Sync>,
+    config: &Yaml,
+    switches: &FaultSwitches,
+    label: &str) -> Arc::<dyn SensorsProvider + Send + Sync> {
+
+    match FaultInjectionSensorsProvider::from(sensors.clone(), config, switches, label) {
+        Some(provider) => Arc::new(provider),
+        None => sensors
+    }
+}
+
+fn label_suffix(label: &str) -> String {
+
+    if label.is_empty() { String::new() } else { format!(" ({})", label) }
+}
+
+fn parse_fault(config: &Yaml, index: usize, switches: &FaultSwitches, label: &str) -> Fault {
+
+    let kind_name: &str = config["type"].as_str().unwrap_or_else(|| panic!("Fault {} : missing type", index + 1));
+    let value: Option<f32> = get_f32(&config["value"]);
+
+    let kind: FaultKind = match kind_name {
+        "freeze" => FaultKind::Freeze,
+        "bias" => FaultKind::Bias(value.unwrap_or(0.0)),
+        "drift" => FaultKind::Drift(value.unwrap_or(0.0)),
+        "noise" => FaultKind::Noise(value.unwrap_or(0.0)),
+        "spike" => FaultKind::Spike{ amplitude: value.unwrap_or(0.0), period_s: get_f32(&config["period_s"]).unwrap_or(1.0) },
+        "drop" => FaultKind::Drop(value.unwrap_or(1.0)),
+        _ => panic!("Fault {} : unknown type {}", index + 1, kind_name)
+    };
+
+    let field: Option<SensorField> = match (&config["parameter"], kind) {
+        (_, FaultKind::Drop(_)) => None,
+        (Yaml::String(parameter), _) => match parse_field(parameter) {
+            Some(field) => Some(field),
+            None => panic!("Fault {} : unknown parameter {}", index + 1, parameter)
+        },
+        _ => panic!("Fault {} : missing parameter", index + 1)
+    };
+
+    let trigger_config: &Yaml = &config["trigger"];
+    let trigger: FaultTrigger = if trigger_config["manual"].as_bool().unwrap_or(false) {
+        FaultTrigger::Manual
+    } else if let Some(after_s) = get_f32(&trigger_config["time_s"]) {
+        FaultTrigger::Time{ after_s }
+    } else if let Some(altitude_ft) = get_f32(&trigger_config["altitude_ft"]) {
+        FaultTrigger::Altitude{ altitude_ft, below: trigger_config["below"].as_bool().unwrap_or(false) }
+    } else {
+        FaultTrigger::Always
+    };
+
+    let name: String = match config["name"].as_str() {
+        Some(name) => name.to_string(),
+        None => format!("{} {}", kind_name, config["parameter"].as_str().unwrap_or("sample"))
+    };
+    let name: String = if label.is_empty() { name } else { format!("{} : {}", label, name) };
+
+    Fault {
+        field,
+        kind,
+        trigger,
+        duration_s: get_f32(&config["duration_s"]),
+        switch: switches.register(name, trigger == FaultTrigger::Manual),
+        started_at: None,
+        finished: false,
+        frozen_value: None,
+        last_spike: None,
+        rng: 0x9E37_79B9 ^ (index as u32 + 1).wrapping_mul(0x85EB_CA6B),
+    }
+}
+
+/// Sensors field of a parameter name ("ias", "alt_msl", etc...).
+pub fn parse_field(parameter: &str) -> Option<SensorField> {
+
+    match parameter {
+        "ias" => Some(SensorField::Ias),
+        "alt_msl" => Some(SensorField::AltMsl),
+        "alt_agl" => Some(SensorField::AltAgl),
+        "heading" => Some(SensorField::Heading),
+        "vs" => Some(SensorField::Vs),
+        "aoa" => Some(SensorField::Aoa),
+        "mach" => Some(SensorField::Mach),
+        "g_load" => Some(SensorField::GLoad),
+        "pitch" => Some(SensorField::Pitch),
+        "roll" => Some(SensorField::Roll),
+        "yaw" => Some(SensorField::Yaw),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+struct TestSensorsProvider {
+    values: Mutex<SensorsValues>,
+}
+
+#[cfg(test)]
+impl SensorsProvider for TestSensorsProvider {
+
+    fn acquire(&self) -> Result<SensorsSample, Box<dyn Error>> {
+
+        Ok(SensorsSample::fresh(self.values.lock().unwrap().clone()))
+    }
+}
+
+#[cfg(test)]
+fn build_test_faults(scenario: &str, switches: &FaultSwitches) -> (Arc<TestSensorsProvider>, FaultInjectionSensorsProvider) {
+
+    let config: Yaml = yaml_rust::YamlLoader::load_from_str(scenario).unwrap().remove(0);
+
+    let mut values: SensorsValues = SensorsValues::new();
+    values.ias = 250.0;
+    values.alt_msl = 3_000.0;
+    values.pitch = 2.0;
+    values.heading = 355.0;
+
+    let test_sensors: Arc<TestSensorsProvider> = Arc::new(TestSensorsProvider{ values: Mutex::new(values) });
+
+    let provider: FaultInjectionSensorsProvider = FaultInjectionSensorsProvider::from(test_sensors.clone(), &config, switches, "").unwrap();
+
+    (test_sensors, provider)
+}
+
+#[test]
+fn fault_injection_triggers() {
+
+    let switches: FaultSwitches = FaultSwitches::new();
+    let (test_sensors, provider) = build_test_faults("
+faults:
+    - name: pitot icing
+      parameter: ias
+      type: drift
+      value: -2.0
+      trigger:
+          time_s: 10
+    - parameter: heading
+      type: bias
+      value: 10
+    - parameter: alt_msl
+      type: spike
+      value: 500
+      period_s: 2
+      trigger:
+          altitude_ft: 5000
+      duration_s: 3
+", &switches);
+
+    // Before the time trigger : heading bias only (wrapped around north).
+    let values: SensorsValues = provider.acquire_at(5.0).unwrap().values;
+    assert_eq!(250.0, values.ias);
+    assert_eq!(5.0, values.heading);
+    assert_eq!(3_000.0, values.alt_msl);
+
+    // Drift since the trigger.
+    assert_eq!(250.0, provider.acquire_at(10.0).unwrap().values.ias);
+    assert_eq!(240.0, provider.acquire_at(15.0).unwrap().values.ias);
+    assert!(switches.list()[0].is_active());
+    assert_eq!("pitot icing", switches.list()[0].name);
+    assert_eq!("bias heading", switches.list()[1].name);
+
+    // Altitude trigger : spike every 2 s during 3 s.
+    test_sensors.values.lock().unwrap().alt_msl = 5_000.0;
+    assert_eq!(5_500.0, provider.acquire_at(20.0).unwrap().values.alt_msl);
+    assert_eq!(5_000.0, provider.acquire_at(21.0).unwrap().values.alt_msl);
+    assert_eq!(5_500.0, provider.acquire_at(22.0).unwrap().values.alt_msl);
+    assert_eq!(5_000.0, provider.acquire_at(23.0).unwrap().values.alt_msl);
+    assert!(!switches.list()[2].is_active());
+
+    // Finished : not triggered again.
+    assert_eq!(5_000.0, provider.acquire_at(24.0).unwrap().values.alt_msl);
+}
+
+#[test]
+fn fault_injection_manual_freeze_and_drop() {
+
+    let switches: FaultSwitches = FaultSwitches::new();
+    let (test_sensors, provider) = build_test_faults("
+faults:
+    - name: frozen attitude
+      parameter: pitch
+      type: freeze
+      trigger:
+          manual: true
+    - type: drop
+      trigger:
+          manual: true
+", &switches);
+
+    let freeze: Arc<FaultSwitch> = switches.list()[0].clone();
+    let drop: Arc<FaultSwitch> = switches.list()[1].clone();
+    assert!(freeze.manual && drop.manual);
+
+    test_sensors.values.lock().unwrap().pitch = 3.0;
+    assert_eq!(3.0, provider.acquire_at(1.0).unwrap().values.pitch);
+
+    // Freeze requested from the GUI panel.
+    freeze.set_requested(true);
+    assert_eq!(3.0, provider.acquire_at(2.0).unwrap().values.pitch);
+    test_sensors.values.lock().unwrap().pitch = 8.0;
+    assert_eq!(3.0, provider.acquire_at(3.0).unwrap().values.pitch);
+    assert!(freeze.is_active());
+
+    freeze.set_requested(false);
+    assert_eq!(8.0, provider.acquire_at(4.0).unwrap().values.pitch);
+    assert!(!freeze.is_active());
+
+    // All samples dropped.
+    drop.set_requested(true);
+    assert!(provider.acquire_at(5.0).is_err());
+    drop.set_requested(false);
+    assert!(provider.acquire_at(6.0).is_ok());
+}
+
+#[test]
+fn fault_injection_noise() {
+
+    let switches: FaultSwitches = FaultSwitches::new();
+    let (_, provider) = build_test_faults("
+faults:
+    - parameter: ias
+      type: noise
+      value: 4.0
+", &switches);
+
+    let samples: Vec<f32> = (0..200).map(|i| provider.acquire_at(i as f32 * 0.05).unwrap().values.ias).collect();
+
+    assert!(samples.iter().all(|ias| (ias - 250.0).abs() <= 4.0));
+    assert!(samples.iter().any(|ias| *ias > 252.0) && samples.iter().any(|ias| *ias < 248.0));
+}
+
+#[test]
+fn fault_injection_common_faults() {
+
+    let switches: FaultSwitches = FaultSwitches::new();
+    let scenario: &str = "
+faults:
+    - parameter: ias
+      type: bias
+      value: -20.0
+      trigger:
+          manual: true
+";
+    let (_, provider_1) = build_test_faults(scenario, &switches);
+    let (_, provider_2) = build_test_faults(scenario, &switches);
+
+    // Same fault on both sensors copies, one switch.
+    assert_eq!(1, switches.list().len());
+
+    switches.list()[0].set_requested(true);
+    assert_eq!(230.0, provider_1.acquire_at(1.0).unwrap().values.ias);
+    assert_eq!(230.0, provider_2.acquire_at(1.0).unwrap().values.ias);
+}
@@ -0,0 +1,15 @@
+/*!
+ * Units conversions
+ * Avionics units (knots, feets, feets/min, nautical miles) to SI units and back.
+ */
+
+/// Standard gravity (m/s²).
+pub const G: f32 = 9.806_65;
+
+pub const KT_TO_MS: f32 = 0.514_444;
+pub const FT_TO_M: f32 = 0.3048;
+pub const M_TO_FT: f32 = 1.0 / FT_TO_M;
+pub const FPM_TO_MS: f32 = FT_TO_M / 60.0;
+pub const M_TO_NM: f32 = 1.0 / 1852.0;
+pub const NM_TO_FT: f32 = 1852.0 / FT_TO_M;
+pub const KT_TO_FPM: f32 = NM_TO_FT / 60.0;